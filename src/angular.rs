@@ -0,0 +1,645 @@
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{EdgeKind, Entity, EntityType, ImportInfo};
+use crate::git::ChangedFile;
+use crate::source::FileSource;
+
+/// Angular decorators whose metadata is kept on the entity.
+pub(crate) const ANGULAR_DECORATORS: &[&str] = &["Component", "Directive", "Pipe"];
+
+/// Extensions of component templates and stylesheets.
+pub(crate) const RESOURCE_EXTENSIONS: &[&str] = &["html", "css", "scss", "sass", "less"];
+
+/// What templates need to know about a component, directive or pipe.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct AngularMetadata {
+    /// `selector` of a component or directive
+    pub selector: Option<String>,
+    /// `name` of a pipe
+    pub pipe_name: Option<String>,
+    /// Usage of the inline `template`
+    pub template: Option<TemplateUsage>,
+    /// `templateUrl`, resolved against the declaring file
+    pub template_url: Option<String>,
+    /// `styleUrl` and `styleUrls`, resolved against the declaring file
+    pub style_urls: Vec<String>,
+}
+
+/// Whether a change concerns a template or stylesheet of the entity.
+pub(crate) fn touches_resource(cf: &ChangedFile, entity: &Entity) -> bool {
+    [Some(cf.path.as_str()), cf.removed_path()]
+        .into_iter()
+        .flatten()
+        .any(|path| is_resource_of(entity, path))
+}
+
+/// Whether a file is a template or stylesheet of the entity: its
+/// `templateUrl` or `styleUrl(s)`, or a file named like a component's file
+/// with a resource extension, such as `user.component.html` next to
+/// `user.component.ts`.
+fn is_resource_of(entity: &Entity, path: &str) -> bool {
+    let Some(angular) = &entity.angular else {
+        return false;
+    };
+    if angular.template_url.as_deref() == Some(path)
+        || angular.style_urls.iter().any(|url| url == path)
+    {
+        return true;
+    }
+
+    let path = Path::new(path);
+    matches!(entity.entity_type, EntityType::Component)
+        && path
+            .extension()
+            .is_some_and(|ext| RESOURCE_EXTENSIONS.iter().any(|resource| ext == *resource))
+        && path.with_extension("ts") == Path::new(&entity.file_path)
+}
+
+/// Elements and pipes a template uses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TemplateUsage {
+    pub elements: Vec<TemplateElement>,
+    pub pipes: Vec<String>,
+}
+
+/// An element of a template, as far as selectors can match it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct TemplateElement {
+    pub tag: String,
+    /// Attribute, input and output names without binding syntax, sorted
+    pub attributes: Vec<String>,
+}
+
+/// One compound selector of a comma-separated Angular selector, e.g.
+/// `button[mat-button]`. Classes and `:not()` are not matched.
+#[derive(Debug, PartialEq)]
+struct Selector {
+    tag: Option<String>,
+    attributes: Vec<String>,
+}
+
+impl Selector {
+    fn parse_list(selector: &str) -> Vec<Selector> {
+        selector
+            .split(',')
+            .filter_map(|part| Selector::parse(&strip_not(part.trim())))
+            .collect()
+    }
+
+    fn parse(selector: &str) -> Option<Selector> {
+        let bytes = selector.as_bytes();
+        let mut tag = None;
+        let mut attributes = Vec::new();
+        let mut pos = 0;
+
+        while pos < bytes.len() {
+            match bytes[pos] {
+                b'[' => {
+                    let start = pos + 1;
+                    let end = find_byte(bytes, start, b']').unwrap_or(bytes.len());
+                    let name = selector[start..end].split('=').next().unwrap_or("").trim();
+                    if !name.is_empty() {
+                        attributes.push(name.to_string());
+                    }
+                    pos = end + 1;
+                }
+                b'.' => {
+                    pos += 1;
+                    while pos < bytes.len() && is_name_byte(bytes[pos]) {
+                        pos += 1;
+                    }
+                }
+                byte if is_name_byte(byte) && pos == 0 => {
+                    while pos < bytes.len() && is_name_byte(bytes[pos]) {
+                        pos += 1;
+                    }
+                    tag = Some(selector[..pos].to_string());
+                }
+                _ => pos += 1,
+            }
+        }
+
+        (tag.is_some() || !attributes.is_empty()).then_some(Selector { tag, attributes })
+    }
+
+    fn matches(&self, element: &TemplateElement) -> bool {
+        self.tag
+            .as_ref()
+            .is_none_or(|tag| tag.eq_ignore_ascii_case(&element.tag))
+            && self
+                .attributes
+                .iter()
+                .all(|attribute| element.attributes.contains(attribute))
+    }
+}
+
+/// Adds a template edge from every component to the components, directives
+/// and pipes its template uses, and marks those as used. Templates of a
+/// `templateUrl` are read from `source`.
+pub(crate) fn link_templates(source: &FileSource, entities: &mut HashMap<String, Entity>) {
+    let mut selectors: Vec<(Selector, &Entity)> = Vec::new();
+    let mut pipes: HashMap<&str, Vec<&Entity>> = HashMap::new();
+
+    for entity in entities.values() {
+        let Some(angular) = &entity.angular else {
+            continue;
+        };
+        if let Some(selector) = &angular.selector {
+            selectors.extend(
+                Selector::parse_list(selector)
+                    .into_iter()
+                    .map(|selector| (selector, entity)),
+            );
+        }
+        if let Some(name) = &angular.pipe_name {
+            pipes.entry(name).or_default().push(entity);
+        }
+    }
+
+    if selectors.is_empty() && pipes.is_empty() {
+        return;
+    }
+
+    let links: Vec<(String, Vec<ImportInfo>)> =
+        entities
+            .par_iter()
+            .filter_map(|(id, entity)| {
+                let angular = entity.angular.as_ref()?;
+                let usage = match (&angular.template, &angular.template_url) {
+                    (Some(usage), _) => Cow::Borrowed(usage),
+                    (None, Some(url)) => match source.read_to_string(Path::new(url)) {
+                        Ok(html) => Cow::Owned(parse_template(&html)),
+                        Err(_) => return None,
+                    },
+                    (None, None) => return None,
+                };
+
+                let used =
+                    usage
+                        .elements
+                        .iter()
+                        .flat_map(|element| {
+                            selectors
+                                .iter()
+                                .filter(|(selector, _)| selector.matches(element))
+                                .map(|(_, target)| *target)
+                        })
+                        .chain(usage.pipes.iter().flat_map(|pipe| {
+                            pipes.get(pipe.as_str()).into_iter().flatten().copied()
+                        }));
+
+                let mut seen = HashSet::new();
+                let deps: Vec<ImportInfo> = used
+                    .filter(|target| target.id != *id && seen.insert(&target.id))
+                    .map(|target| ImportInfo {
+                        kind: EdgeKind::Template,
+                        ..ImportInfo::new(target.name.clone(), target.file_path.clone())
+                    })
+                    .collect();
+
+                (!deps.is_empty()).then(|| (id.clone(), deps))
+            })
+            .collect();
+
+    for (id, deps) in links {
+        for dep in &deps {
+            if let Some(target) = entities.get_mut(&dep.id) {
+                target.used = true;
+            }
+        }
+        if let Some(entity) = entities.get_mut(&id) {
+            let mut all_deps = entity.deps.as_ref().clone();
+            all_deps.extend(deps);
+            entity.deps = Arc::new(all_deps);
+        }
+    }
+}
+
+/// Collects the elements and pipes of an Angular template. Control flow
+/// blocks like `@if (user$ | async)` are covered; anything else that is
+/// not markup, interpolation or a binding is ignored.
+pub(crate) fn parse_template(html: &str) -> TemplateUsage {
+    let bytes = html.as_bytes();
+    let mut elements: Vec<TemplateElement> = Vec::new();
+    let mut pipes = BTreeSet::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos..].starts_with(b"<!--") {
+            pos = find(bytes, pos + 4, b"-->").map_or(bytes.len(), |end| end + 3);
+        } else if bytes[pos] == b'<' && bytes.get(pos + 1).is_some_and(u8::is_ascii_alphabetic) {
+            let (element, end) = parse_element(html, pos + 1, &mut pipes);
+            if !elements.contains(&element) {
+                elements.push(element);
+            }
+            pos = end;
+        } else if bytes[pos..].starts_with(b"{{") {
+            let end = find(bytes, pos + 2, b"}}").unwrap_or(bytes.len());
+            expression_pipes(&html[pos + 2..end], &mut pipes);
+            pos = end + 2;
+        } else if bytes[pos] == b'@' {
+            let mut open = pos + 1;
+            while open < bytes.len() && bytes[open].is_ascii_alphabetic() {
+                open += 1;
+            }
+            while open < bytes.len() && bytes[open].is_ascii_whitespace() {
+                open += 1;
+            }
+            if bytes.get(open) == Some(&b'(') {
+                let close = matching_paren(bytes, open);
+                expression_pipes(&html[open + 1..close], &mut pipes);
+                pos = close + 1;
+            } else {
+                pos = open;
+            }
+        } else {
+            pos += 1;
+        }
+    }
+
+    TemplateUsage {
+        elements,
+        pipes: pipes.into_iter().collect(),
+    }
+}
+
+/// Parses the tag name and attributes of an element starting at `pos`,
+/// right after the `<`. Returns the element and the position after it.
+fn parse_element(
+    html: &str,
+    mut pos: usize,
+    pipes: &mut BTreeSet<String>,
+) -> (TemplateElement, usize) {
+    let bytes = html.as_bytes();
+    let start = pos;
+    while pos < bytes.len() && is_name_byte(bytes[pos]) {
+        pos += 1;
+    }
+    let tag = html[start..pos].to_string();
+    let mut attributes = Vec::new();
+
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        match bytes.get(pos) {
+            None => break,
+            Some(b'>') => {
+                pos += 1;
+                break;
+            }
+            Some(b'/') => {
+                pos += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let name_start = pos;
+        while pos < bytes.len()
+            && !bytes[pos].is_ascii_whitespace()
+            && !matches!(bytes[pos], b'=' | b'>' | b'/')
+        {
+            pos += 1;
+        }
+        let name = &html[name_start..pos];
+        if name.is_empty() {
+            pos += 1;
+            continue;
+        }
+
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        let mut value = None;
+        if bytes.get(pos) == Some(&b'=') {
+            pos += 1;
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            match bytes.get(pos) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    let end = find_byte(bytes, pos + 1, quote).unwrap_or(bytes.len());
+                    value = Some(&html[pos + 1..end]);
+                    pos = end + 1;
+                }
+                _ => {
+                    let value_start = pos;
+                    while pos < bytes.len()
+                        && !bytes[pos].is_ascii_whitespace()
+                        && bytes[pos] != b'>'
+                    {
+                        pos += 1;
+                    }
+                    value = Some(&html[value_start..pos]);
+                }
+            }
+        }
+
+        if let Some(value) = value {
+            if is_binding(name) {
+                expression_pipes(value, pipes);
+            } else {
+                interpolation_pipes(value, pipes);
+            }
+        }
+        if let Some(name) = attribute_name(name) {
+            attributes.push(name.to_string());
+        }
+    }
+
+    attributes.sort();
+    attributes.dedup();
+    (TemplateElement { tag, attributes }, pos.min(bytes.len()))
+}
+
+fn is_binding(name: &str) -> bool {
+    name.starts_with(['[', '(', '*'])
+        || name.starts_with("bind-")
+        || name.starts_with("on-")
+        || name.starts_with("bindon-")
+}
+
+/// The name an attribute offers to selectors: `[(ngModel)]` is `ngModel`,
+/// `*ngIf` is `ngIf`, `(keyup.enter)` is `keyup`. References, animations,
+/// template variables and attribute, class and style bindings offer none.
+fn attribute_name(raw: &str) -> Option<&str> {
+    let name = raw
+        .trim_start_matches(['[', '(', '*'])
+        .trim_end_matches([']', ')']);
+    let name = ["bindon-", "bind-", "on-"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name);
+
+    let offers_none = name.is_empty()
+        || name.starts_with(['#', '@'])
+        || ["let-", "attr.", "class.", "style."]
+            .iter()
+            .any(|prefix| name.starts_with(prefix));
+    if offers_none {
+        return None;
+    }
+    name.split('.').next()
+}
+
+/// Pipes of the `{{ }}` interpolations in a text.
+fn interpolation_pipes(text: &str, pipes: &mut BTreeSet<String>) {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    while let Some(start) = find(bytes, pos, b"{{") {
+        let end = find(bytes, start + 2, b"}}").unwrap_or(bytes.len());
+        expression_pipes(&text[start + 2..end], pipes);
+        pos = end;
+    }
+}
+
+/// Pipes of a template expression: names after a `|` that is not part of
+/// `||`, outside of string literals.
+fn expression_pipes(expression: &str, pipes: &mut BTreeSet<String>) {
+    let bytes = expression.as_bytes();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            quote @ (b'"' | b'\'' | b'`') => {
+                pos = find_byte(bytes, pos + 1, quote).map_or(bytes.len(), |end| end + 1);
+            }
+            b'|' if bytes.get(pos + 1) == Some(&b'|') => pos += 2,
+            b'|' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                let start = pos;
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_')
+                {
+                    pos += 1;
+                }
+                if pos > start && !bytes[start].is_ascii_digit() {
+                    pipes.insert(expression[start..pos].to_string());
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+}
+
+/// Removes `:not(...)` parts from a selector.
+fn strip_not(selector: &str) -> Cow<'_, str> {
+    if !selector.contains(":not(") {
+        return Cow::Borrowed(selector);
+    }
+
+    let bytes = selector.as_bytes();
+    let mut stripped = String::new();
+    let mut pos = 0;
+    while let Some(start) = find(bytes, pos, b":not(") {
+        stripped.push_str(&selector[pos..start]);
+        pos = matching_paren(bytes, start + 4) + 1;
+    }
+    stripped.push_str(&selector[pos.min(selector.len())..]);
+    Cow::Owned(stripped)
+}
+
+/// Position of the parenthesis closing the one at `open`, or the end.
+fn matching_paren(bytes: &[u8], open: usize) -> usize {
+    let mut depth = 0;
+    for (pos, byte) in bytes.iter().enumerate().skip(open) {
+        match byte {
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return pos;
+                }
+            }
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+fn is_name_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b':' | b'.')
+}
+
+fn find(bytes: &[u8], from: usize, pattern: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+        .map(|offset| from + offset)
+}
+
+fn find_byte(bytes: &[u8], from: usize, byte: u8) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .iter()
+        .position(|b| *b == byte)
+        .map(|offset| from + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+
+    #[test]
+    fn test_parse_template() {
+        let usage = parse_template(
+            r#"<!-- <app-ignored></app-ignored> -->
+<app-user-card [user]="user | displayName" (selected)="pick($event)"></app-user-card>
+<button mat-button *ngIf="a || b" [(ngModel)]="x" #ref [attr.role]="role">{{ date | dateFormat:'short' }}</button>
+@if (user$ | async; as user) { <input (keyup.enter)="go()" title="{{ 'x|y' | translate }}"> }"#,
+        );
+
+        let elements: Vec<String> = usage
+            .elements
+            .iter()
+            .map(|e| format!("{} {}", e.tag, e.attributes.join(",")))
+            .collect();
+        assert_eq!(
+            elements,
+            vec![
+                "app-user-card selected,user",
+                "button mat-button,ngIf,ngModel",
+                "input keyup,title",
+            ]
+        );
+        assert_eq!(
+            usage.pipes,
+            vec!["async", "dateFormat", "displayName", "translate"]
+        );
+    }
+
+    #[test]
+    fn test_selector_matching() {
+        let element = |tag: &str, attributes: &[&str]| TemplateElement {
+            tag: tag.to_string(),
+            attributes: attributes.iter().map(|a| a.to_string()).collect(),
+        };
+        let selectors =
+            Selector::parse_list("button[mat-button]:not(.x), a[mat-button], [appHighlight]");
+
+        assert_eq!(selectors.len(), 3);
+        assert!(selectors[0].matches(&element("button", &["mat-button", "type"])));
+        assert!(!selectors[0].matches(&element("button", &["type"])));
+        assert!(!selectors[1].matches(&element("button", &["mat-button"])));
+        assert!(selectors[2].matches(&element("div", &["appHighlight"])));
+    }
+
+    #[test]
+    fn test_link_templates() {
+        let component = |name: &str, angular: AngularMetadata| {
+            let mut entity = Entity::new(
+                name.to_string(),
+                EntityType::Component,
+                format!("/src/{}.ts", name),
+                Arc::new(Vec::new()),
+            );
+            entity.angular = Some(Arc::new(angular));
+            entity
+        };
+
+        let page = component(
+            "Page",
+            AngularMetadata {
+                selector: Some("app-page".to_string()),
+                template: Some(parse_template("<app-card></app-card> {{ d | short }}")),
+                ..Default::default()
+            },
+        );
+        let card = component(
+            "Card",
+            AngularMetadata {
+                selector: Some("app-card".to_string()),
+                ..Default::default()
+            },
+        );
+        let pipe = component(
+            "ShortPipe",
+            AngularMetadata {
+                pipe_name: Some("short".to_string()),
+                ..Default::default()
+            },
+        );
+        let (page_id, card_id) = (page.id.clone(), card.id.clone());
+        let mut entities: HashMap<String, Entity> = [page, card, pipe]
+            .into_iter()
+            .map(|e| (e.id.clone(), e))
+            .collect();
+
+        link_templates(&crate::source::FILESYSTEM, &mut entities);
+
+        let deps: Vec<(&str, EdgeKind)> = entities[&page_id]
+            .deps
+            .iter()
+            .map(|d| (d.name.as_str(), d.kind))
+            .collect();
+        assert_eq!(
+            deps,
+            vec![
+                ("Card", EdgeKind::Template),
+                ("ShortPipe", EdgeKind::Template)
+            ]
+        );
+        assert!(entities[&card_id].used);
+        assert!(!entities[&page_id].used);
+    }
+
+    #[test]
+    fn test_touches_resource() {
+        use crate::git::ChangeType;
+
+        let mut entity = Entity::new(
+            "UserComponent".to_string(),
+            EntityType::Component,
+            "/src/user.component.ts".to_string(),
+            Arc::new(Vec::new()),
+        );
+        let touches = |entity: &Entity, path: &str| {
+            touches_resource(
+                &ChangedFile::new(path.to_string(), ChangeType::Modified),
+                entity,
+            )
+        };
+
+        // Conventional siblings need Angular metadata
+        assert!(!touches(&entity, "/src/user.component.html"));
+
+        entity.angular = Some(Arc::new(AngularMetadata {
+            template_url: Some("/src/templates/user.html".to_string()),
+            style_urls: vec!["/styles/user.scss".to_string()],
+            ..Default::default()
+        }));
+        for path in [
+            "/src/templates/user.html",
+            "/styles/user.scss",
+            "/src/user.component.html",
+            "/src/user.component.css",
+        ] {
+            assert!(touches(&entity, path), "{path} should be a resource");
+        }
+        for path in [
+            "/src/user.component.spec.ts",
+            "/src/user.html",
+            "/src/other.component.html",
+        ] {
+            assert!(!touches(&entity, path), "{path} should not be a resource");
+        }
+
+        let renamed = ChangedFile {
+            old_path: Some("/src/user.component.scss".to_string()),
+            ..ChangedFile::new("/src/user.scss".to_string(), ChangeType::Renamed)
+        };
+        assert!(touches_resource(&renamed, &entity));
+    }
+}
@@ -1,12 +1,5 @@
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
-#[derive(Clone, Debug, ValueEnum)]
-pub enum ProjectType {
-    Web,
-    Mobile,
-    Libs,
-}
-
 #[derive(Clone, Debug, ValueEnum, PartialEq)]
 pub enum GraphEntityType {
     Class,
@@ -22,6 +15,64 @@ pub enum GraphEntityType {
     Worker,
 }
 
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// One JSON document with a versioned schema
+    Json,
+    /// Newline-delimited JSON, one record per line
+    Ndjson,
+    /// SARIF 2.1.0 findings (unused, cycles, mem-leaks, affected-mem-leaks)
+    Sarif,
+    /// Markdown summary for pull request comments (graph-diff)
+    Markdown,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TestRunner {
+    /// `npx jest` per project, with the project's Jest config
+    Jest,
+    /// `npx vitest run` per project, with the project's Vitest or Vite config
+    Vitest,
+    /// `npx nx test` per Nx project
+    Nx,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum EdgeKind {
+    /// Static imports of values
+    Import,
+    /// `import type` and `import { type X }`
+    Type,
+    /// Lazy `import('./x').then(m => m.X)`, e.g. loadComponent
+    Lazy,
+    /// Destructured dynamic imports
+    Dynamic,
+    /// `new Worker(new URL(...))`
+    Worker,
+    /// Angular template usage of components, directives and pipes
+    Template,
+    /// Angular dependency injection
+    Di,
+}
+
+#[derive(Args, Debug)]
+pub struct EdgeFilterArgs {
+    /// Only follow dependency edges of these kinds (comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        conflicts_with = "skip_edge_kind"
+    )]
+    pub edge_kind: Vec<EdgeKind>,
+    /// Ignore dependency edges of these kinds (comma-separated, e.g. type for runtime cycles)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub skip_edge_kind: Vec<EdgeKind>,
+}
+
 #[derive(Clone, Debug, ValueEnum)]
 pub enum RankBy {
     /// Rank by number of dependencies (outgoing edges)
@@ -33,24 +84,60 @@ pub enum RankBy {
 pub struct StingArgs {
     #[command(subcommand)]
     pub command: Commands,
+    #[command(flatten)]
+    pub scan: ScanArgs,
+    /// Output format of command results
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+    /// Number of threads used to scan and parse files (default: all CPU cores)
+    #[arg(long, short = 'j', global = true)]
+    pub jobs: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// Only scan files matching these glob patterns, relative to the project path (comma-separated)
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub include: Vec<String>,
+    /// Skip files and directories matching these glob patterns, relative to the project path (comma-separated)
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub exclude: Vec<String>,
+    /// Do not honour .gitignore and .ignore files
+    #[arg(long, global = true, default_value = "false")]
+    pub no_ignore: bool,
+    /// Report every excluded file together with the rule that excluded it
+    #[arg(long, global = true, default_value = "false")]
+    pub verbose: bool,
+    /// Parse every file instead of reusing results from .sting/cache
+    #[arg(long, global = true, default_value = "false")]
+    pub no_cache: bool,
+    /// Analyze the project at this git revision (branch, tag, or commit SHA), read from its tree without checking it out
+    #[arg(long, global = true)]
+    pub rev: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Queries different types of entities in a typescript project
     QueryAll(QueryAllArgs),
-    /// Queries different types of entities in a typescript project
+    /// Searches entities by name and shows their direct dependencies and consumers
     Query(QueryArgs),
+    /// Explains an entity: its dependencies, consumers, cycles and memory leak findings
+    Explain(ExplainArgs),
     /// Lists all unused entities in the typescript project
     Unused(UnusedArgs),
     /// Outputs the dependency graph as JSON (D3.js compatible)
     Graph(GraphArgs),
     /// Lists all file paths affected by git changes compared to a base reference
     Affected(AffectedArgs),
+    /// Reports how the dependency graph changed compared to a base reference
+    GraphDiff(GraphDiffArgs),
     /// Finds the dependency chain between two entities
     Chain(ChainArgs),
     /// Detects circular dependencies in the project
     Cycles(CyclesArgs),
+    /// Prints the Angular route tree, with lazy chunks, guards and resolvers
+    Routes(RoutesArgs),
     /// Ranks entities by various metrics
     Rank(RankArgs),
     /// Detects potential memory leaks by static analysis
@@ -59,6 +146,27 @@ pub enum Commands {
     AffectedMemLeaks(AffectedMemLeaksArgs),
     /// Skill-related commands
     Skill(SkillArgs),
+    /// Parse cache commands
+    Cache(CacheArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Removes the parse cache of a typescript project
+    Clear(CacheClearArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct CacheClearArgs {
+    /// Path to the root of the typescript project
+    #[arg(default_value = ".")]
+    pub path: String,
 }
 
 #[derive(Args, Debug)]
@@ -87,8 +195,20 @@ pub struct SkillInstallArgs {
 pub struct QueryArgs {
     /// Path to the root of the typescript project
     pub path: String,
-    /// Query string to filter entities by
+    /// Entity name to search for; exact, prefix, substring and fuzzy matches are ranked in that order
     pub query: String,
+    /// Filter to specific entity types (comma-separated, e.g. class,interface)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub entity_type: Vec<GraphEntityType>,
+    /// Filter results to a project declared in sting.toml
+    #[arg(long)]
+    pub project: Option<String>,
+    /// Only match entities in files matching this glob pattern, relative to the project path
+    #[arg(long)]
+    pub file: Option<String>,
+    /// Maximum number of matches to show (default: 10)
+    #[arg(long, default_value = "10")]
+    pub limit: usize,
 }
 
 #[derive(Args, Debug)]
@@ -97,6 +217,32 @@ pub struct QueryAllArgs {
     pub path: String,
 }
 
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// Path to the root of the typescript project
+    pub path: String,
+    /// Entity name or ID, or a file path relative to the project path to explain every entity declared in it
+    pub entity: String,
+    /// Only consider entities in files matching this glob pattern, relative to the project path
+    #[arg(long)]
+    pub file: Option<String>,
+    /// Git reference to check whether the entity is affected by changes since (branch, tag, or commit SHA)
+    #[arg(long)]
+    pub base: Option<String>,
+    /// Maximum number of cycles to detect in the project (default: 100)
+    #[arg(long, default_value = "100")]
+    pub max_cycles: usize,
+    /// Maximum cycle length to detect (default: 10)
+    #[arg(long, default_value = "10")]
+    pub max_depth: usize,
+    /// Maximum number of detailed memory leak findings to show
+    #[arg(long, default_value = "5")]
+    pub max_findings: usize,
+    /// Use stricter RxJS heuristics and keep take(1) subscriptions as potential leaks
+    #[arg(long, default_value = "false")]
+    pub strict: bool,
+}
+
 #[derive(Args, Debug)]
 pub struct UnusedArgs {
     /// Path to the root of the typescript project
@@ -119,24 +265,68 @@ pub struct GraphArgs {
 pub struct AffectedArgs {
     /// Path to the root of the typescript project
     pub path: String,
-    /// Git reference to compare against (branch, tag, or commit SHA)
+    /// Git reference to compare against (branch, tag, or commit SHA), or a range: A..B compares A with B, A...B compares B with the merge-base of A and B
     #[arg(long)]
     pub base: String,
+    /// Commit to analyze instead of HEAD; files are read from its tree without checking it out
+    #[arg(long, conflicts_with_all = ["staged", "include_uncommitted"])]
+    pub head: Option<String>,
     /// Include transitive consumers (multi-hop dependency traversal)
     #[arg(long, default_value = "false")]
     pub transitive: bool,
     /// Output only unique directory paths (without filenames) for use with test runners
     #[arg(long, default_value = "false", conflicts_with = "tests")]
     pub paths: bool,
-    /// Output full paths to test files related to affected entities
+    /// Output full paths to test files that import affected entities, directly or through other code
     #[arg(long, default_value = "false", conflicts_with = "paths")]
     pub tests: bool,
+    /// With --tests or --runner, also select every test file in the directories of affected entities
+    #[arg(long, default_value = "false")]
+    pub test_dirs: bool,
+    /// Output one command per project that runs its affected tests; JSON output lists them as test targets
+    #[arg(long, value_enum, conflicts_with_all = ["paths", "summary"])]
+    pub runner: Option<TestRunner>,
+    /// Output the paths of the routes whose pages use an affected entity, so they can be re-tested
+    #[arg(long, default_value = "false", conflicts_with_all = ["paths", "tests", "runner"])]
+    pub routes: bool,
     /// Output the detailed human-readable summary instead of path-only output
     #[arg(long, default_value = "false", conflicts_with_all = ["paths", "tests"])]
     pub summary: bool,
-    /// Filter results to a specific project type (web, mobile, or libs)
-    #[arg(long, value_enum)]
-    pub project: Option<ProjectType>,
+    /// Filter results to a project declared in sting.toml (default: web, mobile, or libs)
+    #[arg(long)]
+    pub project: Option<String>,
+    /// Also include staged, unstaged and untracked changes of the working tree
+    #[arg(long, default_value = "false", conflicts_with = "staged")]
+    pub include_uncommitted: bool,
+    /// Also include changes staged in the index
+    #[arg(long, default_value = "false")]
+    pub staged: bool,
+    /// Minimum similarity in percent for a deleted and an added file to count as a rename or copy; 0 turns detection off (default: 50)
+    #[arg(long, default_value = "50", value_parser = clap::value_parser!(u16).range(0..=100))]
+    pub rename_threshold: u16,
+    /// Only count entities of modified files as changed when the diff touches their declaration
+    #[arg(long, default_value = "false")]
+    pub precise: bool,
+    #[command(flatten)]
+    pub edges: EdgeFilterArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct GraphDiffArgs {
+    /// Path to the root of the typescript project
+    pub path: String,
+    /// Git reference to compare against (branch, tag, or commit SHA), or a range: A..B compares A with B, A...B compares B with the merge-base of A and B
+    #[arg(long)]
+    pub base: String,
+    /// Commit to compare instead of the working directory; files are read from its tree without checking it out
+    #[arg(long)]
+    pub head: Option<String>,
+    /// Maximum number of cycles to detect per revision (default: 100)
+    #[arg(long, default_value = "100")]
+    pub max_cycles: usize,
+    /// Maximum cycle length to detect (default: 10)
+    #[arg(long, default_value = "10")]
+    pub max_depth: usize,
 }
 
 #[derive(Args, Debug)]
@@ -158,6 +348,8 @@ pub struct ChainArgs {
     /// Maximum path depth/length to explore (default: 10)
     #[arg(long, default_value = "10")]
     pub max_depth: usize,
+    #[command(flatten)]
+    pub edges: EdgeFilterArgs,
 }
 
 #[derive(Args, Debug)]
@@ -170,6 +362,14 @@ pub struct CyclesArgs {
     /// Maximum cycle length to detect (default: 10)
     #[arg(long, default_value = "10")]
     pub max_depth: usize,
+    #[command(flatten)]
+    pub edges: EdgeFilterArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct RoutesArgs {
+    /// Path to the root of the typescript project
+    pub path: String,
 }
 
 #[derive(Args, Debug)]
@@ -206,12 +406,15 @@ pub struct AffectedMemLeaksArgs {
     /// Git reference to compare against (branch, tag, or commit SHA)
     #[arg(long)]
     pub base: String,
+    /// Minimum similarity in percent for a deleted and an added file to count as a rename or copy; 0 turns detection off (default: 50)
+    #[arg(long, default_value = "50", value_parser = clap::value_parser!(u16).range(0..=100))]
+    pub rename_threshold: u16,
     /// Include transitive consumers (multi-hop dependency traversal)
     #[arg(long, default_value = "false")]
     pub transitive: bool,
-    /// Filter results to a specific project type (web, mobile, or libs)
-    #[arg(long, value_enum)]
-    pub project: Option<ProjectType>,
+    /// Filter results to a project declared in sting.toml (default: web, mobile, or libs)
+    #[arg(long)]
+    pub project: Option<String>,
     /// Filter to specific entity types (comma-separated, e.g. component,service)
     #[arg(long, value_enum, value_delimiter = ',')]
     pub entity_type: Vec<GraphEntityType>,
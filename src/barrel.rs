@@ -0,0 +1,368 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entity::ImportInfo;
+use crate::parser::ReExport;
+
+/// Maximum number of index files followed for a single import.
+const MAX_REEXPORT_DEPTH: usize = 32;
+
+/// Resolves imports that go through barrel files (`index.ts` re-exports)
+/// to the files that actually declare the imported entities.
+pub(crate) struct BarrelIndex<'a> {
+    declared: HashSet<(&'a str, &'a str)>,
+    reexports: HashMap<&'a str, &'a [ReExport]>,
+}
+
+impl<'a> BarrelIndex<'a> {
+    /// Builds the index from the declared entities `(file_path, name)` and
+    /// the re-exports of every parsed file.
+    pub fn new(
+        declared: impl IntoIterator<Item = (&'a str, &'a str)>,
+        reexports: impl IntoIterator<Item = (&'a str, &'a [ReExport])>,
+    ) -> Self {
+        BarrelIndex {
+            declared: declared.into_iter().collect(),
+            reexports: reexports
+                .into_iter()
+                .filter(|(_, r)| !r.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Resolves an import to the declaring entities. A namespace re-export
+    /// resolves to every entity of the re-exported module. Imports that can
+    /// not be followed are returned unchanged.
+    pub fn resolve(&self, import: &ImportInfo) -> Vec<ImportInfo> {
+        let mut visited = HashSet::new();
+        let resolved = self.resolve_name(&import.path, &import.name, &mut visited, 0);
+
+        if resolved.is_empty() {
+            return vec![import.clone()];
+        }
+
+        resolved
+            .into_iter()
+            .map(|(path, name)| {
+                if path == import.path && name == import.name {
+                    import.clone()
+                } else {
+                    ImportInfo {
+                        kind: import.kind,
+                        ..ImportInfo::new(name, path)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn resolve_all(&self, imports: &[ImportInfo]) -> Vec<ImportInfo> {
+        let mut seen = HashSet::new();
+        imports
+            .iter()
+            .flat_map(|import| self.resolve(import))
+            .filter(|import| seen.insert(import.id.clone()))
+            .collect()
+    }
+
+    fn resolve_name(
+        &self,
+        path: &str,
+        name: &str,
+        visited: &mut HashSet<(String, String)>,
+        depth: usize,
+    ) -> Vec<(String, String)> {
+        if self.declared.contains(&(path, name)) {
+            return vec![(path.to_string(), name.to_string())];
+        }
+
+        if depth >= MAX_REEXPORT_DEPTH || !visited.insert((path.to_string(), name.to_string())) {
+            return Vec::new();
+        }
+
+        let Some(reexports) = self.reexports.get(path) else {
+            return Vec::new();
+        };
+
+        // Explicit re-exports take precedence over `export *`, like in TypeScript
+        for reexport in reexports.iter() {
+            match reexport {
+                ReExport::Named {
+                    exported,
+                    imported,
+                    path: source,
+                } if exported == name => {
+                    let resolved = self.resolve_name(source, imported, visited, depth + 1);
+                    if resolved.is_empty() && imported != "default" {
+                        return vec![(source.clone(), imported.clone())];
+                    }
+                    return resolved;
+                }
+                ReExport::Namespace {
+                    exported,
+                    path: source,
+                } if exported == name => {
+                    return self.module_exports(source, &mut HashSet::new(), depth + 1);
+                }
+                _ => {}
+            }
+        }
+
+        for reexport in reexports.iter() {
+            if let ReExport::All { path: source } = reexport {
+                let resolved = self.resolve_name(source, name, visited, depth + 1);
+                if !resolved.is_empty() {
+                    return resolved;
+                }
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Collects every entity exported by a module, following its re-exports.
+    fn module_exports(
+        &self,
+        path: &str,
+        visited_modules: &mut HashSet<String>,
+        depth: usize,
+    ) -> Vec<(String, String)> {
+        if depth >= MAX_REEXPORT_DEPTH || !visited_modules.insert(path.to_string()) {
+            return Vec::new();
+        }
+
+        let mut exports: Vec<(String, String)> = self
+            .declared
+            .iter()
+            .filter(|(file, _)| *file == path)
+            .map(|(file, name)| (file.to_string(), name.to_string()))
+            .collect();
+        exports.sort();
+
+        if let Some(reexports) = self.reexports.get(path) {
+            for reexport in reexports.iter() {
+                match reexport {
+                    ReExport::Named { exported, .. } => {
+                        exports.extend(self.resolve_name(
+                            path,
+                            exported,
+                            &mut HashSet::new(),
+                            depth + 1,
+                        ));
+                    }
+                    ReExport::Namespace { path: source, .. } | ReExport::All { path: source } => {
+                        exports.extend(self.module_exports(source, visited_modules, depth + 1));
+                    }
+                }
+            }
+        }
+
+        exports
+    }
+}
+
+/// Removed module that an import of `name` from `path` reaches through
+/// the re-exports of barrel files. Such imports cannot be resolved, so
+/// they stay at the barrel and do not point at the old path.
+pub(crate) fn removed_reexport_source<'r>(
+    reexports: &'r HashMap<String, Vec<ReExport>>,
+    path: &str,
+    name: &str,
+    removed: &HashSet<&str>,
+) -> Option<&'r str> {
+    removed_source(reexports, path, name, removed, &mut HashSet::new(), 0)
+}
+
+fn removed_source<'r>(
+    reexports: &'r HashMap<String, Vec<ReExport>>,
+    path: &str,
+    name: &str,
+    removed: &HashSet<&str>,
+    visited: &mut HashSet<(String, String)>,
+    depth: usize,
+) -> Option<&'r str> {
+    if depth >= MAX_REEXPORT_DEPTH || !visited.insert((path.to_string(), name.to_string())) {
+        return None;
+    }
+
+    for reexport in reexports.get(path)? {
+        // Names re-exported from the source, if followed further
+        let (source, imported) = match reexport {
+            ReExport::Named {
+                exported,
+                imported,
+                path: source,
+            } if exported == name => (source, Some(imported.as_str())),
+            ReExport::Namespace {
+                exported,
+                path: source,
+            } if exported == name => (source, None),
+            ReExport::All { path: source } => (source, Some(name)),
+            _ => continue,
+        };
+
+        if removed.contains(source.as_str()) {
+            return Some(source);
+        }
+        if let Some(imported) = imported
+            && let Some(found) =
+                removed_source(reexports, source, imported, removed, visited, depth + 1)
+        {
+            return Some(found);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EdgeKind;
+
+    fn named(exported: &str, imported: &str, path: &str) -> ReExport {
+        ReExport::Named {
+            exported: exported.to_string(),
+            imported: imported.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    fn all(path: &str) -> ReExport {
+        ReExport::All {
+            path: path.to_string(),
+        }
+    }
+
+    fn import(name: &str, path: &str) -> ImportInfo {
+        ImportInfo::new(name.to_string(), path.to_string())
+    }
+
+    #[test]
+    fn test_declared_import_is_unchanged() {
+        let declared = [("/libs/x/src/foo.ts", "Foo")];
+        let index = BarrelIndex::new(declared, []);
+
+        let resolved = index.resolve(&import("Foo", "/libs/x/src/foo.ts"));
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].path, "/libs/x/src/foo.ts");
+    }
+
+    #[test]
+    fn test_star_reexport_chain_resolves_to_declaring_file() {
+        let declared = [("/libs/x/src/foo.service.ts", "FooService")];
+        let root_index = vec![all("/libs/x/src/index.ts")];
+        let src_index = vec![all("/libs/x/src/foo.service.ts")];
+        let index = BarrelIndex::new(
+            declared,
+            [
+                ("/libs/x/index.ts", root_index.as_slice()),
+                ("/libs/x/src/index.ts", src_index.as_slice()),
+            ],
+        );
+
+        let resolved = index.resolve(&import("FooService", "/libs/x/index.ts"));
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].name, "FooService");
+        assert_eq!(resolved[0].path, "/libs/x/src/foo.service.ts");
+    }
+
+    #[test]
+    fn test_named_reexport_with_alias() {
+        let declared = [("/libs/x/src/foo.ts", "Foo")];
+        let reexports = vec![named("Bar", "Foo", "/libs/x/src/foo.ts")];
+        let index = BarrelIndex::new(declared, [("/libs/x/index.ts", reexports.as_slice())]);
+
+        let resolved = index.resolve(&import("Bar", "/libs/x/index.ts"));
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].name, "Foo");
+        assert_eq!(resolved[0].path, "/libs/x/src/foo.ts");
+    }
+
+    #[test]
+    fn test_reexport_keeps_import_kind() {
+        let declared = [("/libs/x/src/foo.ts", "Foo")];
+        let reexports = vec![all("/libs/x/src/foo.ts")];
+        let index = BarrelIndex::new(declared, [("/libs/x/index.ts", reexports.as_slice())]);
+
+        let resolved = index.resolve(&ImportInfo {
+            kind: EdgeKind::Type,
+            ..import("Foo", "/libs/x/index.ts")
+        });
+
+        assert_eq!(resolved[0].path, "/libs/x/src/foo.ts");
+        assert_eq!(resolved[0].kind, EdgeKind::Type);
+    }
+
+    #[test]
+    fn test_namespace_reexport_resolves_to_all_module_entities() {
+        let declared = [("/libs/x/src/utils.ts", "a"), ("/libs/x/src/utils.ts", "b")];
+        let reexports = vec![ReExport::Namespace {
+            exported: "utils".to_string(),
+            path: "/libs/x/src/utils.ts".to_string(),
+        }];
+        let index = BarrelIndex::new(declared, [("/libs/x/index.ts", reexports.as_slice())]);
+
+        let resolved = index.resolve(&import("utils", "/libs/x/index.ts"));
+        let names: Vec<&str> = resolved.iter().map(|i| i.name.as_str()).collect();
+
+        assert_eq!(names, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_unresolvable_import_is_unchanged() {
+        let reexports = vec![all("/libs/x/src/other.ts")];
+        let index = BarrelIndex::new([], [("/libs/x/index.ts", reexports.as_slice())]);
+
+        let original = import("Missing", "/libs/x/index.ts");
+        let resolved = index.resolve(&original);
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].id, original.id);
+    }
+
+    #[test]
+    fn test_cyclic_star_reexports_terminate() {
+        let a = vec![all("/b.ts")];
+        let b = vec![all("/a.ts")];
+        let index = BarrelIndex::new([], [("/a.ts", a.as_slice()), ("/b.ts", b.as_slice())]);
+
+        let resolved = index.resolve(&import("Foo", "/a.ts"));
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].path, "/a.ts");
+    }
+
+    #[test]
+    fn test_removed_reexport_source_follows_barrels() {
+        let reexports = HashMap::from([
+            (
+                "/libs/x/index.ts".to_string(),
+                vec![
+                    all("/libs/x/src/index.ts"),
+                    named("Bar", "Bar", "/libs/x/bar.ts"),
+                ],
+            ),
+            (
+                "/libs/x/src/index.ts".to_string(),
+                vec![all("/libs/x/src/old.ts"), all("/libs/x/src/kept.ts")],
+            ),
+        ]);
+        let removed = HashSet::from(["/libs/x/src/old.ts"]);
+
+        assert_eq!(
+            removed_reexport_source(&reexports, "/libs/x/index.ts", "Foo", &removed),
+            Some("/libs/x/src/old.ts")
+        );
+        assert_eq!(
+            removed_reexport_source(&reexports, "/libs/x/index.ts", "Bar", &HashSet::new()),
+            None
+        );
+        assert_eq!(
+            removed_reexport_source(&reexports, "/libs/x/other.ts", "Foo", &removed),
+            None
+        );
+    }
+}
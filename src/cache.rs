@@ -0,0 +1,367 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::parser::{FileParseResult, Parser};
+
+/// Cache directory relative to the workspace root.
+pub(crate) const CACHE_DIR: &str = ".sting/cache";
+
+const CACHE_FILE_NAME: &str = "parse.json";
+
+/// Version of the cached parse results, bumped when what the parser
+/// records changes between releases.
+const CACHE_FORMAT: u32 = 10;
+
+/// Files modified this shortly before their content was hashed may be
+/// modified again without a visible mtime change, so their mtime alone
+/// is not trusted.
+const RACY_WINDOW_NANOS: u128 = 2_000_000_000;
+
+/// Parse results of one file, valid as long as its content is unchanged.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CacheEntry {
+    /// Modification time in nanoseconds since the epoch
+    mtime: u128,
+    /// SHA-256 of the file content
+    hash: String,
+    /// When the hash was last computed, in nanoseconds since the epoch
+    verified: u128,
+    result: FileParseResult,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// Sting version and parser settings the entries were produced with
+    fingerprint: String,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// Parse results persisted in `.sting/cache` between runs.
+///
+/// An entry is reused when the file's mtime is unchanged, or when its
+/// content hash is unchanged after a touch or checkout. Files modified
+/// right before they were hashed are always hashed again. Entries are
+/// discarded wholesale when the fingerprint changes: a new sting version
+/// or cache format, other decorators or tsconfig paths. Files added or
+/// removed since the last run only discard the entries of the files whose
+/// imports may resolve to them.
+pub(crate) struct ParseCache {
+    path: PathBuf,
+    fingerprint: String,
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl ParseCache {
+    /// Loads the cache of a workspace. A missing, unreadable or outdated
+    /// cache is treated as empty.
+    pub fn load(root_path: &Path, parser: &Parser, files: &[String]) -> Self {
+        let path = root_path.join(CACHE_DIR).join(CACHE_FILE_NAME);
+        let fingerprint = fingerprint(parser);
+
+        let mut entries = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+            .filter(|cache| cache.fingerprint == fingerprint)
+            .map(|cache| cache.entries)
+            .unwrap_or_default();
+
+        // The previous run cached every file it scanned
+        let scanned: HashSet<&str> = files.iter().map(String::as_str).collect();
+        let added = files
+            .iter()
+            .map(String::as_str)
+            .filter(|f| !entries.contains_key(*f));
+        let removed = entries
+            .keys()
+            .map(String::as_str)
+            .filter(|f| !scanned.contains(f));
+        let moved: HashSet<String> = added.chain(removed).flat_map(module_paths).collect();
+        if !moved.is_empty() {
+            entries.retain(|_, entry| !entry.result.modules.iter().any(|m| moved.contains(m)));
+        }
+
+        ParseCache {
+            path,
+            fingerprint,
+            entries,
+        }
+    }
+
+    /// Parses a file, reusing the cached result when the file is unchanged.
+    /// Returns the entry to store for the next run and whether it was a hit.
+    pub fn parse(&self, parser: &Parser, file_path: &str) -> Result<(CacheEntry, bool)> {
+        let mtime = modification_time(file_path)?;
+        let cached = self.entries.get(file_path);
+
+        if let Some(entry) = cached
+            && entry.mtime == mtime
+            && mtime + RACY_WINDOW_NANOS < entry.verified
+        {
+            return Ok((entry.refreshed(mtime, entry.verified), true));
+        }
+
+        let verified = now();
+        let content = fs::read_to_string(file_path)?;
+        let hash = content_hash(&content);
+
+        if let Some(entry) = cached
+            && entry.hash == hash
+        {
+            return Ok((entry.refreshed(mtime, verified), true));
+        }
+
+        let result = parser.parse_content(file_path, &content);
+        Ok((
+            CacheEntry {
+                mtime,
+                hash,
+                verified,
+                result,
+            },
+            false,
+        ))
+    }
+
+    /// Writes the entries of this run, dropping files no longer scanned.
+    pub fn save<'e>(
+        &self,
+        entries: impl IntoIterator<Item = (&'e str, &'e CacheEntry)>,
+    ) -> Result<()> {
+        #[derive(Serialize)]
+        struct CacheFileRef<'e> {
+            fingerprint: &'e str,
+            entries: HashMap<&'e str, &'e CacheEntry>,
+        }
+
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Unable to create cache directory {}", dir.display()))?;
+
+        // Keep the cache out of version control without touching the
+        // workspace .gitignore
+        let gitignore = dir.parent().unwrap_or(dir).join(".gitignore");
+        if !gitignore.exists() {
+            fs::write(&gitignore, "*\n")?;
+        }
+
+        let json = serde_json::to_vec(&CacheFileRef {
+            fingerprint: &self.fingerprint,
+            entries: entries.into_iter().collect(),
+        })?;
+
+        // Write and rename so concurrent runs never read a partial file
+        let tmp_path = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Unable to write cache {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+impl CacheEntry {
+    pub fn into_result(self) -> FileParseResult {
+        self.result
+    }
+
+    fn refreshed(&self, mtime: u128, verified: u128) -> Self {
+        CacheEntry {
+            mtime,
+            hash: self.hash.clone(),
+            verified,
+            result: self.result.clone(),
+        }
+    }
+}
+
+/// Removes the cache directory of a workspace. Returns whether there was one.
+pub(crate) fn clear(root_path: &Path) -> Result<bool> {
+    let dir = root_path.join(CACHE_DIR);
+    if !dir.exists() {
+        return Ok(false);
+    }
+
+    fs::remove_dir_all(&dir)
+        .with_context(|| format!("Unable to remove cache directory {}", dir.display()))?;
+    Ok(true)
+}
+
+fn fingerprint(parser: &Parser) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(CACHE_FORMAT.to_le_bytes());
+    hasher.update(parser.fingerprint());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Module paths an import resolving to `file` may name: the file with and
+/// without extension, and its directory for index files.
+fn module_paths(file: &str) -> Vec<String> {
+    let mut paths = vec![file.to_string()];
+    if let Some(stem) = file
+        .strip_suffix(".ts")
+        .or_else(|| file.strip_suffix(".tsx"))
+    {
+        paths.push(stem.to_string());
+        if let Some(dir) = stem.strip_suffix("/index") {
+            paths.push(dir.to_string());
+        }
+    }
+    paths
+}
+
+fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn modification_time(file_path: &str) -> Result<u128> {
+    Ok(nanos_since_epoch(fs::metadata(file_path)?.modified()?))
+}
+
+fn now() -> u128 {
+    nanos_since_epoch(SystemTime::now())
+}
+
+fn nanos_since_epoch(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StingConfig;
+    use crate::source::FILESYSTEM;
+
+    fn setup() -> (tempfile::TempDir, PathBuf, String) {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let file = root.join("a.ts");
+        fs::write(&file, "export class A {}").unwrap();
+        let file = file.to_str().unwrap().to_string();
+        (temp, root, file)
+    }
+
+    fn run(root: &Path, files: &[String]) -> Vec<(String, bool)> {
+        let parser = Parser::new(root, &StingConfig::default(), &FILESYSTEM);
+        let cache = ParseCache::load(root, &parser, files);
+
+        let parsed: Vec<(String, CacheEntry, bool)> = files
+            .iter()
+            .map(|f| {
+                let (entry, hit) = cache.parse(&parser, f).unwrap();
+                (f.clone(), entry, hit)
+            })
+            .collect();
+        cache
+            .save(parsed.iter().map(|(f, e, _)| (f.as_str(), e)))
+            .unwrap();
+
+        parsed
+            .into_iter()
+            .map(|(_, entry, hit)| {
+                let names: Vec<String> = entry
+                    .result
+                    .entities
+                    .iter()
+                    .map(|e| e.name.clone())
+                    .collect();
+                (names.join(","), hit)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unchanged_files_are_reused() {
+        let (_temp, root, file) = setup();
+        let files = vec![file];
+
+        assert_eq!(run(&root, &files), vec![("A".to_string(), false)]);
+        assert_eq!(run(&root, &files), vec![("A".to_string(), true)]);
+        assert!(root.join(".sting/.gitignore").is_file());
+    }
+
+    #[test]
+    fn test_changed_content_is_reparsed() {
+        let (_temp, root, file) = setup();
+        let files = vec![file.clone()];
+        run(&root, &files);
+
+        fs::write(&file, "export class B {}").unwrap();
+        assert_eq!(run(&root, &files), vec![("B".to_string(), false)]);
+
+        // Same content with a new mtime is still a hit
+        fs::write(&file, "export class B {}").unwrap();
+        assert_eq!(run(&root, &files), vec![("B".to_string(), true)]);
+    }
+
+    #[test]
+    fn test_added_and_removed_files_only_invalidate_their_importers() {
+        let (_temp, root, file) = setup();
+        let importer = root.join("c.ts");
+        fs::write(
+            &importer,
+            "import { X } from './x';\nexport class C { x: X }",
+        )
+        .unwrap();
+        let importer = importer.to_str().unwrap().to_string();
+        let mut files = vec![file, importer];
+        run(&root, &files);
+
+        let unrelated = root.join("b.ts");
+        fs::write(&unrelated, "export class B {}").unwrap();
+        files.insert(1, unrelated.to_str().unwrap().to_string());
+        assert_eq!(
+            run(&root, &files),
+            vec![
+                ("A".to_string(), true),
+                ("B".to_string(), false),
+                ("C".to_string(), true)
+            ]
+        );
+
+        // The import now resolves to x/index.ts
+        let imported = root.join("x/index.ts");
+        fs::create_dir_all(root.join("x")).unwrap();
+        fs::write(&imported, "export class X {}").unwrap();
+        files.push(imported.to_str().unwrap().to_string());
+        assert_eq!(
+            run(&root, &files),
+            vec![
+                ("A".to_string(), true),
+                ("B".to_string(), true),
+                ("C".to_string(), false),
+                ("X".to_string(), false)
+            ]
+        );
+
+        fs::remove_file(&imported).unwrap();
+        files.pop();
+        assert_eq!(
+            run(&root, &files),
+            vec![
+                ("A".to_string(), true),
+                ("B".to_string(), true),
+                ("C".to_string(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clear_removes_cache_directory() {
+        let (_temp, root, file) = setup();
+        run(&root, &[file]);
+
+        assert!(clear(&root).unwrap());
+        assert!(!root.join(CACHE_DIR).exists());
+        assert!(!clear(&root).unwrap());
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+use crate::entity::EntityType;
+use crate::source::FileSource;
+
+/// Config file looked up at the project root.
+pub(crate) const CONFIG_FILE_NAME: &str = "sting.toml";
+
+/// Projects scanned when no config file declares any.
+const DEFAULT_PROJECTS: &[(&str, &str)] = &[
+    ("web", "apps/web"),
+    ("mobile", "apps/mobile"),
+    ("libs", "libs"),
+];
+
+/// Angular decorators recognised without any config.
+const DEFAULT_DECORATORS: &[(&str, EntityType)] = &[
+    ("Component", EntityType::Component),
+    ("Injectable", EntityType::Service),
+    ("Directive", EntityType::Directive),
+    ("Pipe", EntityType::Pipe),
+];
+
+/// Paths skipped by the scanner unless `default_excludes = false`.
+/// Matched against paths relative to the workspace root.
+const DEFAULT_EXCLUDES: &[&str] = &[
+    "**/node_modules/**",
+    "**/mocks/**",
+    "**/__mocks__/**",
+    "**/mocks_stubs/**",
+    "**/tests/**",
+    "**/environments/**",
+    "**/i18n/**",
+    "**/.storybook/**",
+    "**/fakes/**",
+    "**/*.d.ts",
+    "**/*.stories.ts",
+    "**/*-stub.ts",
+    "**/*-test.ts",
+    "**/*-stub.service.ts",
+    "**/*mocks.ts",
+    "**/*mock.ts",
+];
+
+/// Default excludes that keep test code out of the entities. Test files
+/// below them are still scanned to find the tests of affected code.
+const TEST_EXCLUDES: &[&str] = &["**/tests/**", "**/*-test.ts"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawConfig {
+    projects: BTreeMap<String, RawProject>,
+    aliases: BTreeMap<String, AliasTargets>,
+    entities: RawEntities,
+    scan: RawScan,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProject {
+    root: String,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AliasTargets {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawEntities {
+    decorators: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawScan {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    ignore_files: bool,
+    default_excludes: bool,
+    cache: bool,
+}
+
+impl Default for RawScan {
+    fn default() -> Self {
+        RawScan {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            ignore_files: true,
+            default_excludes: true,
+            cache: true,
+        }
+    }
+}
+
+/// Where a scan pattern was declared, reported with excluded files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PatternOrigin {
+    Default,
+    Config,
+    CommandLineInclude,
+    CommandLineExclude,
+}
+
+impl fmt::Display for PatternOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternOrigin::Default => write!(f, "default"),
+            PatternOrigin::Config => write!(f, "{}", CONFIG_FILE_NAME),
+            PatternOrigin::CommandLineInclude => write!(f, "--include"),
+            PatternOrigin::CommandLineExclude => write!(f, "--exclude"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ScanPattern {
+    pub pattern: String,
+    pub origin: PatternOrigin,
+    /// Test files matched by the pattern are still scanned as tests
+    pub keeps_tests: bool,
+}
+
+impl ScanPattern {
+    fn new(pattern: &str, origin: PatternOrigin) -> Self {
+        ScanPattern {
+            pattern: pattern.to_string(),
+            origin,
+            keeps_tests: origin == PatternOrigin::Default && TEST_EXCLUDES.contains(&pattern),
+        }
+    }
+}
+
+/// Which files the scanner picks up below the project roots.
+#[derive(Debug, Clone)]
+pub(crate) struct ScanSettings {
+    /// When non-empty, only files matching one of these are scanned
+    pub include: Vec<ScanPattern>,
+    pub exclude: Vec<ScanPattern>,
+    /// Honour `.gitignore` and `.ignore` files
+    pub ignore_files: bool,
+    /// Print every excluded path with the rule that excluded it
+    pub report_excluded: bool,
+    /// Reuse parse results of unchanged files from `.sting/cache`
+    pub cache: bool,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        ScanSettings {
+            include: Vec::new(),
+            exclude: DEFAULT_EXCLUDES
+                .iter()
+                .map(|pattern| ScanPattern::new(pattern, PatternOrigin::Default))
+                .collect(),
+            ignore_files: true,
+            report_excluded: false,
+            cache: true,
+        }
+    }
+}
+
+impl ScanSettings {
+    /// Adds `--include` patterns from the command line.
+    pub fn include_from_command_line(&mut self, patterns: &[String]) {
+        self.include.extend(
+            patterns
+                .iter()
+                .map(|p| ScanPattern::new(p, PatternOrigin::CommandLineInclude)),
+        );
+    }
+
+    /// Adds `--exclude` patterns from the command line.
+    pub fn exclude_from_command_line(&mut self, patterns: &[String]) {
+        self.exclude.extend(
+            patterns
+                .iter()
+                .map(|p| ScanPattern::new(p, PatternOrigin::CommandLineExclude)),
+        );
+    }
+}
+
+/// A named source root of the workspace, e.g. an app or the shared libs.
+#[derive(Debug, Clone)]
+pub(crate) struct Project {
+    pub name: String,
+    /// Root directory relative to the workspace root
+    pub root: PathBuf,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl Project {
+    fn new(name: &str, root: &str, include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Project {
+            name: name.to_string(),
+            root: PathBuf::from(root.trim_end_matches('/')),
+            include: build_glob_set(include)
+                .with_context(|| format!("Invalid include pattern in project '{}'", name))?,
+            exclude: build_glob_set(exclude)
+                .with_context(|| format!("Invalid exclude pattern in project '{}'", name))?,
+        })
+    }
+
+    /// Whether a file belongs to the project: it is below the project root
+    /// and passes the include/exclude patterns, which are matched against
+    /// the path relative to the project root.
+    pub fn contains(&self, root_path: &Path, file_path: &str) -> bool {
+        let Ok(relative) = Path::new(file_path).strip_prefix(root_path.join(&self.root)) else {
+            return false;
+        };
+
+        if let Some(include) = &self.include
+            && !include.is_match(relative)
+        {
+            return false;
+        }
+
+        !self
+            .exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(relative))
+    }
+}
+
+/// Workspace layout and conventions from `sting.toml`, with defaults for
+/// everything the file does not declare.
+#[derive(Debug, Clone)]
+pub(crate) struct StingConfig {
+    pub projects: Vec<Project>,
+    /// Import aliases in tsconfig `paths` syntax, targets relative to the
+    /// workspace root. They take precedence over the tsconfig.
+    pub aliases: Vec<(String, Vec<String>)>,
+    /// Decorator names mapped to the entity type of the decorated class
+    pub decorators: HashMap<String, EntityType>,
+    pub scan: ScanSettings,
+}
+
+impl Default for StingConfig {
+    fn default() -> Self {
+        StingConfig {
+            projects: DEFAULT_PROJECTS
+                .iter()
+                .map(|(name, root)| Project::new(name, root, &[], &[]).unwrap())
+                .collect(),
+            aliases: Vec::new(),
+            decorators: DEFAULT_DECORATORS
+                .iter()
+                .map(|(name, entity_type)| (name.to_string(), entity_type.clone()))
+                .collect(),
+            scan: ScanSettings::default(),
+        }
+    }
+}
+
+impl StingConfig {
+    /// Loads `sting.toml` from the workspace root, falling back to the
+    /// default layout when there is none.
+    pub fn load(root_path: &Path, source: &FileSource) -> Result<Self> {
+        let config_path = root_path.join(CONFIG_FILE_NAME);
+        if !source.is_file(&config_path) {
+            return Ok(Self::default());
+        }
+
+        let content = source
+            .read_to_string(&config_path)
+            .with_context(|| format!("Unable to read {}", config_path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid config {}", config_path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let raw: RawConfig = toml::from_str(content)?;
+        let mut config = Self::default();
+
+        if !raw.projects.is_empty() {
+            config.projects = raw
+                .projects
+                .iter()
+                .map(|(name, project)| {
+                    Project::new(name, &project.root, &project.include, &project.exclude)
+                })
+                .collect::<Result<_>>()?;
+        }
+
+        config.aliases = raw
+            .aliases
+            .into_iter()
+            .map(|(pattern, targets)| {
+                let targets = match targets {
+                    AliasTargets::One(target) => vec![target],
+                    AliasTargets::Many(targets) => targets,
+                };
+                (pattern, targets)
+            })
+            .collect();
+
+        for (decorator, entity_type) in raw.entities.decorators {
+            let entity_type = entity_type
+                .parse()
+                .with_context(|| format!("Invalid entity type for decorator '{}'", decorator))?;
+            config.decorators.insert(decorator, entity_type);
+        }
+
+        if !raw.scan.default_excludes {
+            config.scan.exclude.clear();
+        }
+        config.scan.include.extend(
+            raw.scan
+                .include
+                .iter()
+                .map(|p| ScanPattern::new(p, PatternOrigin::Config)),
+        );
+        config.scan.exclude.extend(
+            raw.scan
+                .exclude
+                .iter()
+                .map(|p| ScanPattern::new(p, PatternOrigin::Config)),
+        );
+        config.scan.ignore_files = raw.scan.ignore_files;
+        config.scan.cache = raw.scan.cache;
+
+        Ok(config)
+    }
+
+    /// Looks up a project by name.
+    pub fn project(&self, name: &str) -> Result<&Project> {
+        self.projects
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.projects.iter().map(|p| p.name.as_str()).collect();
+                anyhow::anyhow!(
+                    "Unknown project '{}'. Declared projects: {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// Resolves an optional `--project` name.
+    pub fn project_filter(&self, name: Option<&str>) -> Result<Option<&Project>> {
+        name.map(|name| self.project(name)).transpose()
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_has_standard_projects() {
+        let config = StingConfig::default();
+        let names: Vec<&str> = config.projects.iter().map(|p| p.name.as_str()).collect();
+
+        assert_eq!(names, vec!["web", "mobile", "libs"]);
+        assert!(matches!(
+            config.decorators.get("Injectable"),
+            Some(EntityType::Service)
+        ));
+    }
+
+    #[test]
+    fn test_parse_full_config() {
+        let config = StingConfig::parse(
+            r#"
+[projects.admin]
+root = "apps/admin/"
+exclude = ["**/legacy/**"]
+
+[projects.shared]
+root = "packages/shared"
+include = ["src/**"]
+
+[aliases]
+"@shared/*" = "packages/shared/src/*"
+"@ui" = ["packages/ui/index", "packages/ui-legacy/index"]
+
+[entities.decorators]
+Store = "service"
+"#,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = config.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["admin", "shared"]);
+        assert_eq!(config.projects[0].root, PathBuf::from("apps/admin"));
+        assert_eq!(config.aliases.len(), 2);
+        assert_eq!(config.aliases[1].1.len(), 2);
+        assert!(matches!(
+            config.decorators.get("Store"),
+            Some(EntityType::Service)
+        ));
+        assert!(config.decorators.contains_key("Component"));
+    }
+
+    #[test]
+    fn test_project_contains_respects_globs() {
+        let config = StingConfig::parse(
+            r#"
+[projects.admin]
+root = "apps/admin"
+include = ["src/**"]
+exclude = ["**/legacy/**"]
+"#,
+        )
+        .unwrap();
+        let project = config.project("admin").unwrap();
+        let root = Path::new("/repo");
+
+        assert!(project.contains(root, "/repo/apps/admin/src/app.ts"));
+        assert!(!project.contains(root, "/repo/apps/admin/src/legacy/old.ts"));
+        assert!(!project.contains(root, "/repo/apps/admin/scripts/build.ts"));
+        assert!(!project.contains(root, "/repo/apps/admin-old/src/app.ts"));
+    }
+
+    #[test]
+    fn test_unknown_project_lists_declared_names() {
+        let config = StingConfig::default();
+        let err = config.project("desktop").unwrap_err().to_string();
+
+        assert!(err.contains("desktop"));
+        assert!(err.contains("web, mobile, libs"));
+    }
+
+    #[test]
+    fn test_parse_scan_settings() {
+        let config = StingConfig::parse(
+            r#"
+[scan]
+include = ["apps/**", "libs/**"]
+exclude = ["**/generated/**"]
+ignore_files = false
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.scan.include.len(), 2);
+        assert_eq!(config.scan.include[0].origin, PatternOrigin::Config);
+        assert_eq!(config.scan.exclude.len(), DEFAULT_EXCLUDES.len() + 1);
+        assert_eq!(
+            config.scan.exclude.last().unwrap().pattern,
+            "**/generated/**"
+        );
+        assert!(!config.scan.ignore_files);
+
+        let config = StingConfig::parse("[scan]\ndefault_excludes = false").unwrap();
+        assert!(config.scan.exclude.is_empty());
+        assert!(config.scan.ignore_files);
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        assert!(StingConfig::parse("[projects.web]\nroots = \"apps/web\"").is_err());
+        assert!(StingConfig::parse("[entities.decorators]\nStore = \"store\"").is_err());
+    }
+}
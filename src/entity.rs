@@ -1,10 +1,12 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
+use std::sync::Arc;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+use crate::angular::AngularMetadata;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum EntityType {
     Unknown,
     Class,
@@ -39,29 +41,95 @@ impl std::fmt::Display for EntityType {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl std::str::FromStr for EntityType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "class" => Ok(EntityType::Class),
+            "component" => Ok(EntityType::Component),
+            "service" => Ok(EntityType::Service),
+            "directive" => Ok(EntityType::Directive),
+            "pipe" => Ok(EntityType::Pipe),
+            "enum" => Ok(EntityType::Enum),
+            "type" => Ok(EntityType::Type),
+            "interface" => Ok(EntityType::Interface),
+            "function" => Ok(EntityType::Function),
+            "const" => Ok(EntityType::Const),
+            "worker" => Ok(EntityType::Worker),
+            _ => anyhow::bail!("Unknown entity type '{}'", s),
+        }
+    }
+}
+
+/// How an entity comes to depend on another.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeKind {
+    /// A static import of a value
+    #[default]
+    Import,
+    /// `import type { X }` or `import { type X }`, erased at runtime
+    Type,
+    /// A lazy `import('./x').then(m => m.X)`, e.g. in `loadComponent`
+    Lazy,
+    /// A destructured dynamic import, `const { X } = await import('./x')`
+    Dynamic,
+    /// `new Worker(new URL('./x.worker', import.meta.url))`
+    Worker,
+    /// An Angular template uses a component, directive or pipe
+    Template,
+    /// Angular dependency injection: constructor parameters, `inject()`
+    /// and providers
+    Di,
+}
+
+impl std::fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EdgeKind::Import => write!(f, "import"),
+            EdgeKind::Type => write!(f, "type"),
+            EdgeKind::Lazy => write!(f, "lazy"),
+            EdgeKind::Dynamic => write!(f, "dynamic"),
+            EdgeKind::Worker => write!(f, "worker"),
+            EdgeKind::Template => write!(f, "template"),
+            EdgeKind::Di => write!(f, "di"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ImportInfo {
     pub id: String,
     pub name: String,
     pub path: String,
+    pub kind: EdgeKind,
 }
 
 impl ImportInfo {
     pub fn new(name: String, path: String) -> Self {
         let id = generate_entity_id(&path, &name);
-        ImportInfo { id, name, path }
+        ImportInfo {
+            id,
+            name,
+            path,
+            kind: EdgeKind::Import,
+        }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Entity {
     pub id: String,
     pub name: String,
     pub entity_type: EntityType,
     pub file_path: String,
-    #[serde(skip)]
-    pub deps: Rc<Vec<ImportInfo>>,
+    pub deps: Arc<Vec<ImportInfo>>,
     pub used: bool,
+    /// Decorator metadata of Angular components, directives and pipes
+    pub angular: Option<Arc<AngularMetadata>>,
 }
 
 impl Entity {
@@ -69,7 +137,7 @@ impl Entity {
         name: String,
         entity_type: EntityType,
         file_path: String,
-        deps: Rc<Vec<ImportInfo>>,
+        deps: Arc<Vec<ImportInfo>>,
     ) -> Self {
         let id = generate_entity_id(&file_path, &name);
         Entity {
@@ -79,6 +147,7 @@ impl Entity {
             file_path,
             deps,
             used: false,
+            angular: None,
         }
     }
 }
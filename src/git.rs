@@ -1,8 +1,12 @@
+use std::collections::HashMap;
 use std::fmt;
+use std::ops::Range;
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use git2::{Delta, DiffOptions, Repository};
+use git2::{Commit, Delta, Diff, DiffFindOptions, DiffOptions, Repository};
+
+use crate::angular::RESOURCE_EXTENSIONS;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ChangeType {
@@ -10,6 +14,7 @@ pub enum ChangeType {
     Modified,
     Deleted,
     Renamed,
+    Copied,
 }
 
 impl fmt::Display for ChangeType {
@@ -19,88 +24,280 @@ impl fmt::Display for ChangeType {
             ChangeType::Modified => write!(f, "M"),
             ChangeType::Deleted => write!(f, "D"),
             ChangeType::Renamed => write!(f, "R"),
+            ChangeType::Copied => write!(f, "C"),
         }
     }
 }
 
+/// Similarity in percent above which a deleted and an added file count
+/// as a rename, like `git diff -M`.
+pub const DEFAULT_RENAME_THRESHOLD: u16 = 50;
+
 #[derive(Debug, Clone)]
 pub struct ChangedFile {
     pub path: String,
+    /// Path before a rename, or the origin of a copy
+    pub old_path: Option<String>,
     #[allow(dead_code)]
     pub change_type: ChangeType,
 }
 
 impl ChangedFile {
     pub fn new(path: String, change_type: ChangeType) -> Self {
-        Self { path, change_type }
+        Self {
+            path,
+            old_path: None,
+            change_type,
+        }
+    }
+
+    /// Path the file no longer exists at: the old path of a rename or the
+    /// path of a deleted file. Consumers may still import it.
+    pub fn removed_path(&self) -> Option<&str> {
+        match self.change_type {
+            ChangeType::Renamed => self.old_path.as_deref(),
+            ChangeType::Deleted => Some(&self.path),
+            _ => None,
+        }
+    }
+
+    /// Whether the change concerns a file at `path`, before or after it.
+    pub fn touches(&self, path: &str) -> bool {
+        self.path == path || self.removed_path() == Some(path)
     }
 }
 
-pub fn get_changed_files(repo_path: &Path, base_ref: &str) -> Result<Vec<ChangedFile>> {
-    let repo = Repository::discover(repo_path).with_context(|| {
-        format!(
-            "Failed to find git repository at or above '{}'",
-            repo_path.display()
-        )
-    })?;
+/// Which changes on top of the merge-base count as changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChangeScope {
+    /// Commits up to HEAD
+    #[default]
+    Committed,
+    /// Commits and changes staged in the index
+    Staged,
+    /// Commits, staged and unstaged changes, and untracked `.ts` files and
+    /// component templates and stylesheets
+    Uncommitted,
+}
 
-    let repo_root = repo
-        .workdir()
-        .ok_or_else(|| anyhow::anyhow!("Repository has no working directory (bare repository)"))?;
+/// The commits whose changes are compared: what changed on `head` since
+/// `base`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitRange {
+    pub base: String,
+    /// Explicit head commit, None for HEAD
+    pub head: Option<String>,
+    /// Compare `head` with the merge-base of `base` and `head` rather than
+    /// with `base` itself
+    pub merge_base: bool,
+}
 
-    // Resolve the base reference to a commit
-    let base_obj = repo.revparse_single(base_ref).with_context(|| {
-        format!(
-            "Could not resolve git reference '{}'. Ensure it exists.",
-            base_ref
-        )
-    })?;
+impl CommitRange {
+    /// Changes on HEAD since it diverged from `base`.
+    pub fn new(base: &str) -> Self {
+        CommitRange {
+            base: base.to_string(),
+            head: None,
+            merge_base: true,
+        }
+    }
 
-    let base_commit = base_obj
-        .peel_to_commit()
-        .with_context(|| format!("Reference '{}' does not point to a commit", base_ref))?;
+    /// Parses a base reference and an optional head. Like `git diff`,
+    /// `A..B` compares A with B directly and `A...B` compares B with the
+    /// merge-base of A and B; an empty side means HEAD. A plain base keeps
+    /// the merge-base semantics.
+    pub fn parse(base: &str, head: Option<&str>) -> Result<Self> {
+        let (range, merge_base) = match base.split_once("...") {
+            Some(range) => (Some(range), true),
+            None => (base.split_once(".."), false),
+        };
 
-    let head_ref = repo
-        .head()
-        .with_context(|| "Failed to get HEAD reference")?;
-    let head_commit = head_ref
-        .peel_to_commit()
-        .with_context(|| "HEAD does not point to a commit")?;
+        let Some((from, to)) = range else {
+            return Ok(CommitRange {
+                base: base.to_string(),
+                head: head.map(str::to_string),
+                merge_base: true,
+            });
+        };
 
-    let head_tree = head_commit
-        .tree()
-        .with_context(|| "Failed to get tree from HEAD commit")?;
-
-    // Find the merge-base (common ancestor) between HEAD and base
-    // This ensures we only get files changed in the current branch,
-    // regardless of whether the local base branch is up-to-date
-    let merge_base_oid = repo
-        .merge_base(head_commit.id(), base_commit.id())
-        .with_context(|| {
-            format!(
-                "Could not find merge-base between HEAD and '{}'. Ensure the branches share common history.",
-                base_ref
-            )
-        })?;
+        if head.is_some() {
+            anyhow::bail!(
+                "--head cannot be combined with the range '{}'; use '{}..<head>' instead",
+                base,
+                from
+            );
+        }
+
+        let side = |rev: &str| {
+            if rev.is_empty() {
+                "HEAD".to_string()
+            } else {
+                rev.to_string()
+            }
+        };
+
+        Ok(CommitRange {
+            base: side(from),
+            head: (!to.is_empty()).then(|| to.to_string()),
+            merge_base,
+        })
+    }
+
+    /// Name of the head commit, for messages.
+    pub fn head_name(&self) -> &str {
+        self.head.as_deref().unwrap_or("HEAD")
+    }
 
-    let merge_base_commit = repo
-        .find_commit(merge_base_oid)
-        .with_context(|| "Failed to find merge-base commit")?;
+    /// Id of the commit the head is compared with: the merge-base, or the
+    /// base itself for a two-dot range.
+    pub fn base_commit_id(&self, repo_path: &Path) -> Result<String> {
+        let repo = open_repository(repo_path)?;
+        let (base_commit, _) = self.resolve(&repo)?;
+        Ok(base_commit.id().to_string())
+    }
+
+    /// Resolves the commit the head is compared with and the head commit.
+    fn resolve<'r>(&self, repo: &'r Repository) -> Result<(Commit<'r>, Commit<'r>)> {
+        let resolve = |rev: &str| {
+            repo.revparse_single(rev)
+                .with_context(|| {
+                    format!(
+                        "Could not resolve git reference '{}'. Ensure it exists.",
+                        rev
+                    )
+                })?
+                .peel_to_commit()
+                .with_context(|| format!("Reference '{}' does not point to a commit", rev))
+        };
 
-    let merge_base_tree = merge_base_commit
+        let base_commit = resolve(&self.base)?;
+        let head_commit = match &self.head {
+            Some(head) => resolve(head)?,
+            None => repo
+                .head()
+                .with_context(|| "Failed to get HEAD reference")?
+                .peel_to_commit()
+                .with_context(|| "HEAD does not point to a commit")?,
+        };
+
+        if !self.merge_base {
+            return Ok((base_commit, head_commit));
+        }
+
+        // Find the merge-base (common ancestor) between head and base
+        // This ensures we only get files changed in the current branch,
+        // regardless of whether the local base branch is up-to-date
+        let merge_base_oid = repo
+            .merge_base(head_commit.id(), base_commit.id())
+            .with_context(|| {
+                format!(
+                    "Could not find merge-base between {} and '{}'. Ensure the branches share common history.",
+                    self.head_name(),
+                    self.base
+                )
+            })?;
+        let merge_base = repo
+            .find_commit(merge_base_oid)
+            .with_context(|| "Failed to find merge-base commit")?;
+
+        Ok((merge_base, head_commit))
+    }
+
+    /// What the base is compared with, for messages.
+    pub fn target(&self, scope: ChangeScope) -> &str {
+        match scope {
+            ChangeScope::Committed => self.head_name(),
+            ChangeScope::Staged => "the index",
+            ChangeScope::Uncommitted => "the working tree",
+        }
+    }
+}
+
+fn workdir(repo: &Repository) -> Result<&Path> {
+    repo.workdir()
+        .ok_or_else(|| anyhow::anyhow!("Repository has no working directory (bare repository)"))
+}
+
+/// Diff between the base of `range` and its head, the index or the
+/// working tree, depending on `scope`.
+fn diff_range<'r>(
+    repo: &'r Repository,
+    range: &CommitRange,
+    scope: ChangeScope,
+    diff_opts: &mut DiffOptions,
+) -> Result<Diff<'r>> {
+    if scope != ChangeScope::Committed && range.head.is_some() {
+        anyhow::bail!(
+            "Staged and uncommitted changes can only be compared on top of HEAD, not '{}'",
+            range.head_name()
+        );
+    }
+
+    let (base_commit, head_commit) = range.resolve(repo)?;
+
+    let head_tree = head_commit
         .tree()
-        .with_context(|| "Failed to get tree from merge-base commit")?;
+        .with_context(|| format!("Failed to get tree from {}", range.head_name()))?;
+    let base_tree = base_commit
+        .tree()
+        .with_context(|| format!("Failed to get tree from '{}'", range.base))?;
 
-    let mut diff_opts = DiffOptions::new();
-    diff_opts.include_untracked(false);
+    match scope {
+        ChangeScope::Committed => {
+            repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), Some(diff_opts))
+        }
+        ChangeScope::Staged => repo.diff_tree_to_index(Some(&base_tree), None, Some(diff_opts)),
+        ChangeScope::Uncommitted => {
+            repo.diff_tree_to_workdir_with_index(Some(&base_tree), Some(diff_opts))
+        }
+    }
+    .with_context(|| {
+        format!(
+            "Failed to compute diff between '{}' and {}",
+            range.base,
+            range.target(scope)
+        )
+    })
+}
 
-    let diff = repo
-        .diff_tree_to_tree(
-            Some(&merge_base_tree),
-            Some(&head_tree),
-            Some(&mut diff_opts),
+fn open_repository(repo_path: &Path) -> Result<Repository> {
+    Repository::discover(repo_path).with_context(|| {
+        format!(
+            "Failed to find git repository at or above '{}'",
+            repo_path.display()
         )
-        .with_context(|| "Failed to compute diff between merge-base and HEAD")?;
+    })
+}
+
+/// Files changed on the head of `range`. Renames and copies are detected
+/// when the files are at least `rename_threshold` percent similar; 0
+/// turns detection off.
+pub fn get_changed_files(
+    repo_path: &Path,
+    range: &CommitRange,
+    scope: ChangeScope,
+    rename_threshold: u16,
+) -> Result<Vec<ChangedFile>> {
+    let repo = open_repository(repo_path)?;
+    let repo_root = workdir(&repo)?;
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.include_untracked(scope == ChangeScope::Uncommitted);
+    diff_opts.recurse_untracked_dirs(scope == ChangeScope::Uncommitted);
+
+    let mut diff = diff_range(&repo, range, scope, &mut diff_opts)?;
+
+    if rename_threshold > 0 {
+        let mut find_opts = DiffFindOptions::new();
+        find_opts
+            .renames(true)
+            .copies(true)
+            .for_untracked(scope == ChangeScope::Uncommitted)
+            .rename_threshold(rename_threshold)
+            .copy_threshold(rename_threshold);
+        diff.find_similar(Some(&mut find_opts))
+            .with_context(|| "Failed to detect renamed files")?;
+    }
 
     let mut changed_files = Vec::new();
 
@@ -111,7 +308,18 @@ pub fn get_changed_files(repo_path: &Path, base_ref: &str) -> Result<Vec<Changed
                 Delta::Deleted => ChangeType::Deleted,
                 Delta::Modified => ChangeType::Modified,
                 Delta::Renamed => ChangeType::Renamed,
-                Delta::Copied => ChangeType::Added,
+                Delta::Copied => ChangeType::Copied,
+                // Only untracked sources, templates and stylesheets matter,
+                // not build output or notes
+                Delta::Untracked
+                    if delta.new_file().path().is_some_and(|p| {
+                        p.extension().is_some_and(|ext| {
+                            ext == "ts" || RESOURCE_EXTENSIONS.iter().any(|r| ext == *r)
+                        })
+                    }) =>
+                {
+                    ChangeType::Added
+                }
                 _ => return true, // Skip other types
             };
 
@@ -121,10 +329,17 @@ pub fn get_changed_files(repo_path: &Path, base_ref: &str) -> Result<Vec<Changed
                 delta.new_file().path()
             };
 
+            let old_path = match change_type {
+                ChangeType::Renamed | ChangeType::Copied => delta.old_file().path(),
+                _ => None,
+            };
+
             if let Some(path) = file_path {
-                let absolute_path = repo_root.join(path);
-                let path_str = absolute_path.to_string_lossy().to_string();
-                changed_files.push(ChangedFile::new(path_str, change_type));
+                let absolute = |path: &Path| repo_root.join(path).to_string_lossy().to_string();
+                changed_files.push(ChangedFile {
+                    old_path: old_path.map(absolute),
+                    ..ChangedFile::new(absolute(path), change_type)
+                });
             }
 
             true
@@ -138,6 +353,52 @@ pub fn get_changed_files(repo_path: &Path, base_ref: &str) -> Result<Vec<Changed
     Ok(changed_files)
 }
 
+/// Changed lines of the files modified on the head of `range`, by path.
+/// Lines are 1-based ranges on the head side; an empty range `n..n` marks
+/// lines removed right before line `n`. Files without text changes have
+/// no entry.
+pub fn get_changed_lines(
+    repo_path: &Path,
+    range: &CommitRange,
+    scope: ChangeScope,
+) -> Result<HashMap<String, Vec<Range<usize>>>> {
+    let repo = open_repository(repo_path)?;
+    let repo_root = workdir(&repo)?;
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.context_lines(0);
+
+    let diff = diff_range(&repo, range, scope, &mut diff_opts)?;
+
+    let mut changed_lines: HashMap<String, Vec<Range<usize>>> = HashMap::new();
+
+    diff.foreach(
+        &mut |_, _| true,
+        None,
+        Some(&mut |delta, hunk| {
+            if delta.status() == Delta::Modified
+                && let Some(path) = delta.new_file().path()
+            {
+                let start = hunk.new_start() as usize;
+                let lines = match hunk.new_lines() as usize {
+                    // Without new lines, `start` is the line before the removal
+                    0 => start + 1..start + 1,
+                    count => start..start + count,
+                };
+                changed_lines
+                    .entry(repo_root.join(path).to_string_lossy().to_string())
+                    .or_default()
+                    .push(lines);
+            }
+            true
+        }),
+        None,
+    )
+    .with_context(|| "Failed to iterate over diff")?;
+
+    Ok(changed_lines)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,6 +427,7 @@ mod tests {
         assert_eq!(format!("{}", ChangeType::Modified), "M");
         assert_eq!(format!("{}", ChangeType::Deleted), "D");
         assert_eq!(format!("{}", ChangeType::Renamed), "R");
+        assert_eq!(format!("{}", ChangeType::Copied), "C");
     }
 
     #[test]
@@ -245,7 +507,13 @@ mod tests {
             .unwrap();
 
         // Get changed files comparing feature branch to main
-        let changed = get_changed_files(temp.path(), "main").unwrap();
+        let changed = get_changed_files(
+            temp.path(),
+            &CommitRange::new("main"),
+            ChangeScope::Committed,
+            DEFAULT_RENAME_THRESHOLD,
+        )
+        .unwrap();
 
         // Extract just the filenames for easier assertion
         let changed_names: Vec<&str> = changed
@@ -310,7 +578,13 @@ mod tests {
         fs::write(temp.path().join("file_c.txt"), "content c").unwrap();
         create_commit(&repo, "Commit C", Some(&commit_b));
 
-        let changed = get_changed_files(temp.path(), "v1.0").unwrap();
+        let changed = get_changed_files(
+            temp.path(),
+            &CommitRange::new("v1.0"),
+            ChangeScope::Committed,
+            DEFAULT_RENAME_THRESHOLD,
+        )
+        .unwrap();
 
         let changed_names: Vec<&str> = changed
             .iter()
@@ -361,7 +635,13 @@ mod tests {
         repo.commit(Some("HEAD"), &sig, &sig, "Changes", &tree, &[&commit_a])
             .unwrap();
 
-        let changed = get_changed_files(temp.path(), "base").unwrap();
+        let changed = get_changed_files(
+            temp.path(),
+            &CommitRange::new("base"),
+            ChangeScope::Committed,
+            DEFAULT_RENAME_THRESHOLD,
+        )
+        .unwrap();
 
         let find_change = |name: &str| -> Option<&ChangedFile> {
             changed.iter().find(|cf| cf.path.ends_with(name))
@@ -381,4 +661,250 @@ mod tests {
         );
         assert_eq!(changed.len(), 3);
     }
+
+    #[test]
+    fn test_get_changed_files_detects_renames() {
+        let temp = tempdir().unwrap();
+        let repo = Repository::init(temp.path()).unwrap();
+        let content = "export class UserService {\n  load() {\n    return 1;\n  }\n}\n";
+
+        fs::write(temp.path().join("user.ts"), content).unwrap();
+        let commit_a_oid = create_commit(&repo, "Initial", None);
+        let commit_a = repo.find_commit(commit_a_oid).unwrap();
+        repo.tag_lightweight("base", commit_a.as_object(), false)
+            .unwrap();
+
+        fs::rename(
+            temp.path().join("user.ts"),
+            temp.path().join("user.service.ts"),
+        )
+        .unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("user.ts")).unwrap();
+        index.write().unwrap();
+        create_commit(&repo, "Rename", Some(&commit_a));
+
+        let range = CommitRange::new("base");
+
+        let changed = get_changed_files(
+            temp.path(),
+            &range,
+            ChangeScope::Committed,
+            DEFAULT_RENAME_THRESHOLD,
+        )
+        .unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].change_type, ChangeType::Renamed);
+        assert!(changed[0].path.ends_with("user.service.ts"));
+        let old_path = changed[0].old_path.as_deref().unwrap();
+        assert!(old_path.ends_with("user.ts"));
+        assert_eq!(changed[0].removed_path(), Some(old_path));
+        assert!(changed[0].touches(old_path));
+
+        let changed = get_changed_files(temp.path(), &range, ChangeScope::Committed, 0).unwrap();
+        let mut types: Vec<String> = changed
+            .iter()
+            .map(|cf| cf.change_type.to_string())
+            .collect();
+        types.sort();
+        assert_eq!(types, vec!["A", "D"]);
+    }
+
+    #[test]
+    fn test_get_changed_lines() {
+        let temp = tempdir().unwrap();
+        let repo = Repository::init(temp.path()).unwrap();
+
+        fs::write(temp.path().join("models.ts"), "a\nb\nc\nd\ne\n").unwrap();
+        fs::write(temp.path().join("other.ts"), "x\n").unwrap();
+        let commit_a_oid = create_commit(&repo, "Initial", None);
+        let commit_a = repo.find_commit(commit_a_oid).unwrap();
+        repo.tag_lightweight("base", commit_a.as_object(), false)
+            .unwrap();
+
+        // Change b, remove d, add two lines after e
+        fs::write(temp.path().join("models.ts"), "a\nB\nc\ne\nf\ng\n").unwrap();
+        fs::write(temp.path().join("added.ts"), "y\n").unwrap();
+        create_commit(&repo, "Changes", Some(&commit_a));
+
+        let changed_lines = get_changed_lines(
+            temp.path(),
+            &CommitRange::new("base"),
+            ChangeScope::Committed,
+        )
+        .unwrap();
+
+        assert_eq!(changed_lines.len(), 1);
+        let (path, lines) = changed_lines.iter().next().unwrap();
+        assert!(path.ends_with("models.ts"));
+        assert_eq!(lines, &vec![2..3, 4..4, 5..7]);
+    }
+
+    #[test]
+    fn test_get_changed_files_local_changes() {
+        let temp = tempdir().unwrap();
+        let repo = Repository::init(temp.path()).unwrap();
+
+        repo.config().unwrap().set_str("user.name", "Test").unwrap();
+        repo.config()
+            .unwrap()
+            .set_str("user.email", "test@test.com")
+            .unwrap();
+
+        fs::write(temp.path().join("committed.ts"), "a").unwrap();
+        fs::write(temp.path().join("staged.ts"), "a").unwrap();
+        fs::write(temp.path().join("unstaged.ts"), "a").unwrap();
+        let commit_a_oid = create_commit(&repo, "Initial", None);
+        let commit_a = repo.find_commit(commit_a_oid).unwrap();
+        repo.tag_lightweight("base", commit_a.as_object(), false)
+            .unwrap();
+
+        fs::write(temp.path().join("committed.ts"), "b").unwrap();
+        create_commit(&repo, "Committed change", Some(&commit_a));
+
+        // Staged modification
+        fs::write(temp.path().join("staged.ts"), "b").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged.ts")).unwrap();
+        index.write().unwrap();
+
+        // Unstaged modification and untracked files
+        fs::write(temp.path().join("unstaged.ts"), "b").unwrap();
+        fs::create_dir(temp.path().join("new")).unwrap();
+        fs::write(temp.path().join("new/untracked.ts"), "b").unwrap();
+        fs::write(temp.path().join("notes.txt"), "b").unwrap();
+
+        let names = |scope: ChangeScope| -> Vec<String> {
+            let mut names: Vec<String> = get_changed_files(
+                temp.path(),
+                &CommitRange::new("base"),
+                scope,
+                DEFAULT_RENAME_THRESHOLD,
+            )
+            .unwrap()
+            .iter()
+            .map(|cf| {
+                Path::new(&cf.path)
+                    .strip_prefix(temp.path().canonicalize().unwrap())
+                    .or_else(|_| Path::new(&cf.path).strip_prefix(temp.path()))
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(ChangeScope::Committed), vec!["committed.ts"]);
+        assert_eq!(
+            names(ChangeScope::Staged),
+            vec!["committed.ts", "staged.ts"]
+        );
+        assert_eq!(
+            names(ChangeScope::Uncommitted),
+            vec![
+                "committed.ts",
+                "new/untracked.ts",
+                "staged.ts",
+                "unstaged.ts"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commit_range_parse() {
+        let range = CommitRange::parse("main", None).unwrap();
+        assert_eq!(range, CommitRange::new("main"));
+
+        let range = CommitRange::parse("main", Some("abc123")).unwrap();
+        assert_eq!(range.head.as_deref(), Some("abc123"));
+        assert!(range.merge_base);
+
+        let range = CommitRange::parse("v1.0..v1.1", None).unwrap();
+        assert_eq!(range.base, "v1.0");
+        assert_eq!(range.head.as_deref(), Some("v1.1"));
+        assert!(!range.merge_base);
+
+        let range = CommitRange::parse("main...feature", None).unwrap();
+        assert_eq!(range.base, "main");
+        assert_eq!(range.head.as_deref(), Some("feature"));
+        assert!(range.merge_base);
+
+        let range = CommitRange::parse("main..", None).unwrap();
+        assert_eq!(range.head, None);
+        assert_eq!(CommitRange::parse("..main", None).unwrap().base, "HEAD");
+
+        assert!(CommitRange::parse("main..feature", Some("other")).is_err());
+    }
+
+    #[test]
+    fn test_get_changed_files_ranges() {
+        // HEAD:    A --- B
+        //           \
+        // feature:   D
+        // feature is never checked out, only named in the range
+        let temp = tempdir().unwrap();
+        let repo = Repository::init(temp.path()).unwrap();
+
+        fs::write(temp.path().join("base.ts"), "a").unwrap();
+        let commit_a_oid = create_commit(&repo, "A", None);
+        let commit_a = repo.find_commit(commit_a_oid).unwrap();
+        repo.branch("feature", &commit_a, false).unwrap();
+
+        fs::write(temp.path().join("main.ts"), "b").unwrap();
+        create_commit(&repo, "B", Some(&commit_a));
+
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        let mut builder = repo.treebuilder(Some(&commit_a.tree().unwrap())).unwrap();
+        let blob = repo.blob(b"d").unwrap();
+        builder.insert("feature.ts", blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        repo.commit(
+            Some("refs/heads/feature"),
+            &sig,
+            &sig,
+            "D",
+            &tree,
+            &[&commit_a],
+        )
+        .unwrap();
+
+        let names = |base: &str, head: Option<&str>| -> Vec<String> {
+            let range = CommitRange::parse(base, head).unwrap();
+            let mut names: Vec<String> = get_changed_files(
+                temp.path(),
+                &range,
+                ChangeScope::Committed,
+                DEFAULT_RENAME_THRESHOLD,
+            )
+            .unwrap()
+            .iter()
+            .map(|cf| format!("{} {}", cf.change_type, cf.path.rsplit('/').next().unwrap()))
+            .collect();
+            names.sort();
+            names
+        };
+
+        // Three dots and --head keep the merge-base semantics
+        assert_eq!(names("HEAD...feature", None), vec!["A feature.ts"]);
+        assert_eq!(names("HEAD", Some("feature")), vec!["A feature.ts"]);
+
+        // Two dots compare both commits directly
+        assert_eq!(
+            names("HEAD..feature", None),
+            vec!["A feature.ts", "D main.ts"]
+        );
+
+        let range = CommitRange::parse("HEAD", Some("feature")).unwrap();
+        assert!(
+            get_changed_files(
+                temp.path(),
+                &range,
+                ChangeScope::Uncommitted,
+                DEFAULT_RENAME_THRESHOLD
+            )
+            .is_err()
+        );
+    }
 }
@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use serde::Serialize;
 
-use crate::entity::Entity;
+use crate::entity::{EdgeKind, Entity};
 
 #[derive(Debug, Clone, Serialize)]
 pub(crate) struct GraphNode {
@@ -17,6 +17,7 @@ pub(crate) struct GraphNode {
 pub(crate) struct GraphEdge {
     pub source: String,
     pub target: String,
+    pub kind: EdgeKind,
 }
 
 #[derive(Debug, Serialize)]
@@ -55,6 +56,7 @@ impl DependencyGraph {
                     edges.push(GraphEdge {
                         source: entity.id.clone(),
                         target: target_id.clone(),
+                        kind: import.kind,
                     });
                 }
             }
@@ -82,6 +84,21 @@ impl DependencyGraph {
         index
     }
 
+    /// Build a forward index mapping source_id -> Vec<target_ids>
+    /// This allows us to find all entities a given entity depends on.
+    pub fn build_dependency_index(&self) -> HashMap<String, Vec<String>> {
+        let mut index: HashMap<String, Vec<String>> = HashMap::new();
+
+        for edge in &self.edges {
+            index
+                .entry(edge.source.clone())
+                .or_default()
+                .push(edge.target.clone());
+        }
+
+        index
+    }
+
     /// Find the shortest path from start_id to end_id following dependency edges.
     /// Uses BFS to find the shortest path.
     /// Returns Some(Vec<String>) with entity IDs in path order, or None if no path exists.
@@ -174,6 +191,7 @@ impl DependencyGraph {
         all_paths
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn dfs_all_paths(
         &self,
         current: &str,
@@ -271,6 +289,7 @@ impl DependencyGraph {
         cycles
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn dfs_find_cycles(
         &self,
         node: &str,
@@ -336,7 +355,7 @@ impl DependencyGraph {
 
     /// Normalize a cycle by rotating it to start from the lexicographically smallest ID.
     /// This ensures the same cycle found from different starting points is deduplicated.
-    fn normalize_cycle(&self, cycle: &[String]) -> Vec<String> {
+    pub fn normalize_cycle(&self, cycle: &[String]) -> Vec<String> {
         if cycle.is_empty() {
             return Vec::new();
         }
@@ -361,47 +380,66 @@ impl DependencyGraph {
         target_ids: &HashSet<String>,
         transitive: bool,
     ) -> HashSet<String> {
-        let consumer_index = self.build_consumer_index();
-        let mut consumers = HashSet::new();
-
-        if transitive {
-            // BFS to find all transitive consumers
-            let mut visited = target_ids.clone();
-            let mut queue: VecDeque<String> = target_ids.iter().cloned().collect();
-
-            while let Some(current) = queue.pop_front() {
-                if let Some(deps) = consumer_index.get(&current) {
-                    for consumer_id in deps {
-                        if !visited.contains(consumer_id) {
-                            visited.insert(consumer_id.clone());
-                            queue.push_back(consumer_id.clone());
-                            consumers.insert(consumer_id.clone());
-                        }
+        reachable(&self.build_consumer_index(), target_ids, transitive)
+    }
+
+    /// Find all entities the given source IDs depend on.
+    /// If transitive is true, performs BFS to find all transitive dependencies.
+    /// Returns a set of dependency entity IDs (excluding the original source IDs).
+    pub fn find_dependencies(
+        &self,
+        source_ids: &HashSet<String>,
+        transitive: bool,
+    ) -> HashSet<String> {
+        reachable(&self.build_dependency_index(), source_ids, transitive)
+    }
+}
+
+/// Entities reachable from start_ids in an adjacency index, excluding start_ids.
+fn reachable(
+    index: &HashMap<String, Vec<String>>,
+    start_ids: &HashSet<String>,
+    transitive: bool,
+) -> HashSet<String> {
+    let mut found = HashSet::new();
+
+    if transitive {
+        // BFS to find everything reachable
+        let mut visited = start_ids.clone();
+        let mut queue: VecDeque<String> = start_ids.iter().cloned().collect();
+
+        while let Some(current) = queue.pop_front() {
+            if let Some(next_ids) = index.get(&current) {
+                for next_id in next_ids {
+                    if !visited.contains(next_id) {
+                        visited.insert(next_id.clone());
+                        queue.push_back(next_id.clone());
+                        found.insert(next_id.clone());
                     }
                 }
             }
-        } else {
-            // Single-hop: only direct consumers
-            for target_id in target_ids {
-                if let Some(deps) = consumer_index.get(target_id) {
-                    for consumer_id in deps {
-                        if !target_ids.contains(consumer_id) {
-                            consumers.insert(consumer_id.clone());
-                        }
+        }
+    } else {
+        // Single-hop: only direct neighbours
+        for start_id in start_ids {
+            if let Some(next_ids) = index.get(start_id) {
+                for next_id in next_ids {
+                    if !start_ids.contains(next_id) {
+                        found.insert(next_id.clone());
                     }
                 }
             }
         }
-
-        consumers
     }
+
+    found
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::entity::{EntityType, ImportInfo};
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     fn create_entity(
         name: &str,
@@ -413,7 +451,7 @@ mod tests {
             name.to_string(),
             entity_type,
             file_path.to_string(),
-            Rc::new(deps),
+            Arc::new(deps),
         )
     }
 
@@ -701,6 +739,40 @@ mod tests {
         assert!(consumers.contains(&a_id));
     }
 
+    #[test]
+    fn test_find_dependencies() {
+        let mut entities: HashMap<String, Entity> = HashMap::new();
+
+        // A -> B -> C chain
+        let entity_c = create_entity("C", EntityType::Function, "/src/c.ts", vec![]);
+        let c_id = entity_c.id.clone();
+        entities.insert(entity_c.id.clone(), entity_c);
+
+        let import_c = ImportInfo::new("C".to_string(), "/src/c.ts".to_string());
+        let entity_b = create_entity("B", EntityType::Function, "/src/b.ts", vec![import_c]);
+        let b_id = entity_b.id.clone();
+        entities.insert(entity_b.id.clone(), entity_b);
+
+        let import_b = ImportInfo::new("B".to_string(), "/src/b.ts".to_string());
+        let entity_a = create_entity("A", EntityType::Function, "/src/a.ts", vec![import_b]);
+        let a_id = entity_a.id.clone();
+        entities.insert(entity_a.id.clone(), entity_a);
+
+        let graph = DependencyGraph::from_entities(&entities);
+
+        let mut source_ids = HashSet::new();
+        source_ids.insert(a_id);
+
+        let direct = graph.find_dependencies(&source_ids, false);
+        assert_eq!(direct.len(), 1);
+        assert!(direct.contains(&b_id));
+
+        let transitive = graph.find_dependencies(&source_ids, true);
+        assert_eq!(transitive.len(), 2);
+        assert!(transitive.contains(&b_id));
+        assert!(transitive.contains(&c_id));
+    }
+
     #[test]
     fn test_find_consumers_handles_cycles() {
         let mut entities: HashMap<String, Entity> = HashMap::new();
@@ -724,9 +796,9 @@ mod tests {
         let import_a = ImportInfo::new("A".to_string(), "/src/a.ts".to_string());
         let import_b = ImportInfo::new("B".to_string(), "/src/b.ts".to_string());
 
-        entities.get_mut(&a_id).unwrap().deps = std::rc::Rc::new(vec![import_c]);
-        entities.get_mut(&b_id).unwrap().deps = std::rc::Rc::new(vec![import_a]);
-        entities.get_mut(&c_id).unwrap().deps = std::rc::Rc::new(vec![import_b]);
+        entities.get_mut(&a_id).unwrap().deps = std::sync::Arc::new(vec![import_c]);
+        entities.get_mut(&b_id).unwrap().deps = std::sync::Arc::new(vec![import_a]);
+        entities.get_mut(&c_id).unwrap().deps = std::sync::Arc::new(vec![import_b]);
 
         let graph = DependencyGraph::from_entities(&entities);
 
@@ -849,9 +921,9 @@ mod tests {
         let import_c = ImportInfo::new("C".to_string(), "/src/c.ts".to_string());
         let import_a = ImportInfo::new("A".to_string(), "/src/a.ts".to_string());
 
-        entities.get_mut(&a_id).unwrap().deps = std::rc::Rc::new(vec![import_b]);
-        entities.get_mut(&b_id).unwrap().deps = std::rc::Rc::new(vec![import_c]);
-        entities.get_mut(&c_id).unwrap().deps = std::rc::Rc::new(vec![import_a]);
+        entities.get_mut(&a_id).unwrap().deps = std::sync::Arc::new(vec![import_b]);
+        entities.get_mut(&b_id).unwrap().deps = std::sync::Arc::new(vec![import_c]);
+        entities.get_mut(&c_id).unwrap().deps = std::sync::Arc::new(vec![import_a]);
 
         let graph = DependencyGraph::from_entities(&entities);
         let path = graph.find_path(&a_id, &c_id);
@@ -1132,8 +1204,8 @@ mod tests {
         let import_b = ImportInfo::new("B".to_string(), "/src/b.ts".to_string());
         let import_a = ImportInfo::new("A".to_string(), "/src/a.ts".to_string());
 
-        entities.get_mut(&a_id).unwrap().deps = std::rc::Rc::new(vec![import_b]);
-        entities.get_mut(&b_id).unwrap().deps = std::rc::Rc::new(vec![import_a]);
+        entities.get_mut(&a_id).unwrap().deps = std::sync::Arc::new(vec![import_b]);
+        entities.get_mut(&b_id).unwrap().deps = std::sync::Arc::new(vec![import_a]);
 
         let graph = DependencyGraph::from_entities(&entities);
         let cycles = graph.find_cycles(100, 100);
@@ -1163,9 +1235,9 @@ mod tests {
         let import_c = ImportInfo::new("C".to_string(), "/src/c.ts".to_string());
         let import_a = ImportInfo::new("A".to_string(), "/src/a.ts".to_string());
 
-        entities.get_mut(&a_id).unwrap().deps = std::rc::Rc::new(vec![import_b]);
-        entities.get_mut(&b_id).unwrap().deps = std::rc::Rc::new(vec![import_c]);
-        entities.get_mut(&c_id).unwrap().deps = std::rc::Rc::new(vec![import_a]);
+        entities.get_mut(&a_id).unwrap().deps = std::sync::Arc::new(vec![import_b]);
+        entities.get_mut(&b_id).unwrap().deps = std::sync::Arc::new(vec![import_c]);
+        entities.get_mut(&c_id).unwrap().deps = std::sync::Arc::new(vec![import_a]);
 
         let graph = DependencyGraph::from_entities(&entities);
         let cycles = graph.find_cycles(100, 100);
@@ -1200,10 +1272,10 @@ mod tests {
         let import_d = ImportInfo::new("D".to_string(), "/src/d.ts".to_string());
         let import_c = ImportInfo::new("C".to_string(), "/src/c.ts".to_string());
 
-        entities.get_mut(&a_id).unwrap().deps = std::rc::Rc::new(vec![import_b]);
-        entities.get_mut(&b_id).unwrap().deps = std::rc::Rc::new(vec![import_a]);
-        entities.get_mut(&c_id).unwrap().deps = std::rc::Rc::new(vec![import_d]);
-        entities.get_mut(&d_id).unwrap().deps = std::rc::Rc::new(vec![import_c]);
+        entities.get_mut(&a_id).unwrap().deps = std::sync::Arc::new(vec![import_b]);
+        entities.get_mut(&b_id).unwrap().deps = std::sync::Arc::new(vec![import_a]);
+        entities.get_mut(&c_id).unwrap().deps = std::sync::Arc::new(vec![import_d]);
+        entities.get_mut(&d_id).unwrap().deps = std::sync::Arc::new(vec![import_c]);
 
         let graph = DependencyGraph::from_entities(&entities);
 
@@ -1242,10 +1314,10 @@ mod tests {
         let import_d = ImportInfo::new("D".to_string(), "/src/d.ts".to_string());
         let import_a = ImportInfo::new("A".to_string(), "/src/a.ts".to_string());
 
-        entities.get_mut(&a_id).unwrap().deps = std::rc::Rc::new(vec![import_b]);
-        entities.get_mut(&b_id).unwrap().deps = std::rc::Rc::new(vec![import_c]);
-        entities.get_mut(&c_id).unwrap().deps = std::rc::Rc::new(vec![import_d]);
-        entities.get_mut(&d_id).unwrap().deps = std::rc::Rc::new(vec![import_a]);
+        entities.get_mut(&a_id).unwrap().deps = std::sync::Arc::new(vec![import_b]);
+        entities.get_mut(&b_id).unwrap().deps = std::sync::Arc::new(vec![import_c]);
+        entities.get_mut(&c_id).unwrap().deps = std::sync::Arc::new(vec![import_d]);
+        entities.get_mut(&d_id).unwrap().deps = std::sync::Arc::new(vec![import_a]);
 
         let graph = DependencyGraph::from_entities(&entities);
 
@@ -1275,8 +1347,8 @@ mod tests {
         let import_b = ImportInfo::new("B".to_string(), "/src/b.ts".to_string());
         let import_a = ImportInfo::new("A".to_string(), "/src/a.ts".to_string());
 
-        entities.get_mut(&a_id).unwrap().deps = std::rc::Rc::new(vec![import_b]);
-        entities.get_mut(&b_id).unwrap().deps = std::rc::Rc::new(vec![import_a]);
+        entities.get_mut(&a_id).unwrap().deps = std::sync::Arc::new(vec![import_b]);
+        entities.get_mut(&b_id).unwrap().deps = std::sync::Arc::new(vec![import_a]);
 
         let graph = DependencyGraph::from_entities(&entities);
         let cycles = graph.find_cycles(100, 100);
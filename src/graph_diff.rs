@@ -0,0 +1,505 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::entity::{EdgeKind, Entity, EntityType};
+use crate::graph::DependencyGraph;
+use crate::output::{CycleRecord, DependencyCountChange, EdgeRecord, EntityRecord};
+
+/// Structural changes of the dependency graph from a base to a head
+/// revision. Entities are matched by id, so a moved or renamed entity is
+/// removed and added.
+#[derive(Debug, Serialize)]
+pub(crate) struct GraphDiff {
+    pub added_entities: Vec<EntityRecord>,
+    pub removed_entities: Vec<EntityRecord>,
+    pub added_edges: Vec<EdgeRecord>,
+    pub removed_edges: Vec<EdgeRecord>,
+    pub new_cycles: Vec<CycleRecord>,
+    pub resolved_cycles: Vec<CycleRecord>,
+    /// Entities of both revisions whose number of dependencies changed
+    pub dependency_changes: Vec<DependencyCountChange>,
+    /// Whether `--max-cycles` cut the cycle search short on either side,
+    /// so that cycle changes may be incomplete
+    pub cycles_limited: bool,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_entities.is_empty()
+            && self.removed_entities.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.new_cycles.is_empty()
+            && self.resolved_cycles.is_empty()
+            && self.dependency_changes.is_empty()
+    }
+}
+
+/// Edges and cycles of one side of the diff, by entity id.
+struct Snapshot<'a> {
+    entities: &'a HashMap<String, Entity>,
+    /// Edges by source, target and kind, so that a dependency whose kind
+    /// changed is removed with the old kind and added with the new one
+    edges: BTreeSet<(String, String, EdgeKind)>,
+    /// Cycles rotated to start at their smallest id
+    cycles: BTreeSet<Vec<String>>,
+    cycles_limited: bool,
+}
+
+impl<'a> Snapshot<'a> {
+    fn new(entities: &'a HashMap<String, Entity>, max_cycles: usize, max_depth: usize) -> Self {
+        let graph = DependencyGraph::from_entities(entities);
+        let cycles = graph.find_cycles(max_cycles, max_depth);
+
+        Snapshot {
+            entities,
+            edges: graph
+                .edges
+                .iter()
+                .map(|edge| (edge.source.clone(), edge.target.clone(), edge.kind))
+                .collect(),
+            cycles_limited: cycles.len() >= max_cycles,
+            cycles: cycles
+                .iter()
+                .map(|cycle| graph.normalize_cycle(cycle))
+                .collect(),
+        }
+    }
+
+    /// The entity with this id, if it is declared in this revision rather
+    /// than only imported.
+    fn declared(&self, id: &str) -> Option<&'a Entity> {
+        self.entities
+            .get(id)
+            .filter(|e| !matches!(e.entity_type, EntityType::Unknown))
+    }
+
+    /// Number of entities `id` depends on, however many kinds of edges
+    /// lead to each.
+    fn dependency_count(&self, id: &str) -> usize {
+        self.edges
+            .range((id.to_string(), String::new(), EdgeKind::default())..)
+            .take_while(|(source, _, _)| source == id)
+            .map(|(_, target, _)| target)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    /// Declared entities missing from `other`.
+    fn entities_missing_from(&self, other: &Snapshot) -> Vec<EntityRecord> {
+        let mut records: Vec<EntityRecord> = self
+            .entities
+            .values()
+            .filter(|e| self.declared(&e.id).is_some() && other.declared(&e.id).is_none())
+            .map(Into::into)
+            .collect();
+        records.sort_by(|a, b| a.file.cmp(&b.file).then(a.name.cmp(&b.name)));
+        records
+    }
+
+    fn edges_missing_from(&self, other: &Snapshot) -> Vec<EdgeRecord> {
+        let mut records: Vec<EdgeRecord> = self
+            .edges
+            .difference(&other.edges)
+            .filter_map(|(source, target, kind)| {
+                Some(EdgeRecord {
+                    source: self.entities.get(source)?.into(),
+                    target: self.entities.get(target)?.into(),
+                    kind: *kind,
+                })
+            })
+            .collect();
+        records.sort_by(|a, b| {
+            (
+                &a.source.file,
+                &a.source.name,
+                &a.target.file,
+                &a.target.name,
+                a.kind,
+            )
+                .cmp(&(
+                    &b.source.file,
+                    &b.source.name,
+                    &b.target.file,
+                    &b.target.name,
+                    b.kind,
+                ))
+        });
+        records
+    }
+
+    fn cycles_missing_from(&self, other: &Snapshot) -> Vec<CycleRecord> {
+        let mut records: Vec<CycleRecord> = self
+            .cycles
+            .difference(&other.cycles)
+            .map(|cycle| CycleRecord {
+                entities: cycle
+                    .iter()
+                    .filter_map(|id| self.entities.get(id).map(Into::into))
+                    .collect(),
+            })
+            .collect();
+        records.sort_by_cached_key(cycle_names);
+        records
+    }
+}
+
+/// Compares the dependency graphs of the entities of two revisions.
+pub(crate) fn diff(
+    base: &HashMap<String, Entity>,
+    head: &HashMap<String, Entity>,
+    max_cycles: usize,
+    max_depth: usize,
+) -> GraphDiff {
+    let before = Snapshot::new(base, max_cycles, max_depth);
+    let after = Snapshot::new(head, max_cycles, max_depth);
+
+    let mut dependency_changes: Vec<DependencyCountChange> = head
+        .values()
+        .filter(|e| after.declared(&e.id).is_some() && before.declared(&e.id).is_some())
+        .filter_map(|entity| {
+            let count_before = before.dependency_count(&entity.id);
+            let count_after = after.dependency_count(&entity.id);
+            (count_before != count_after).then(|| DependencyCountChange {
+                entity: entity.into(),
+                before: count_before,
+                after: count_after,
+            })
+        })
+        .collect();
+    dependency_changes.sort_by(|a, b| {
+        a.entity
+            .file
+            .cmp(&b.entity.file)
+            .then(a.entity.name.cmp(&b.entity.name))
+    });
+
+    GraphDiff {
+        added_entities: after.entities_missing_from(&before),
+        removed_entities: before.entities_missing_from(&after),
+        added_edges: after.edges_missing_from(&before),
+        removed_edges: before.edges_missing_from(&after),
+        new_cycles: after.cycles_missing_from(&before),
+        resolved_cycles: before.cycles_missing_from(&after),
+        dependency_changes,
+        cycles_limited: before.cycles_limited || after.cycles_limited,
+    }
+}
+
+/// Names of a cycle's entities, closed with the first one again.
+fn cycle_names(cycle: &CycleRecord) -> String {
+    let mut names: Vec<&str> = cycle.entities.iter().map(|e| e.name.as_str()).collect();
+    if let Some(first) = names.first() {
+        names.push(first);
+    }
+    names.join(" -> ")
+}
+
+/// Prints the diff; `head` is None for the working directory.
+pub(crate) fn print_text(diff: &GraphDiff, base: &str, head: Option<&str>) {
+    match head {
+        Some(head) => println!(
+            "Comparing the dependency graph of '{}' with '{}'\n",
+            base, head
+        ),
+        None => println!(
+            "Comparing the dependency graph of '{}' with the working tree\n",
+            base
+        ),
+    }
+
+    if diff.is_empty() {
+        println!("No changes to the dependency graph.");
+        return;
+    }
+
+    println!(
+        "Entities: {} added, {} removed",
+        diff.added_entities.len(),
+        diff.removed_entities.len()
+    );
+    for (sign, records) in [("+", &diff.added_entities), ("-", &diff.removed_entities)] {
+        for entity in records {
+            println!(
+                "  {} {} ({}) {}",
+                sign, entity.name, entity.entity_type, entity.file
+            );
+        }
+    }
+
+    println!(
+        "\nDependencies: {} added, {} removed",
+        diff.added_edges.len(),
+        diff.removed_edges.len()
+    );
+    for (sign, records) in [("+", &diff.added_edges), ("-", &diff.removed_edges)] {
+        for edge in records {
+            println!("  {} {}", sign, edge_text(edge));
+        }
+    }
+
+    println!(
+        "\nCycles: {} new, {} resolved",
+        diff.new_cycles.len(),
+        diff.resolved_cycles.len()
+    );
+    for (sign, records) in [("+", &diff.new_cycles), ("-", &diff.resolved_cycles)] {
+        for cycle in records {
+            println!("  {} {}", sign, cycle_names(cycle));
+        }
+    }
+
+    println!(
+        "\nDependency count changes: {}",
+        diff.dependency_changes.len()
+    );
+    for change in &diff.dependency_changes {
+        println!(
+            "  {} ({}): {} -> {}",
+            change.entity.name, change.entity.entity_type, change.before, change.after
+        );
+    }
+}
+
+/// An edge with the files of both ends, since entities of different files
+/// may share a name, and its kind.
+fn edge_text(edge: &EdgeRecord) -> String {
+    format!(
+        "{} ({}) -> {} ({}) [{}]",
+        edge.source.name, edge.source.file, edge.target.name, edge.target.file, edge.kind
+    )
+}
+
+/// Renders the diff as Markdown for a pull request comment, with paths
+/// relative to the workspace root.
+pub(crate) fn to_markdown(
+    diff: &GraphDiff,
+    root_path: &Path,
+    base: &str,
+    head: Option<&str>,
+) -> String {
+    let relative = |file: &str| {
+        Path::new(file)
+            .strip_prefix(root_path)
+            .unwrap_or(Path::new(file))
+            .to_string_lossy()
+            .to_string()
+    };
+
+    let mut lines = vec![
+        "## Dependency graph changes".to_string(),
+        String::new(),
+        match head {
+            Some(head) => format!("Comparing `{}` with `{}`.", base, head),
+            None => format!("Comparing `{}` with the working tree.", base),
+        },
+        String::new(),
+    ];
+
+    if diff.is_empty() {
+        lines.push("No changes to the dependency graph.".to_string());
+        return lines.join("\n");
+    }
+
+    lines.extend([
+        "| | Added | Removed |".to_string(),
+        "| --- | ---: | ---: |".to_string(),
+        format!(
+            "| Entities | {} | {} |",
+            diff.added_entities.len(),
+            diff.removed_entities.len()
+        ),
+        format!(
+            "| Dependencies | {} | {} |",
+            diff.added_edges.len(),
+            diff.removed_edges.len()
+        ),
+        format!(
+            "| Cycles | {} | {} |",
+            diff.new_cycles.len(),
+            diff.resolved_cycles.len()
+        ),
+    ]);
+
+    let mut section = |title: &str, items: Vec<String>| {
+        if !items.is_empty() {
+            lines.extend([String::new(), format!("### {}", title), String::new()]);
+            lines.extend(items);
+        }
+    };
+
+    let cycles = |records: &[CycleRecord]| -> Vec<String> {
+        records
+            .iter()
+            .map(|cycle| format!("- `{}`", cycle_names(cycle)))
+            .collect()
+    };
+    let entities = |records: &[EntityRecord]| -> Vec<String> {
+        records
+            .iter()
+            .map(|e| format!("- `{}` ({}) `{}`", e.name, e.entity_type, relative(&e.file)))
+            .collect()
+    };
+    let edges = |records: &[EdgeRecord]| -> Vec<String> {
+        records
+            .iter()
+            .map(|edge| {
+                format!(
+                    "- `{}` -> `{}` (`{}`, {})",
+                    edge.source.name,
+                    edge.target.name,
+                    relative(&edge.target.file),
+                    edge.kind
+                )
+            })
+            .collect()
+    };
+
+    section("New cycles", cycles(&diff.new_cycles));
+    section("Resolved cycles", cycles(&diff.resolved_cycles));
+    section("Added entities", entities(&diff.added_entities));
+    section("Removed entities", entities(&diff.removed_entities));
+    section("Added dependencies", edges(&diff.added_edges));
+    section("Removed dependencies", edges(&diff.removed_edges));
+
+    if !diff.dependency_changes.is_empty() {
+        let mut rows = vec![
+            "| Entity | Before | After |".to_string(),
+            "| --- | ---: | ---: |".to_string(),
+        ];
+        rows.extend(diff.dependency_changes.iter().map(|change| {
+            format!(
+                "| `{}` ({}) | {} | {} |",
+                change.entity.name, change.entity.entity_type, change.before, change.after
+            )
+        }));
+        section("Dependency count changes", rows);
+    }
+
+    if diff.cycles_limited {
+        section(
+            "Note",
+            vec![
+                "The cycle search hit `--max-cycles`; cycle changes may be incomplete.".to_string(),
+            ],
+        );
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::ImportInfo;
+    use std::sync::Arc;
+
+    fn entity(name: &str, deps: &[&str]) -> Entity {
+        Entity::new(
+            name.to_string(),
+            EntityType::Class,
+            format!("/repo/{}.ts", name.to_lowercase()),
+            Arc::new(
+                deps.iter()
+                    .map(|dep| {
+                        ImportInfo::new(dep.to_string(), format!("/repo/{}.ts", dep.to_lowercase()))
+                    })
+                    .collect(),
+            ),
+        )
+    }
+
+    fn entities(list: Vec<Entity>) -> HashMap<String, Entity> {
+        list.into_iter().map(|e| (e.id.clone(), e)).collect()
+    }
+
+    fn names(records: &[EntityRecord]) -> Vec<&str> {
+        records.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diff_reports_entities_edges_and_cycles() {
+        let base = entities(vec![
+            entity("A", &["B"]),
+            entity("B", &["C"]),
+            entity("C", &["B"]),
+            entity("Old", &[]),
+        ]);
+        let head = entities(vec![
+            entity("A", &["B", "New"]),
+            entity("B", &[]),
+            entity("C", &["A"]),
+            entity("New", &["C"]),
+        ]);
+
+        let diff = diff(&base, &head, 100, 10);
+
+        assert_eq!(names(&diff.added_entities), vec!["New"]);
+        assert_eq!(names(&diff.removed_entities), vec!["Old"]);
+
+        let edges = |records: &[EdgeRecord]| -> Vec<String> {
+            records
+                .iter()
+                .map(|e| format!("{}->{}", e.source.name, e.target.name))
+                .collect()
+        };
+        assert_eq!(edges(&diff.added_edges), vec!["A->New", "C->A", "New->C"]);
+        assert_eq!(edges(&diff.removed_edges), vec!["B->C", "C->B"]);
+        assert_eq!(
+            edge_text(&diff.added_edges[0]),
+            "A (/repo/a.ts) -> New (/repo/new.ts) [import]"
+        );
+
+        assert_eq!(diff.new_cycles.len(), 1);
+        assert_eq!(diff.new_cycles[0].entities.len(), 3);
+        assert_eq!(diff.resolved_cycles.len(), 1);
+        assert_eq!(names(&diff.resolved_cycles[0].entities).len(), 2);
+
+        let changes: Vec<(&str, usize, usize)> = diff
+            .dependency_changes
+            .iter()
+            .map(|c| (c.entity.name.as_str(), c.before, c.after))
+            .collect();
+        assert_eq!(changes, vec![("A", 1, 2), ("B", 1, 0)]);
+    }
+
+    #[test]
+    fn test_diff_reports_edges_whose_kind_changed() {
+        let mut lazy = entity("A", &[]);
+        lazy.deps = Arc::new(vec![ImportInfo {
+            kind: EdgeKind::Lazy,
+            ..ImportInfo::new("B".to_string(), "/repo/b.ts".to_string())
+        }]);
+        let base = entities(vec![entity("A", &["B"]), entity("B", &[])]);
+        let head = entities(vec![lazy, entity("B", &[])]);
+
+        let diff = diff(&base, &head, 100, 10);
+
+        assert!(!diff.is_empty());
+        assert_eq!(
+            edge_text(&diff.added_edges[0]),
+            "A (/repo/a.ts) -> B (/repo/b.ts) [lazy]"
+        );
+        assert_eq!(
+            edge_text(&diff.removed_edges[0]),
+            "A (/repo/a.ts) -> B (/repo/b.ts) [import]"
+        );
+        assert!(diff.dependency_changes.is_empty());
+    }
+
+    #[test]
+    fn test_unchanged_graph_is_empty() {
+        let base = entities(vec![entity("A", &["B"]), entity("B", &[])]);
+        let head = entities(vec![entity("A", &["B"]), entity("B", &[])]);
+
+        let diff = diff(&base, &head, 100, 10);
+
+        assert!(diff.is_empty());
+        assert!(
+            to_markdown(&diff, Path::new("/repo"), "main", None)
+                .ends_with("No changes to the dependency graph.")
+        );
+    }
+}
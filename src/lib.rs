@@ -1,46 +1,75 @@
+mod angular;
+mod barrel;
+mod cache;
+mod config;
 mod entity;
 mod git;
 mod graph;
+mod graph_diff;
 mod mem_leaks;
+mod output;
 mod parser;
+mod routes;
+mod runner;
+mod sarif;
 mod scanner;
+mod search;
+mod source;
+mod tsconfig;
 
 use std::collections::{HashMap, HashSet};
-use std::fs;
 use std::path::Path;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use anyhow::Result;
-
-use entity::{Entity, EntityType};
-use git::{ChangeType, ChangedFile, get_changed_files};
+use rayon::prelude::*;
+
+pub use output::OutputFormat;
+pub use runner::TestRunner;
+
+use barrel::BarrelIndex;
+use cache::ParseCache;
+use config::{Project, StingConfig};
+pub use entity::EdgeKind;
+use entity::{Entity, EntityType, ImportInfo};
+pub use git::ChangeScope;
+use git::{
+    ChangeType, ChangedFile, CommitRange, DEFAULT_RENAME_THRESHOLD, get_changed_files,
+    get_changed_lines,
+};
 use graph::DependencyGraph;
-use mem_leaks::analyze_and_print as analyze_mem_leaks_and_print;
-use parser::Parser;
-use scanner::Scanner;
-
-fn is_test_file(path: &str) -> bool {
-    path.ends_with(".test.ts") || path.ends_with(".spec.ts") || path.ends_with(".e2e.ts")
-}
-
-fn find_test_files_in_directories(directories: &HashSet<String>) -> Vec<String> {
+use output::{
+    AffectedEntityRecord, AffectedMemLeaksReport, AffectedReport, AffectedStatus, ChainPath,
+    ChainReport, ChangedFileRecord, CycleRecord, CyclesReport, EntityExplanation, EntityRecord,
+    ExplainReport, GraphDiffReport, MemLeaksReport, Neighbourhood, QueryAllReport, QueryMatch,
+    QueryReport, RankRecord, RankReport, RoutesReport, UnusedReport,
+};
+use parser::{FileParseResult, Parser, ReExport, RouteTable};
+use sarif::SarifBuilder;
+use scanner::{Scanner, is_test_file};
+use search::{EntityFilter, MatchKind};
+use source::FileSource;
+
+fn find_test_files_in_directories(
+    source: &FileSource,
+    directories: &HashSet<String>,
+) -> Vec<String> {
     let mut test_files: HashSet<String> = HashSet::new();
 
     for dir_path in directories {
         let dir = Path::new(dir_path);
-        if !dir.is_dir() {
+        if !source.is_dir(dir) {
             continue;
         }
 
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(path_str) = path.to_str() {
-                        if is_test_file(path_str) {
-                            test_files.insert(path_str.to_string());
-                        }
-                    }
+        if let Ok(entries) = source.read_dir(dir) {
+            for entry in entries {
+                let path = entry.path;
+                if entry.is_file
+                    && let Some(path_str) = path.to_str()
+                    && is_test_file(path_str)
+                {
+                    test_files.insert(path_str.to_string());
                 }
             }
         }
@@ -51,20 +80,110 @@ fn find_test_files_in_directories(directories: &HashSet<String>) -> Vec<String>
     sorted
 }
 
+/// Command line overrides for how the workspace is scanned and parsed.
+#[derive(Debug, Default, Clone)]
+pub struct ScanOptions {
+    /// Glob patterns a file must match to be scanned, relative to the root
+    pub include: Vec<String>,
+    /// Glob patterns of files and directories to skip, relative to the root
+    pub exclude: Vec<String>,
+    /// Ignore `.gitignore` and `.ignore` files
+    pub no_ignore: bool,
+    /// Report every excluded file together with the rule that excluded it
+    pub verbose: bool,
+    /// Parse every file instead of reusing results from `.sting/cache`
+    pub no_cache: bool,
+    /// Read files from the tree of this git revision instead of the
+    /// working directory
+    pub rev: Option<String>,
+}
+
+/// Which kinds of dependency edges a command follows.
+#[derive(Debug, Default, Clone)]
+pub struct EdgeFilter {
+    /// Only follow edges of these kinds; every kind when empty
+    pub only: Vec<EdgeKind>,
+    /// Never follow edges of these kinds
+    pub skip: Vec<EdgeKind>,
+}
+
+impl EdgeFilter {
+    fn allows(&self, kind: EdgeKind) -> bool {
+        (self.only.is_empty() || self.only.contains(&kind)) && !self.skip.contains(&kind)
+    }
+
+    /// Drops the dependencies and test imports of kinds not followed.
+    fn apply(&self, result: &mut ScanResult) {
+        if self.only.is_empty() && self.skip.is_empty() {
+            return;
+        }
+        for entity in result.entities.values_mut() {
+            if entity.deps.iter().any(|dep| !self.allows(dep.kind)) {
+                let deps = entity.deps.iter().filter(|dep| self.allows(dep.kind));
+                entity.deps = Arc::new(deps.cloned().collect());
+            }
+        }
+        for imports in result.test_imports.values_mut() {
+            imports.retain(|import| self.allows(import.kind));
+        }
+    }
+}
+
+/// The files to analyze: the working directory, or the tree of the
+/// revision given on the command line.
+fn open_source(root_path: &Path, scan_options: &ScanOptions) -> Result<FileSource> {
+    match &scan_options.rev {
+        Some(rev) => FileSource::git_tree(root_path, rev),
+        None => Ok(FileSource::Filesystem),
+    }
+}
+
+/// Loads `sting.toml` and applies the command line scan options on top.
+fn load_config(
+    root_path: &Path,
+    source: &FileSource,
+    scan_options: &ScanOptions,
+) -> Result<StingConfig> {
+    let mut config = StingConfig::load(root_path, source)?;
+    config.scan.include_from_command_line(&scan_options.include);
+    config.scan.exclude_from_command_line(&scan_options.exclude);
+    if scan_options.no_ignore {
+        config.scan.ignore_files = false;
+    }
+    if scan_options.no_cache {
+        config.scan.cache = false;
+    }
+    config.scan.report_excluded = scan_options.verbose;
+    Ok(config)
+}
+
 struct ScanResult {
     entities: HashMap<String, Entity>,
+    /// Resolved imports of the test files, by path
+    test_imports: HashMap<String, Vec<ImportInfo>>,
+    /// Route arrays of every file, with barrel imports resolved
+    routes: Vec<RouteTable>,
+    /// Re-exports of the files that have any, by path
+    reexports: HashMap<String, Vec<ReExport>>,
 }
 
-fn scan_and_parse_files(root_path: &Path, verbose: bool) -> Result<ScanResult> {
-    let subdirs = ["apps/web", "apps/mobile", "libs"];
+/// Scans and parses the workspace as it is in `source`. Parse results of
+/// a git tree are not cached.
+fn scan_and_parse_files(
+    root_path: &Path,
+    config: &StingConfig,
+    source: &FileSource,
+    verbose: bool,
+) -> Result<ScanResult> {
     let mut all_files = Vec::new();
+    let mut excluded_tests = Vec::new();
 
-    let scanner = Scanner::new();
+    let scanner = Scanner::new(root_path, &config.scan, source)?;
 
-    for subdir in subdirs {
-        let full_path = root_path.join(subdir);
+    for project in &config.projects {
+        let full_path = root_path.join(&project.root);
 
-        if !full_path.exists() {
+        if !source.exists(&full_path) {
             if verbose {
                 eprintln!(
                     "Warning: Directory {:?} does not exist, skipping...",
@@ -79,11 +198,22 @@ fn scan_and_parse_files(root_path: &Path, verbose: bool) -> Result<ScanResult> {
         }
 
         match scanner.scan(&full_path) {
-            Ok(mut files) => {
+            Ok(scanned) => {
+                let mut files: Vec<String> = scanned
+                    .files
+                    .into_iter()
+                    .filter(|file| project.contains(root_path, file))
+                    .collect();
                 if verbose {
                     println!("  Found {} TypeScript files", files.len());
                 }
                 all_files.append(&mut files);
+                excluded_tests.extend(
+                    scanned
+                        .tests
+                        .into_iter()
+                        .filter(|file| project.contains(root_path, file)),
+                );
             }
             Err(e) => {
                 if verbose {
@@ -93,45 +223,78 @@ fn scan_and_parse_files(root_path: &Path, verbose: bool) -> Result<ScanResult> {
         }
     }
 
+    // Project roots may be nested inside each other
+    all_files.sort();
+    all_files.dedup();
+
     if all_files.is_empty() {
-        anyhow::bail!("No TypeScript files found in {}", root_path.display());
+        match source.revision() {
+            Some(revision) => anyhow::bail!(
+                "No TypeScript files found in {} at {}",
+                root_path.display(),
+                revision
+            ),
+            None => anyhow::bail!("No TypeScript files found in {}", root_path.display()),
+        }
     }
 
+    // Tests below excluded paths only contribute their imports
+    excluded_tests.sort();
+    excluded_tests.dedup();
+    let excluded_tests: HashSet<String> = excluded_tests
+        .into_iter()
+        .filter(|file| all_files.binary_search(file).is_err())
+        .collect();
+    all_files.extend(excluded_tests.iter().cloned());
+    all_files.sort();
+
     let mut entities_map: HashMap<String, Entity> = HashMap::new();
 
     if verbose {
         println!("Processing {} TypeScript files...\n", all_files.len());
     }
 
-    let parser = Parser::new(root_path);
-
-    for file in &all_files {
-        match parser.parse(file) {
-            Ok(result) => {
-                for import in &result.imports {
-                    if let Some(existing) = entities_map.get_mut(&import.id) {
-                        existing.used = true;
-                    } else {
-                        let mut imported_entity = Entity::new(
-                            import.name.clone(),
-                            EntityType::Unknown,
-                            import.path.clone(),
-                            Rc::new(Vec::new()),
-                        );
-                        imported_entity.used = true;
-                        entities_map.insert(import.id.clone(), imported_entity);
-                    }
-                }
+    let parser = Parser::new(root_path, config, source);
 
-                for entity in result.entities {
-                    if let Some(existing) = entities_map.get_mut(&entity.id) {
-                        existing.entity_type = entity.entity_type;
-                        existing.deps = entity.deps;
-                    } else {
-                        entities_map.insert(entity.id.clone(), entity);
-                    }
-                }
+    // Files are parsed in parallel; collecting keeps them in path order
+    let results: Vec<(&str, Result<FileParseResult>)> =
+        if config.scan.cache && source.is_filesystem() {
+            let cache = ParseCache::load(root_path, &parser, &all_files);
+            let entries: Vec<_> = all_files
+                .par_iter()
+                .map(|file| (file.as_str(), cache.parse(&parser, file)))
+                .collect();
+
+            if verbose {
+                let hits = entries
+                    .iter()
+                    .filter(|(_, entry)| matches!(entry, Ok((_, true))))
+                    .count();
+                println!("Reused {} cached parse results\n", hits);
+            }
+
+            let valid = entries
+                .iter()
+                .filter_map(|(file, entry)| entry.as_ref().ok().map(|(entry, _)| (*file, entry)));
+            if let Err(e) = cache.save(valid) {
+                eprintln!("Warning: Could not update the parse cache: {:#}", e);
             }
+
+            entries
+                .into_iter()
+                .map(|(file, entry)| (file, entry.map(|(entry, _)| entry.into_result())))
+                .collect()
+        } else {
+            all_files
+                .par_iter()
+                .map(|file| (file.as_str(), parser.parse(file)))
+                .collect()
+        };
+
+    let mut parsed_files = Vec::with_capacity(results.len());
+    for (file, result) in results {
+        match result {
+            Ok(result) => parsed_files.push((file, result)),
             Err(e) => {
                 if verbose {
                     eprintln!("Warning: Could not parse file {}: {}", file, e);
@@ -140,8 +303,102 @@ fn scan_and_parse_files(root_path: &Path, verbose: bool) -> Result<ScanResult> {
         }
     }
 
+    // Follow barrel re-exports so imports point at the declaring files
+    let barrel_index = BarrelIndex::new(
+        parsed_files.iter().flat_map(|(_, result)| {
+            result
+                .entities
+                .iter()
+                .map(|e| (e.file_path.as_str(), e.name.as_str()))
+        }),
+        parsed_files
+            .iter()
+            .map(|(file, result)| (*file, result.reexports.as_slice())),
+    );
+
+    let resolved_imports: Vec<Vec<ImportInfo>> = parsed_files
+        .par_iter()
+        .map(|(_, result)| barrel_index.resolve_all(&result.imports))
+        .collect();
+    let resolved_deps: Vec<Vec<Vec<ImportInfo>>> = parsed_files
+        .par_iter()
+        .map(|(_, result)| {
+            result
+                .entities
+                .iter()
+                .map(|entity| barrel_index.resolve_all(&entity.deps))
+                .collect()
+        })
+        .collect();
+
+    let routes: Vec<RouteTable> = parsed_files
+        .iter()
+        .filter(|(file, _)| !excluded_tests.contains(*file))
+        .flat_map(|(_, result)| result.routes.iter().cloned())
+        .map(|mut table| {
+            table.map_targets(&|target| {
+                barrel_index
+                    .resolve(target)
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| target.clone())
+            });
+            table
+        })
+        .collect();
+
+    let mut test_imports = HashMap::new();
+    let mut reexports = HashMap::new();
+
+    for (((file, mut result), imports), deps) in parsed_files
+        .into_iter()
+        .zip(resolved_imports)
+        .zip(resolved_deps)
+    {
+        if is_test_file(file) {
+            test_imports.insert(file.to_string(), imports.clone());
+        }
+        if excluded_tests.contains(file) {
+            continue;
+        }
+        if !result.reexports.is_empty() {
+            reexports.insert(file.to_string(), std::mem::take(&mut result.reexports));
+        }
+
+        for import in &imports {
+            if let Some(existing) = entities_map.get_mut(&import.id) {
+                existing.used = true;
+            } else {
+                let mut imported_entity = Entity::new(
+                    import.name.clone(),
+                    EntityType::Unknown,
+                    import.path.clone(),
+                    Arc::new(Vec::new()),
+                );
+                imported_entity.used = true;
+                entities_map.insert(import.id.clone(), imported_entity);
+            }
+        }
+
+        for (mut entity, deps) in result.entities.into_iter().zip(deps) {
+            entity.deps = Arc::new(deps);
+            if let Some(existing) = entities_map.get_mut(&entity.id) {
+                existing.entity_type = entity.entity_type;
+                existing.deps = entity.deps;
+                existing.angular = entity.angular;
+            } else {
+                entities_map.insert(entity.id.clone(), entity);
+            }
+        }
+    }
+
+    angular::link_templates(source, &mut entities_map);
+
     Ok(ScanResult {
         entities: entities_map,
+        test_imports,
+        routes,
+        reexports,
     })
 }
 
@@ -158,14 +415,35 @@ fn print_entity(entity: &Entity, show_id: bool, show_deps: bool) {
     println!("---");
 }
 
-pub fn query_all(root_path: &Path) -> Result<()> {
-    let result = scan_and_parse_files(root_path, true)?;
+pub fn clear_cache(root_path: &Path) -> Result<()> {
+    if cache::clear(root_path)? {
+        println!("Removed {}", root_path.join(cache::CACHE_DIR).display());
+    } else {
+        println!("No cache found in {}", root_path.display());
+    }
+    Ok(())
+}
 
-    println!("Found {} entities:\n", result.entities.len());
+pub fn query_all(root_path: &Path, scan_options: &ScanOptions, format: OutputFormat) -> Result<()> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let result = scan_and_parse_files(root_path, &config, &source, format.is_text())?;
 
     let mut sorted_entities: Vec<_> = result.entities.values().collect();
     sorted_entities.sort_by(|a, b| a.id.cmp(&b.id));
 
+    if !format.is_text() {
+        return output::emit(
+            format,
+            &QueryAllReport {
+                entities: sorted_entities.into_iter().map(Into::into).collect(),
+                total: result.entities.len(),
+            },
+        );
+    }
+
+    println!("Found {} entities:\n", result.entities.len());
+
     for entity in sorted_entities {
         print_entity(entity, true, true);
     }
@@ -175,20 +453,420 @@ pub fn query_all(root_path: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn query(root_path: &Path, query: &str) -> Result<()> {
-    let result = scan_and_parse_files(root_path, false)?;
+/// Searches entities by name, best match first: exact, prefix, substring,
+/// then fuzzy matches. Each match is shown with its direct dependencies
+/// and consumers.
+#[allow(clippy::too_many_arguments)]
+pub fn query(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    query: &str,
+    entity_type_filters: &[String],
+    project_name: Option<&str>,
+    file_pattern: Option<&str>,
+    limit: usize,
+) -> Result<()> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let project = config.project_filter(project_name)?;
+    let filter = EntityFilter::new(root_path, entity_type_filters, project, file_pattern)?;
+    let result = scan_and_parse_files(root_path, &config, &source, false)?;
 
-    if let Some(entity) = result.entities.get(query) {
-        print_entity(entity, true, true);
-    } else {
-        println!("Entity not found: {}", query);
+    let matches = search::search(result.entities.values(), query, &filter);
+    let total = matches.len();
+
+    let graph = DependencyGraph::from_entities(&result.entities);
+    let matches: Vec<QueryMatch> = matches
+        .into_iter()
+        .take(limit)
+        .map(|(entity, name_match)| {
+            let ids = HashSet::from([entity.id.clone()]);
+            QueryMatch {
+                entity: entity.into(),
+                match_kind: name_match.kind,
+                deps: entity_records(&result.entities, graph.find_dependencies(&ids, false)),
+                consumers: entity_records(&result.entities, graph.find_consumers(&ids, false)),
+            }
+        })
+        .collect();
+
+    if !format.is_text() {
+        return output::emit(
+            format,
+            &QueryReport {
+                query: query.to_string(),
+                matches,
+                total,
+            },
+        );
+    }
+
+    if matches.is_empty() {
+        println!("No entities found matching '{}'", query);
+        return Ok(());
+    }
+
+    println!(
+        "Found {} {} for '{}':\n",
+        total,
+        if total == 1 { "match" } else { "matches" },
+        query
+    );
+
+    for found in &matches {
+        println!("Name: {}", found.entity.name);
+        println!("Type: {}", found.entity.entity_type);
+        println!("File: {}", found.entity.file);
+        println!("ID: {}", found.entity.id);
+        println!("Match: {}", found.match_kind);
+        print_neighbours("Deps", &found.deps);
+        print_neighbours("Consumers", &found.consumers);
+        println!("---");
+    }
+
+    if total > matches.len() {
+        println!(
+            "\nShowing {} of {} matches. Use --limit to show more.",
+            matches.len(),
+            total
+        );
     }
 
     Ok(())
 }
 
-pub fn unused(root_path: &Path, fail_on_findings: bool) -> Result<()> {
-    let result = scan_and_parse_files(root_path, true)?;
+/// Explains one entity, or every entity declared in a file: where it is
+/// declared, what it depends on and what consumes it, directly and
+/// transitively, the cycles it takes part in and its memory leak findings.
+/// With a base reference, also whether the changes since then affect it.
+#[allow(clippy::too_many_arguments)]
+pub fn explain(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    query: &str,
+    file_pattern: Option<&str>,
+    base_ref: Option<&str>,
+    max_cycles: usize,
+    max_depth: usize,
+    max_findings: usize,
+    strict: bool,
+) -> Result<()> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let filter = EntityFilter::new(root_path, &[], None, file_pattern)?;
+    let changed_files = base_ref
+        .map(|base_ref| {
+            let range = CommitRange {
+                head: scan_options.rev.clone(),
+                ..CommitRange::new(base_ref)
+            };
+            get_changed_files(
+                root_path,
+                &range,
+                ChangeScope::Committed,
+                DEFAULT_RENAME_THRESHOLD,
+            )
+        })
+        .transpose()?;
+    let result = scan_and_parse_files(root_path, &config, &source, false)?;
+
+    let explained = resolve_entities(root_path, &source, &result.entities, query, &filter)?;
+
+    let graph = DependencyGraph::from_entities(&result.entities);
+    let cycles = graph.find_cycles(max_cycles, max_depth);
+
+    // Findings are attributed by declaration, so analyze whole files
+    let explained_files: HashSet<&str> = explained.iter().map(|e| e.file_path.as_str()).collect();
+    let file_entities: HashMap<String, Entity> = result
+        .entities
+        .iter()
+        .filter(|(_, e)| explained_files.contains(e.file_path.as_str()))
+        .map(|(id, e)| (id.clone(), e.clone()))
+        .collect();
+    let mut leak_reports = mem_leaks::analyze(&source, &file_entities, &[], strict);
+
+    let mut declaration_lines: HashMap<&str, HashMap<String, usize>> = HashMap::new();
+
+    let explanations: Vec<EntityExplanation> = explained
+        .iter()
+        .map(|entity| {
+            let ids = HashSet::from([entity.id.clone()]);
+            let transitive_deps = graph.find_dependencies(&ids, true);
+
+            let line = match entity.entity_type {
+                EntityType::Worker => Some(1),
+                _ => declaration_lines
+                    .entry(entity.file_path.as_str())
+                    .or_insert_with(|| parser::declaration_lines(&source, &entity.file_path))
+                    .get(&entity.name)
+                    .copied(),
+            };
+
+            let leaks = leak_reports
+                .iter_mut()
+                .find(|r| r.name == entity.name && r.file_path == entity.file_path)
+                .map(|r| std::mem::take(&mut r.findings))
+                .unwrap_or_default();
+
+            let affected = base_ref.zip(changed_files.as_ref()).map(|(base, changed)| {
+                let own_change = changed.iter().find(|cf| {
+                    cf.path == entity.file_path || angular::touches_resource(cf, entity)
+                });
+                let changed_deps: Vec<String> = transitive_deps
+                    .iter()
+                    .filter(|id| {
+                        result.entities.get(*id).is_some_and(|dep| {
+                            changed.iter().any(|cf| {
+                                cf.touches(&dep.file_path) || angular::touches_resource(cf, dep)
+                            })
+                        })
+                    })
+                    .cloned()
+                    .collect();
+
+                let relation = if own_change.is_some() {
+                    "direct"
+                } else if !changed_deps.is_empty() {
+                    "consumer"
+                } else {
+                    "none"
+                };
+
+                AffectedStatus {
+                    base: base.to_string(),
+                    relation,
+                    change: own_change.map(|cf| change_type_name(&cf.change_type)),
+                    changed_deps: entity_records(&result.entities, changed_deps),
+                }
+            });
+
+            EntityExplanation {
+                entity: (*entity).into(),
+                line,
+                used: entity.used,
+                deps: Neighbourhood {
+                    direct: entity_records(&result.entities, graph.find_dependencies(&ids, false)),
+                    transitive: entity_records(&result.entities, transitive_deps),
+                },
+                consumers: Neighbourhood {
+                    direct: entity_records(&result.entities, graph.find_consumers(&ids, false)),
+                    transitive: entity_records(&result.entities, graph.find_consumers(&ids, true)),
+                },
+                cycles: cycles
+                    .iter()
+                    .filter(|cycle| cycle.contains(&entity.id))
+                    .map(|cycle| CycleRecord {
+                        entities: cycle
+                            .iter()
+                            .filter_map(|id| result.entities.get(id).map(Into::into))
+                            .collect(),
+                    })
+                    .collect(),
+                leaks,
+                affected,
+            }
+        })
+        .collect();
+
+    if !format.is_text() {
+        return output::emit(
+            format,
+            &ExplainReport {
+                query: query.to_string(),
+                entities: explanations,
+            },
+        );
+    }
+
+    for explanation in &explanations {
+        print_explanation(explanation, max_findings);
+    }
+
+    Ok(())
+}
+
+/// Entities explained for a query: every entity declared in the file the
+/// query names, or the entity whose name or id matches it exactly.
+fn resolve_entities<'e>(
+    root_path: &Path,
+    source: &FileSource,
+    entities: &'e HashMap<String, Entity>,
+    query: &str,
+    filter: &EntityFilter,
+) -> Result<Vec<&'e Entity>> {
+    if let Ok(file) = source.canonicalize(&root_path.join(query))
+        && source.is_file(&file)
+    {
+        let file = file.to_string_lossy();
+        let mut declared: Vec<&Entity> = entities
+            .values()
+            .filter(|e| e.file_path == file && filter.matches(e))
+            .collect();
+        if declared.is_empty() {
+            anyhow::bail!("No entities declared in {}", file);
+        }
+        declared.sort_by(|a, b| a.name.cmp(&b.name));
+        return Ok(declared);
+    }
+
+    let matches = search::search(entities.values(), query, filter);
+    let best = matches.first().map(|(_, m)| *m);
+    let exact: Vec<&Entity> = matches
+        .iter()
+        .take_while(|(_, m)| Some(*m) == best && m.kind == MatchKind::Exact)
+        .map(|(e, _)| *e)
+        .collect();
+
+    match exact.as_slice() {
+        [entity] => Ok(vec![*entity]),
+        [] => {
+            let suggestions: Vec<String> = matches
+                .iter()
+                .take(5)
+                .map(|(e, _)| format!("\n  {} ({}) {}", e.name, e.entity_type, e.file_path))
+                .collect();
+            if suggestions.is_empty() {
+                anyhow::bail!("Entity not found: {}", query);
+            }
+            anyhow::bail!(
+                "Entity not found: {}. Did you mean:{}",
+                query,
+                suggestions.concat()
+            )
+        }
+        candidates => {
+            let listed: Vec<String> = candidates
+                .iter()
+                .map(|e| format!("\n  {} ({}) {}", e.name, e.entity_type, e.file_path))
+                .collect();
+            anyhow::bail!(
+                "'{}' matches {} entities, narrow it down with --file:{}",
+                query,
+                candidates.len(),
+                listed.concat()
+            )
+        }
+    }
+}
+
+fn print_explanation(explanation: &EntityExplanation, max_findings: usize) {
+    let entity = &explanation.entity;
+    println!("{} ({})", entity.name, entity.entity_type);
+    match explanation.line {
+        Some(line) => println!("Declared at: {}:{}", entity.file, line),
+        None => println!("Declared in: {}", entity.file),
+    }
+    println!("ID: {}", entity.id);
+    println!("Used: {}", if explanation.used { "yes" } else { "no" });
+    println!();
+
+    for (label, neighbourhood) in [
+        ("Dependencies", &explanation.deps),
+        ("Consumers", &explanation.consumers),
+    ] {
+        println!(
+            "{}: {} direct, {} transitive",
+            label,
+            neighbourhood.direct.len(),
+            neighbourhood.transitive.len()
+        );
+        for record in &neighbourhood.direct {
+            println!("  {} ({}) {}", record.name, record.entity_type, record.file);
+        }
+        let indirect = neighbourhood.transitive.len() - neighbourhood.direct.len();
+        if indirect > 0 {
+            println!("  ... and {} more transitively", indirect);
+        }
+    }
+    println!();
+
+    if explanation.cycles.is_empty() {
+        println!("Cycles: none");
+    } else {
+        println!("Cycles: {}", explanation.cycles.len());
+        for cycle in &explanation.cycles {
+            let names: Vec<&str> = cycle.entities.iter().map(|e| e.name.as_str()).collect();
+            if let Some(first) = names.first() {
+                println!("  {} -> {}", names.join(" -> "), first);
+            }
+        }
+    }
+
+    if explanation.leaks.is_empty() {
+        println!("Memory leaks: none");
+    } else {
+        println!("Memory leaks: {} findings", explanation.leaks.len());
+        for finding in explanation.leaks.iter().take(max_findings) {
+            println!(
+                "  - [{}] line {}: {} ({})",
+                finding.severity, finding.line, finding.message, finding.kind
+            );
+        }
+        if explanation.leaks.len() > max_findings {
+            println!(
+                "  - ... {} more findings",
+                explanation.leaks.len() - max_findings
+            );
+        }
+    }
+
+    if let Some(affected) = &explanation.affected {
+        match affected.relation {
+            "direct" => println!(
+                "Affected since '{}': yes, file {}",
+                affected.base,
+                affected.change.unwrap_or("changed")
+            ),
+            "consumer" => {
+                let names: Vec<&str> = affected
+                    .changed_deps
+                    .iter()
+                    .map(|e| e.name.as_str())
+                    .collect();
+                println!(
+                    "Affected since '{}': yes, depends on {}",
+                    affected.base,
+                    names.join(", ")
+                );
+            }
+            _ => println!("Affected since '{}': no", affected.base),
+        }
+    }
+
+    println!("---");
+}
+
+/// Records of the given entities, sorted by name and file.
+fn entity_records(
+    entities: &HashMap<String, Entity>,
+    ids: impl IntoIterator<Item = String>,
+) -> Vec<EntityRecord> {
+    let mut records: Vec<EntityRecord> = ids
+        .into_iter()
+        .filter_map(|id| entities.get(&id).map(Into::into))
+        .collect();
+    records.sort_by(|a, b| a.name.cmp(&b.name).then(a.file.cmp(&b.file)));
+    records
+}
+
+fn print_neighbours(label: &str, entities: &[EntityRecord]) {
+    println!("{} ({}):", label, entities.len());
+    for entity in entities {
+        println!("  {} ({}) {}", entity.name, entity.entity_type, entity.file);
+    }
+}
+
+pub fn unused(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    fail_on_findings: bool,
+) -> Result<()> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let result = scan_and_parse_files(root_path, &config, &source, format.is_text())?;
 
     let mut unused_entities: Vec<_> = result
         .entities
@@ -196,19 +874,36 @@ pub fn unused(root_path: &Path, fail_on_findings: bool) -> Result<()> {
         .filter(|e| !e.used && !matches!(e.entity_type, EntityType::Unknown))
         .collect();
 
-    unused_entities.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    unused_entities.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.name.cmp(&b.name)));
 
-    println!("Found {} unused entities:\n", unused_entities.len());
+    if format == OutputFormat::Sarif {
+        let mut sarif = SarifBuilder::new(root_path, &source);
+        for entity in &unused_entities {
+            sarif.add_unused(entity);
+        }
+        println!("{}", sarif.into_json()?);
+    } else if format.is_text() {
+        println!("Found {} unused entities:\n", unused_entities.len());
 
-    for entity in &unused_entities {
-        print_entity(entity, false, false);
-    }
+        for entity in &unused_entities {
+            print_entity(entity, false, false);
+        }
 
-    println!(
-        "\nTotal: {} unused out of {} entities",
-        unused_entities.len(),
-        result.entities.len()
-    );
+        println!(
+            "\nTotal: {} unused out of {} entities",
+            unused_entities.len(),
+            result.entities.len()
+        );
+    } else {
+        output::emit(
+            format,
+            &UnusedReport {
+                entities: unused_entities.iter().map(|e| (*e).into()).collect(),
+                unused: unused_entities.len(),
+                total: result.entities.len(),
+            },
+        )?;
+    }
 
     if fail_on_findings && !unused_entities.is_empty() {
         anyhow::bail!(
@@ -220,8 +915,17 @@ pub fn unused(root_path: &Path, fail_on_findings: bool) -> Result<()> {
     Ok(())
 }
 
-pub fn graph_json(root_path: &Path, entity_type_filters: &[String]) -> Result<String> {
-    let result = scan_and_parse_files(root_path, false)?;
+/// Builds the dependency graph. In text format this is the plain D3.js
+/// graph JSON, otherwise the versioned `sting.graph` report.
+pub fn graph_json(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    entity_type_filters: &[String],
+) -> Result<String> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let result = scan_and_parse_files(root_path, &config, &source, false)?;
 
     let filtered_entities = if entity_type_filters.is_empty() {
         result.entities
@@ -236,36 +940,103 @@ pub fn graph_json(root_path: &Path, entity_type_filters: &[String]) -> Result<St
             .collect()
     };
 
-    let graph = DependencyGraph::from_entities(&filtered_entities);
-    let json = graph.to_json()?;
-    Ok(json)
-}
+    let mut graph = DependencyGraph::from_entities(&filtered_entities);
+    graph.nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    graph
+        .edges
+        .sort_by(|a, b| a.source.cmp(&b.source).then(a.target.cmp(&b.target)));
 
-fn matches_project_filter(file_path: &str, project_filter: Option<&str>) -> bool {
-    match project_filter {
-        None => true,
-        Some(pattern) => file_path.contains(pattern),
+    if format.is_text() {
+        Ok(graph.to_json()?)
+    } else {
+        Ok(output::render(format, &graph)?.join("\n"))
     }
 }
 
+fn matches_project_filter(root_path: &Path, file_path: &str, project: Option<&Project>) -> bool {
+    project.is_none_or(|project| project.contains(root_path, file_path))
+}
+
+/// What `affected` compares and how it reports the affected entities.
+#[derive(Debug, Clone)]
+pub struct AffectedOptions {
+    /// Git reference to compare against, or a range `A..B` / `A...B`
+    pub base: String,
+    /// Commit to analyze instead of the working directory
+    pub head: Option<String>,
+    /// Which changes on top of the merge-base count as changed
+    pub scope: ChangeScope,
+    /// Minimum similarity in percent for git to detect renames and copies
+    pub rename_threshold: u16,
+    /// Only count entities of modified files whose declaration the diff
+    /// touches as changed
+    pub precise: bool,
+    /// Include consumers of consumers
+    pub transitive: bool,
+    /// Output the directories of affected files instead of the files
+    pub paths_only: bool,
+    /// Output the test files of affected entities
+    pub tests_only: bool,
+    /// Also select every test file in the directories of affected entities
+    pub test_dirs: bool,
+    /// Group the affected tests by project into commands of this runner
+    pub runner: Option<TestRunner>,
+    /// Output the routes whose pages use an affected entity
+    pub routes_only: bool,
+    /// Output a human-readable summary
+    pub summary: bool,
+    /// Only report entities of this project of `sting.toml`
+    pub project: Option<String>,
+    /// Which kinds of dependency edges lead to consumers
+    pub edges: EdgeFilter,
+}
+
 pub fn affected(
     root_path: &Path,
-    base_ref: &str,
-    transitive: bool,
-    paths_only: bool,
-    tests_only: bool,
-    summary: bool,
-    project_filter: Option<&str>,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    options: &AffectedOptions,
 ) -> Result<()> {
+    let (range, source) = affected_revision(root_path, scan_options, options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let project_filter = config.project_filter(options.project.as_deref())?;
+    let in_project = |file_path: &str| matches_project_filter(root_path, file_path, project_filter);
+    let scope = options.scope;
+    let summary = options.summary && format.is_text();
+    let tests_only = options.tests_only || options.runner.is_some();
+
     if summary {
-        println!("Analyzing changes between HEAD and '{}'...\n", base_ref);
+        println!(
+            "Analyzing changes between {} and '{}'...\n",
+            range.target(scope),
+            range.base
+        );
     }
 
-    let changed_files = get_changed_files(root_path, base_ref)?;
+    let changed_files = get_changed_files(root_path, &range, scope, options.rename_threshold)?;
 
     if changed_files.is_empty() {
         if summary {
-            println!("No changes found between HEAD and '{}'.", base_ref);
+            println!(
+                "No changes found between {} and '{}'.",
+                range.target(scope),
+                range.base
+            );
+        }
+        if !format.is_text() {
+            output::emit(
+                format,
+                &AffectedReport {
+                    base: options.base.clone(),
+                    changed_files: Vec::new(),
+                    entities: Vec::new(),
+                    files: Vec::new(),
+                    directories: Vec::new(),
+                    tests: tests_only.then(Vec::new),
+                    test_targets: options.runner.map(|_| Vec::new()),
+                    routes: options.routes_only.then(Vec::new),
+                },
+            )?;
         }
         return Ok(());
     }
@@ -273,126 +1044,190 @@ pub fn affected(
     if summary {
         println!("Changed files ({}):", changed_files.len());
         for cf in &changed_files {
-            println!("  [{}] {}", cf.change_type, cf.path);
+            match &cf.old_path {
+                Some(old_path) => {
+                    println!("  [{}] {} (from {})", cf.change_type, cf.path, old_path)
+                }
+                None => println!("  [{}] {}", cf.change_type, cf.path),
+            }
         }
         println!();
     }
 
-    let result = scan_and_parse_files(root_path, false)?;
+    let mut result = scan_and_parse_files(root_path, &config, &source, false)?;
+    options.edges.apply(&mut result);
 
     let graph = DependencyGraph::from_entities(&result.entities);
 
-    let changed_paths: HashSet<String> = changed_files.iter().map(|cf| cf.path.clone()).collect();
-
-    let mut direct_affected: Vec<(&Entity, &ChangedFile)> = Vec::new();
-    let mut direct_affected_ids: HashSet<String> = HashSet::new();
-
-    for entity in result.entities.values() {
-        if changed_paths.contains(&entity.file_path)
-            && matches_project_filter(&entity.file_path, project_filter)
-        {
-            if let Some(cf) = changed_files.iter().find(|cf| cf.path == entity.file_path) {
-                direct_affected.push((entity, cf));
-                direct_affected_ids.insert(entity.id.clone());
-            }
-        }
-    }
-
-    direct_affected.sort_by(|a, b| a.0.file_path.cmp(&b.0.file_path));
-
-    let consumer_ids = graph.find_consumers(&direct_affected_ids, transitive);
+    // Consumers that were not updated still import renamed and deleted
+    // files at their old path
+    let removed_paths: HashSet<&str> = changed_files
+        .iter()
+        .filter_map(|cf| cf.removed_path())
+        .collect();
 
-    let mut consumers: Vec<(&Entity, String)> = Vec::new();
-    for consumer_id in &consumer_ids {
-        if let Some(entity) = result.entities.get(consumer_id) {
-            if !matches_project_filter(&entity.file_path, project_filter) {
-                continue;
-            }
-            let consumes: Vec<String> = entity
-                .deps
-                .iter()
-                .filter_map(|dep| {
-                    for (affected_entity, _) in &direct_affected {
-                        if affected_entity.file_path == dep.path && affected_entity.name == dep.name
-                        {
-                            return Some(affected_entity.name.clone());
-                        }
-                    }
-                    None
-                })
-                .collect();
+    let direct_affected = find_directly_affected(
+        root_path,
+        &source,
+        &range,
+        options,
+        &result,
+        &changed_files,
+        &in_project,
+    )?;
+    let direct_affected_ids: HashSet<String> = direct_affected
+        .iter()
+        .map(|(entity, _)| entity.id.clone())
+        .collect();
 
-            let reason = if consumes.is_empty() {
-                "Transitive dependency".to_string()
-            } else {
-                format!("Imports: {}", consumes.join(", "))
-            };
+    let consumers = find_affected_consumers(
+        &result,
+        &graph,
+        &direct_affected,
+        options.transitive,
+        &in_project,
+    );
 
-            consumers.push((entity, reason));
-        }
-    }
+    let affected_entities = || {
+        direct_affected
+            .iter()
+            .map(|(entity, _)| *entity)
+            .chain(consumers.iter().map(|(entity, _)| *entity))
+    };
 
-    consumers.sort_by(|a, b| a.0.file_path.cmp(&b.0.file_path));
+    // Tests and routes of an affected entity, whether or not consumers are
+    // listed transitively
+    let reachable_ids = || {
+        let mut ids = graph.find_consumers(&direct_affected_ids, true);
+        ids.extend(direct_affected_ids.iter().cloned());
+        ids
+    };
 
-    if tests_only {
-        let mut test_files: HashSet<String> = HashSet::new();
+    let tests = tests_only.then(|| {
+        select_tests(
+            &source,
+            &result.test_imports,
+            &reachable_ids(),
+            options.test_dirs.then(|| affected_entities().collect()),
+            &changed_files,
+            &in_project,
+        )
+    });
+
+    // Files and directories to run, so without the ones that are gone
+    let existing_entities =
+        || affected_entities().filter(|entity| !removed_paths.contains(entity.file_path.as_str()));
+
+    let mut affected_files: Vec<String> = existing_entities()
+        .map(|entity| entity.file_path.clone())
+        .collect();
+    affected_files.sort();
+    affected_files.dedup();
 
-        // Collect directories from directly affected entities
-        let mut affected_dirs: HashSet<String> = HashSet::new();
-        for (entity, _) in &direct_affected {
-            if let Some(parent) = Path::new(&entity.file_path).parent() {
-                affected_dirs.insert(parent.to_string_lossy().to_string());
-            }
+    let mut affected_dirs: Vec<String> = existing_entities()
+        .filter_map(|entity| Path::new(&entity.file_path).parent())
+        .map(|parent| parent.to_string_lossy().to_string())
+        .collect();
+    affected_dirs.sort();
+    affected_dirs.dedup();
+
+    let test_targets = options
+        .runner
+        .zip(tests.as_ref())
+        .map(|(runner, tests)| runner::group_tests(&source, root_path, runner, tests));
+
+    let route_tree = options
+        .routes_only
+        .then(|| routes::route_tree(&result.routes, &result.entities));
+    let (affected_routes, entity_routes) = match &route_tree {
+        Some(tree) => {
+            let (routes, by_entity) = routes::affected_routes(tree, &graph, &reachable_ids());
+            (Some(routes), by_entity)
         }
+        None => (None, HashMap::new()),
+    };
+    let routes_of = |entity: &Entity| {
+        route_tree
+            .is_some()
+            .then(|| entity_routes.get(&entity.id).cloned().unwrap_or_default())
+    };
 
-        // Collect directories from consumer entities
-        for (entity, _) in &consumers {
-            if let Some(parent) = Path::new(&entity.file_path).parent() {
-                affected_dirs.insert(parent.to_string_lossy().to_string());
-            }
-        }
+    if !format.is_text() {
+        let entities = direct_affected
+            .iter()
+            .map(|(entity, cf)| AffectedEntityRecord {
+                entity: (*entity).into(),
+                relation: "direct",
+                reason: direct_reason(entity, cf),
+                routes: routes_of(entity),
+            })
+            .chain(
+                consumers
+                    .iter()
+                    .map(|(entity, reason)| AffectedEntityRecord {
+                        entity: (*entity).into(),
+                        relation: "consumer",
+                        reason: reason.clone(),
+                        routes: routes_of(entity),
+                    }),
+            )
+            .collect();
 
-        // Find test files in those directories
-        let discovered_tests = find_test_files_in_directories(&affected_dirs);
-        for test_path in discovered_tests {
-            test_files.insert(test_path);
-        }
+        return output::emit(
+            format,
+            &AffectedReport {
+                base: options.base.clone(),
+                changed_files: changed_files
+                    .iter()
+                    .map(|cf| ChangedFileRecord {
+                        path: cf.path.clone(),
+                        old_path: cf.old_path.clone(),
+                        change: change_type_name(&cf.change_type).to_string(),
+                    })
+                    .collect(),
+                entities,
+                files: affected_files,
+                directories: affected_dirs,
+                tests,
+                test_targets,
+                routes: affected_routes.map(|routes| routes.into_iter().map(Into::into).collect()),
+            },
+        );
+    }
 
-        // Include test files that were directly changed in the git diff
-        for cf in &changed_files {
-            if is_test_file(&cf.path) && matches_project_filter(&cf.path, project_filter) {
-                test_files.insert(cf.path.clone());
-            }
+    if let Some(test_targets) = test_targets {
+        for target in test_targets {
+            println!("{}", target.command);
         }
-
-        // Output sorted test file paths
-        let mut sorted_tests: Vec<String> = test_files.into_iter().collect();
-        sorted_tests.sort();
-
-        for test_path in sorted_tests {
+    } else if let Some(tests) = tests {
+        for test_path in tests {
             println!("{}", test_path);
         }
-
-        return Ok(());
-    }
-
-    if summary {
+    } else if summary {
         println!("---");
         println!("Directly affected entities ({}):\n", direct_affected.len());
 
         for (entity, cf) in &direct_affected {
-            print_affected_entity(
-                entity,
-                &format!("{} file", change_type_to_reason(&cf.change_type)),
-            );
+            print_affected_entity(entity, &direct_reason(entity, cf), routes_of(entity));
         }
 
         if !consumers.is_empty() {
             println!("Consumer entities ({}):\n", consumers.len());
 
             for (entity, reason) in &consumers {
-                print_affected_entity(entity, reason);
+                print_affected_entity(entity, reason, routes_of(entity));
+            }
+        }
+
+        if let Some(affected_routes) = &affected_routes {
+            println!("Affected routes ({}):\n", affected_routes.len());
+            for route in affected_routes {
+                match &route.component {
+                    Some(component) => println!("  {} -> {}", route.path, component),
+                    None => println!("  {}", route.path),
+                }
             }
+            println!();
         }
 
         let total = direct_affected.len() + consumers.len();
@@ -403,85 +1238,373 @@ pub fn affected(
             consumers.len(),
             total
         );
-    } else if paths_only {
-        let mut unique_dirs: HashSet<String> = HashSet::new();
+    } else if let Some(affected_routes) = affected_routes {
+        for route in affected_routes {
+            println!("{}", route.path);
+        }
+    } else if options.paths_only {
+        for dir in affected_dirs {
+            println!("{}", dir);
+        }
+    } else {
+        for file_path in affected_files {
+            println!("{}", file_path);
+        }
+    }
 
-        for (entity, _) in &direct_affected {
-            if let Some(parent) = Path::new(&entity.file_path).parent() {
-                unique_dirs.insert(parent.to_string_lossy().to_string());
-            }
+    Ok(())
+}
+
+/// The commit range `affected` compares and the files of its head side.
+/// An explicit head is analyzed as committed, whatever is checked out, and
+/// staged changes without the unstaged edits on top.
+fn affected_revision(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    options: &AffectedOptions,
+) -> Result<(CommitRange, FileSource)> {
+    if options.head.is_some() && scan_options.rev.is_some() {
+        anyhow::bail!("--head and --rev both select the commit to analyze; pass only one");
+    }
+    let head = options.head.as_deref().or(scan_options.rev.as_deref());
+    let range = CommitRange::parse(&options.base, head)?;
+
+    let source = match (&range.head, options.scope) {
+        (Some(head), _) => FileSource::git_tree(root_path, head)?,
+        (None, ChangeScope::Staged) => FileSource::git_index(root_path)?,
+        (None, _) => FileSource::Filesystem,
+    };
+    Ok((range, source))
+}
+
+/// Entities of the project that a changed file touches, with that file,
+/// sorted by file and name.
+fn find_directly_affected<'r, 'c>(
+    root_path: &Path,
+    source: &FileSource,
+    range: &CommitRange,
+    options: &AffectedOptions,
+    result: &'r ScanResult,
+    changed_files: &'c [ChangedFile],
+    in_project: &dyn Fn(&str) -> bool,
+) -> Result<Vec<(&'r Entity, &'c ChangedFile)>> {
+    let removed_paths: HashSet<&str> = changed_files
+        .iter()
+        .filter_map(|cf| cf.removed_path())
+        .collect();
+
+    // Exported declarations of modified files that the diff left alone
+    let unchanged_exports: HashMap<&str, HashSet<String>> = if options.precise {
+        let changed_lines = get_changed_lines(root_path, range, options.scope)?;
+        changed_files
+            .iter()
+            .filter(|cf| cf.change_type == ChangeType::Modified)
+            .filter_map(|cf| {
+                let lines = changed_lines.get(&cf.path)?;
+                let unchanged = parser::unchanged_exports(source, &cf.path, lines);
+                Some((cf.path.as_str(), unchanged))
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut direct_affected: Vec<(&Entity, &ChangedFile)> = Vec::new();
+
+    for entity in result.entities.values() {
+        let unchanged = unchanged_exports
+            .get(entity.file_path.as_str())
+            .is_some_and(|names| names.contains(&entity.name));
+        // Components are also affected by their templates and stylesheets,
+        // and imports through a barrel by the removed files it re-exports
+        if in_project(&entity.file_path)
+            && let Some(cf) = changed_files
+                .iter()
+                .find(|cf| !unchanged && cf.touches(&entity.file_path))
+                .or_else(|| {
+                    changed_files
+                        .iter()
+                        .find(|cf| angular::touches_resource(cf, entity))
+                })
+                .or_else(|| {
+                    let removed = barrel::removed_reexport_source(
+                        &result.reexports,
+                        &entity.file_path,
+                        &entity.name,
+                        &removed_paths,
+                    )?;
+                    changed_files
+                        .iter()
+                        .find(|cf| cf.removed_path() == Some(removed))
+                })
+        {
+            direct_affected.push((entity, cf));
         }
+    }
 
-        for (entity, _) in &consumers {
-            if let Some(parent) = Path::new(&entity.file_path).parent() {
-                unique_dirs.insert(parent.to_string_lossy().to_string());
+    direct_affected.sort_by(|a, b| {
+        a.0.file_path
+            .cmp(&b.0.file_path)
+            .then(a.0.name.cmp(&b.0.name))
+    });
+    Ok(direct_affected)
+}
+
+/// Entities of the project that depend on a directly affected entity, with
+/// the reason, sorted by file and name.
+fn find_affected_consumers<'r>(
+    result: &'r ScanResult,
+    graph: &DependencyGraph,
+    direct_affected: &[(&Entity, &ChangedFile)],
+    transitive: bool,
+    in_project: &dyn Fn(&str) -> bool,
+) -> Vec<(&'r Entity, String)> {
+    let direct_affected_ids: HashSet<String> = direct_affected
+        .iter()
+        .map(|(entity, _)| entity.id.clone())
+        .collect();
+    let consumer_ids = graph.find_consumers(&direct_affected_ids, transitive);
+
+    let mut consumers: Vec<(&Entity, String)> = Vec::new();
+    for consumer_id in &consumer_ids {
+        if let Some(entity) = result.entities.get(consumer_id) {
+            if !in_project(&entity.file_path) {
+                continue;
             }
-        }
+            let consumes = |kind: EdgeKind| -> Vec<String> {
+                entity
+                    .deps
+                    .iter()
+                    .filter(|dep| dep.kind == kind)
+                    .filter_map(|dep| {
+                        for (affected_entity, _) in direct_affected {
+                            if affected_entity.file_path == dep.path
+                                && affected_entity.name == dep.name
+                            {
+                                return Some(affected_entity.name.clone());
+                            }
+                        }
+                        None
+                    })
+                    .collect()
+            };
 
-        let mut sorted_dirs: Vec<_> = unique_dirs.into_iter().collect();
-        sorted_dirs.sort();
+            let reasons: Vec<String> = [
+                ("Imports", EdgeKind::Import),
+                ("Imports type", EdgeKind::Type),
+                ("Lazy loads", EdgeKind::Lazy),
+                ("Dynamically imports", EdgeKind::Dynamic),
+                ("Starts worker", EdgeKind::Worker),
+                ("Template uses", EdgeKind::Template),
+                ("Injects", EdgeKind::Di),
+            ]
+            .into_iter()
+            .map(|(label, kind)| (label, consumes(kind)))
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(label, names)| format!("{}: {}", label, names.join(", ")))
+            .collect();
 
-        for dir in sorted_dirs {
-            println!("{}", dir);
+            let reason = if reasons.is_empty() {
+                "Transitive dependency".to_string()
+            } else {
+                reasons.join("; ")
+            };
+
+            consumers.push((entity, reason));
         }
-    } else {
-        let mut affected_files: HashSet<String> = HashSet::new();
+    }
+
+    consumers.sort_by(|a, b| {
+        a.0.file_path
+            .cmp(&b.0.file_path)
+            .then(a.0.name.cmp(&b.0.name))
+    });
+    consumers
+}
 
-        for (entity, _) in &direct_affected {
-            affected_files.insert(entity.file_path.clone());
+/// Test files of the project that import one of `tested_ids`, directly or
+/// through the code they import, plus the changed test files. With
+/// `affected_entities`, also every test file in their directories.
+fn select_tests(
+    source: &FileSource,
+    test_imports: &HashMap<String, Vec<ImportInfo>>,
+    tested_ids: &HashSet<String>,
+    affected_entities: Option<Vec<&Entity>>,
+    changed_files: &[ChangedFile],
+    in_project: &dyn Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut test_files: HashSet<String> = HashSet::new();
+
+    for (test_path, imports) in test_imports {
+        if imports.iter().any(|import| tested_ids.contains(&import.id)) && in_project(test_path) {
+            test_files.insert(test_path.clone());
         }
+    }
 
-        for (entity, _) in &consumers {
-            affected_files.insert(entity.file_path.clone());
+    if let Some(entities) = affected_entities {
+        // Collect directories from directly affected entities and consumers
+        let mut affected_dirs: HashSet<String> = HashSet::new();
+        for entity in entities {
+            if let Some(parent) = Path::new(&entity.file_path).parent() {
+                affected_dirs.insert(parent.to_string_lossy().to_string());
+            }
         }
 
-        let mut sorted_files: Vec<_> = affected_files.into_iter().collect();
-        sorted_files.sort();
+        // Find test files in those directories
+        let discovered_tests = find_test_files_in_directories(source, &affected_dirs);
+        for test_path in discovered_tests {
+            test_files.insert(test_path);
+        }
+    }
 
-        for file_path in sorted_files {
-            println!("{}", file_path);
+    // Include test files that were directly changed in the git diff
+    for cf in changed_files {
+        if is_test_file(&cf.path) && in_project(&cf.path) {
+            test_files.insert(cf.path.clone());
         }
     }
 
-    Ok(())
+    let mut sorted_tests: Vec<String> = test_files.into_iter().collect();
+    sorted_tests.sort();
+    sorted_tests
 }
 
-fn print_affected_entity(entity: &Entity, reason: &str) {
+fn print_affected_entity(entity: &Entity, reason: &str, routes: Option<Vec<String>>) {
     println!("Name: {}", entity.name);
     println!("Type: {}", entity.entity_type);
     println!("File: {}", entity.file_path);
     println!("Reason: {}", reason);
+    if let Some(routes) = routes.filter(|routes| !routes.is_empty()) {
+        println!("Routes: {}", routes.join(", "));
+    }
     println!("---");
 }
 
+fn change_type_name(change_type: &ChangeType) -> &'static str {
+    match change_type {
+        ChangeType::Added => "added",
+        ChangeType::Modified => "modified",
+        ChangeType::Deleted => "deleted",
+        ChangeType::Renamed => "renamed",
+        ChangeType::Copied => "copied",
+    }
+}
+
+/// Why an entity of a changed file is directly affected.
+fn direct_reason(entity: &Entity, cf: &ChangedFile) -> String {
+    let reason = change_type_to_reason(&cf.change_type);
+    if cf.path == entity.file_path {
+        format!("{} file", reason)
+    } else if angular::touches_resource(cf, entity) {
+        let file_name = Path::new(&cf.path).file_name().unwrap_or_default();
+        format!(
+            "{} template or style {}",
+            reason,
+            file_name.to_string_lossy()
+        )
+    } else if cf.removed_path() == Some(entity.file_path.as_str()) {
+        format!("{} file (old path)", reason)
+    } else {
+        format!("{} file (re-exported old path)", reason)
+    }
+}
+
 fn change_type_to_reason(change_type: &ChangeType) -> &'static str {
     match change_type {
         ChangeType::Added => "New",
         ChangeType::Modified => "Modified",
         ChangeType::Deleted => "Deleted",
         ChangeType::Renamed => "Renamed",
+        ChangeType::Copied => "Copied",
     }
 }
 
+/// Compares the dependency graph at the base of a commit range with the
+/// graph at its head, or with the working directory when there is no
+/// explicit head. Both sides are parsed from their own file source.
+#[allow(clippy::too_many_arguments)]
+pub fn graph_diff(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    base_ref: &str,
+    head_ref: Option<&str>,
+    max_cycles: usize,
+    max_depth: usize,
+) -> Result<()> {
+    if head_ref.is_some() && scan_options.rev.is_some() {
+        anyhow::bail!("--head and --rev both select the commit to compare; pass only one");
+    }
+    let range = CommitRange::parse(base_ref, head_ref.or(scan_options.rev.as_deref()))?;
+
+    let base_source = FileSource::git_tree(root_path, &range.base_commit_id(root_path)?)?;
+    let base_config = load_config(root_path, &base_source, scan_options)?;
+    let base = scan_and_parse_files(root_path, &base_config, &base_source, false)?;
+
+    let head_source = match &range.head {
+        Some(head) => FileSource::git_tree(root_path, head)?,
+        None => FileSource::Filesystem,
+    };
+    let head_config = load_config(root_path, &head_source, scan_options)?;
+    let head = scan_and_parse_files(root_path, &head_config, &head_source, false)?;
+
+    let diff = graph_diff::diff(&base.entities, &head.entities, max_cycles, max_depth);
+
+    match format {
+        OutputFormat::Text => {
+            graph_diff::print_text(&diff, &range.base, range.head.as_deref());
+            if diff.cycles_limited {
+                eprintln!(
+                    "Note: Cycle search limited to {} cycles, cycle changes may be incomplete. Use --max-cycles to adjust.",
+                    max_cycles
+                );
+            }
+            Ok(())
+        }
+        OutputFormat::Markdown => {
+            println!(
+                "{}",
+                graph_diff::to_markdown(&diff, root_path, &range.base, range.head.as_deref())
+            );
+            Ok(())
+        }
+        _ => output::emit(
+            format,
+            &GraphDiffReport {
+                base: range.base.clone(),
+                head: range.head.clone(),
+                diff,
+            },
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn chain(
     root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
     start_name: &str,
     end_name: &str,
     shortest: bool,
     max_paths: usize,
     max_depth: usize,
+    edge_filter: &EdgeFilter,
 ) -> Result<()> {
-    let result = scan_and_parse_files(root_path, false)?;
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let mut result = scan_and_parse_files(root_path, &config, &source, false)?;
+    edge_filter.apply(&mut result);
     let graph = DependencyGraph::from_entities(&result.entities);
 
     // Find entity IDs by exact name match
-    let start_matches: Vec<&Entity> = result
+    let mut start_matches: Vec<&Entity> = result
         .entities
         .values()
         .filter(|e| e.name == start_name)
         .collect();
 
-    let end_matches: Vec<&Entity> = result
+    let mut end_matches: Vec<&Entity> = result
         .entities
         .values()
         .filter(|e| e.name == end_name)
@@ -495,52 +1618,74 @@ pub fn chain(
         anyhow::bail!("Entity '{}' not found", end_name);
     }
 
+    start_matches.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    end_matches.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
     // Find chains between all matching start and end entities
-    let mut found_any = false;
-    let mut total_paths = 0;
+    let mut paths: Vec<Vec<String>> = Vec::new();
 
     for start_entity in &start_matches {
         for end_entity in &end_matches {
             if shortest {
                 // Only find the shortest path
                 if let Some(path_ids) = graph.find_path(&start_entity.id, &end_entity.id) {
-                    let names: Vec<String> = path_ids
-                        .iter()
-                        .filter_map(|id| result.entities.get(id).map(|e| e.name.clone()))
-                        .collect();
-                    println!("{}", names.join(" -> "));
-                    found_any = true;
+                    paths.push(path_ids);
                 }
             } else {
                 // Find all paths (up to remaining max)
-                let remaining = max_paths.saturating_sub(total_paths);
+                let remaining = max_paths.saturating_sub(paths.len());
                 if remaining == 0 {
                     break;
                 }
-                let all_paths =
-                    graph.find_all_paths(&start_entity.id, &end_entity.id, remaining, max_depth);
-                for path_ids in all_paths {
-                    let names: Vec<String> = path_ids
-                        .iter()
-                        .filter_map(|id| result.entities.get(id).map(|e| e.name.clone()))
-                        .collect();
-                    println!("{}", names.join(" -> "));
-                    found_any = true;
-                    total_paths += 1;
-                }
+                paths.extend(graph.find_all_paths(
+                    &start_entity.id,
+                    &end_entity.id,
+                    remaining,
+                    max_depth,
+                ));
             }
         }
-        if !shortest && total_paths >= max_paths {
+        if !shortest && paths.len() >= max_paths {
             break;
         }
     }
 
-    if !found_any {
+    let limited = !shortest && paths.len() >= max_paths;
+
+    if !format.is_text() {
+        return output::emit(
+            format,
+            &ChainReport {
+                start: start_name.to_string(),
+                end: end_name.to_string(),
+                paths: paths
+                    .iter()
+                    .map(|path_ids| ChainPath {
+                        entities: path_ids
+                            .iter()
+                            .filter_map(|id| result.entities.get(id).map(Into::into))
+                            .collect(),
+                    })
+                    .collect(),
+                limited,
+            },
+        );
+    }
+
+    for path_ids in &paths {
+        let names: Vec<String> = path_ids
+            .iter()
+            .filter_map(|id| result.entities.get(id).map(|e| e.name.clone()))
+            .collect();
+        println!("{}", names.join(" -> "));
+    }
+
+    if paths.is_empty() {
         println!(
             "No dependency chain found between '{}' and '{}'",
             start_name, end_name
         );
-    } else if !shortest && total_paths >= max_paths {
+    } else if limited {
         eprintln!(
             "Note: Output limited to {} paths. Use --max-paths to adjust.",
             max_paths
@@ -550,11 +1695,54 @@ pub fn chain(
     Ok(())
 }
 
-pub fn cycles(root_path: &Path, max_cycles: usize, max_depth: usize) -> Result<()> {
-    let result = scan_and_parse_files(root_path, false)?;
+pub fn cycles(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    max_cycles: usize,
+    max_depth: usize,
+    edge_filter: &EdgeFilter,
+) -> Result<()> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let mut result = scan_and_parse_files(root_path, &config, &source, false)?;
+    edge_filter.apply(&mut result);
     let graph = DependencyGraph::from_entities(&result.entities);
 
     let cycles = graph.find_cycles(max_cycles, max_depth);
+    let limited = cycles.len() >= max_cycles;
+
+    if format == OutputFormat::Sarif {
+        let mut sarif = SarifBuilder::new(root_path, &source);
+        for cycle in &cycles {
+            let entities: Vec<&Entity> = cycle
+                .iter()
+                .filter_map(|id| result.entities.get(id))
+                .collect();
+            sarif.add_cycle(&entities);
+        }
+        println!("{}", sarif.into_json()?);
+        return Ok(());
+    }
+
+    if !format.is_text() {
+        return output::emit(
+            format,
+            &CyclesReport {
+                cycles: cycles
+                    .iter()
+                    .map(|cycle| CycleRecord {
+                        entities: cycle
+                            .iter()
+                            .filter_map(|id| result.entities.get(id).map(Into::into))
+                            .collect(),
+                    })
+                    .collect(),
+                count: cycles.len(),
+                limited,
+            },
+        );
+    }
 
     if cycles.is_empty() {
         println!("No circular dependencies detected.");
@@ -589,7 +1777,6 @@ pub fn cycles(root_path: &Path, max_cycles: usize, max_depth: usize) -> Result<(
         println!("---");
     }
 
-    let limited = cycles.len() >= max_cycles;
     if limited {
         eprintln!(
             "Note: Output limited to {} cycles. Use --max-cycles to adjust.",
@@ -602,8 +1789,76 @@ pub fn cycles(root_path: &Path, max_cycles: usize, max_depth: usize) -> Result<(
     Ok(())
 }
 
-pub fn rank_by_deps(root_path: &Path, entity_type_filters: &[String]) -> Result<()> {
-    let result = scan_and_parse_files(root_path, false)?;
+/// Prints the route tree of the Angular app.
+pub fn routes(root_path: &Path, scan_options: &ScanOptions, format: OutputFormat) -> Result<()> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let result = scan_and_parse_files(root_path, &config, &source, format.is_text())?;
+    let tree = routes::route_tree(&result.routes, &result.entities);
+    let count = routes::flatten(&tree).len();
+
+    if !format.is_text() {
+        return output::emit(
+            format,
+            &RoutesReport {
+                routes: &tree,
+                count,
+            },
+        );
+    }
+
+    if tree.is_empty() {
+        println!("No routes found.");
+        return Ok(());
+    }
+
+    let mut files: Vec<&str> = Vec::new();
+    for node in &tree {
+        if !files.contains(&node.file.as_str()) {
+            files.push(&node.file);
+            println!("{}:", node.file);
+        }
+        print_route(node, 1);
+    }
+
+    println!("\nSummary: {} routes", count);
+
+    Ok(())
+}
+
+fn print_route(node: &routes::RouteNode, depth: usize) {
+    let mut line = format!("{}{}", "  ".repeat(depth), node.path);
+    if let Some(component) = &node.component {
+        line.push_str(&format!(" -> {}", component));
+    }
+    if let Some(redirect_to) = &node.redirect_to {
+        line.push_str(&format!(" -> redirects to {}", redirect_to));
+    }
+    if node.lazy {
+        line.push_str(" (lazy)");
+    }
+    if !node.guards.is_empty() {
+        line.push_str(&format!(" [guards: {}]", node.guards.join(", ")));
+    }
+    if !node.resolvers.is_empty() {
+        line.push_str(&format!(" [resolve: {}]", node.resolvers.join(", ")));
+    }
+    println!("{}", line);
+
+    for child in &node.children {
+        print_route(child, depth + 1);
+    }
+}
+
+pub fn rank_by_deps(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
+    entity_type_filters: &[String],
+) -> Result<()> {
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let result = scan_and_parse_files(root_path, &config, &source, false)?;
 
     let filtered_entities = if entity_type_filters.is_empty() {
         result.entities
@@ -639,7 +1894,28 @@ pub fn rank_by_deps(root_path: &Path, entity_type_filters: &[String]) -> Result<
         })
         .collect();
 
-    ranked.sort_by_key(|(count, node)| (*count, node.name.clone()));
+    ranked.sort_by_key(|(count, node)| (*count, node.name.clone(), node.file.clone()));
+
+    if !format.is_text() {
+        return output::emit(
+            format,
+            &RankReport {
+                by: "deps",
+                entities: ranked
+                    .into_iter()
+                    .map(|(count, node)| RankRecord {
+                        entity: EntityRecord {
+                            id: node.id.clone(),
+                            name: node.name.clone(),
+                            entity_type: node.entity_type.clone(),
+                            file: node.file.clone(),
+                        },
+                        count,
+                    })
+                    .collect(),
+            },
+        );
+    }
 
     // Output tab-separated: count, name, type, file
     for (count, node) in ranked {
@@ -654,98 +1930,170 @@ pub fn rank_by_deps(root_path: &Path, entity_type_filters: &[String]) -> Result<
 
 pub fn mem_leaks(
     root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
     entity_type_filters: &[String],
     max_findings: usize,
     strict: bool,
 ) -> Result<()> {
-    let result = scan_and_parse_files(root_path, false)?;
-    analyze_mem_leaks_and_print(&result.entities, entity_type_filters, max_findings, strict)?;
-    Ok(())
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let result = scan_and_parse_files(root_path, &config, &source, false)?;
+    let reports = mem_leaks::analyze(&source, &result.entities, entity_type_filters, strict);
+
+    if format == OutputFormat::Sarif {
+        println!("{}", leaks_to_sarif(root_path, &source, &reports)?);
+        Ok(())
+    } else if format.is_text() {
+        mem_leaks::print_reports(&reports, max_findings);
+        Ok(())
+    } else {
+        output::emit(
+            format,
+            &MemLeaksReport {
+                reports: &reports,
+                total: reports.len(),
+            },
+        )
+    }
+}
+
+fn leaks_to_sarif(
+    root_path: &Path,
+    source: &FileSource,
+    reports: &[mem_leaks::EntityLeakReport],
+) -> Result<String> {
+    let mut sarif = SarifBuilder::new(root_path, source);
+    for report in reports {
+        sarif.add_leaks(report);
+    }
+    sarif.into_json()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn affected_mem_leaks(
     root_path: &Path,
+    scan_options: &ScanOptions,
+    format: OutputFormat,
     base_ref: &str,
+    rename_threshold: u16,
     transitive: bool,
-    project_filter: Option<&str>,
+    project: Option<&str>,
     entity_type_filters: &[String],
     max_findings: usize,
     strict: bool,
     fail_on_findings: bool,
 ) -> Result<()> {
-    let changed_files = get_changed_files(root_path, base_ref)?;
-
-    if changed_files.is_empty() {
-        println!("No changes found between HEAD and '{}'.", base_ref);
-        return Ok(());
-    }
+    let source = open_source(root_path, scan_options)?;
+    let config = load_config(root_path, &source, scan_options)?;
+    let project_filter = config.project_filter(project)?;
 
-    let result = scan_and_parse_files(root_path, false)?;
-    let graph = DependencyGraph::from_entities(&result.entities);
+    let range = CommitRange {
+        head: scan_options.rev.clone(),
+        ..CommitRange::new(base_ref)
+    };
+    let changed_files =
+        get_changed_files(root_path, &range, ChangeScope::Committed, rename_threshold)?;
 
-    let changed_paths: HashSet<String> = changed_files.iter().map(|cf| cf.path.clone()).collect();
+    let mut sorted_affected_files: Vec<String> = Vec::new();
+    let mut reports = Vec::new();
 
-    let mut direct_affected_ids: HashSet<String> = HashSet::new();
-    for entity in result.entities.values() {
-        if changed_paths.contains(&entity.file_path)
-            && matches_project_filter(&entity.file_path, project_filter)
-        {
-            direct_affected_ids.insert(entity.id.clone());
-        }
-    }
+    if !changed_files.is_empty() {
+        let result = scan_and_parse_files(root_path, &config, &source, false)?;
+        let graph = DependencyGraph::from_entities(&result.entities);
 
-    let consumer_ids = graph.find_consumers(&direct_affected_ids, transitive);
+        // Files that are gone cannot be analyzed, but their consumers can
+        let removed_paths: HashSet<&str> = changed_files
+            .iter()
+            .filter_map(|cf| cf.removed_path())
+            .collect();
 
-    let mut affected_non_test_files: HashSet<String> = HashSet::new();
-    for entity in result.entities.values() {
-        let is_affected =
-            direct_affected_ids.contains(&entity.id) || consumer_ids.contains(&entity.id);
-        if is_affected
-            && matches_project_filter(&entity.file_path, project_filter)
-            && entity.file_path.ends_with(".ts")
-            && !is_test_file(&entity.file_path)
-        {
-            affected_non_test_files.insert(entity.file_path.clone());
+        let mut direct_affected_ids: HashSet<String> = HashSet::new();
+        for entity in result.entities.values() {
+            if changed_files.iter().any(|cf| cf.touches(&entity.file_path))
+                && matches_project_filter(root_path, &entity.file_path, project_filter)
+            {
+                direct_affected_ids.insert(entity.id.clone());
+            }
         }
-    }
 
-    println!(
-        "Affected non-test source files scanned: {}",
-        affected_non_test_files.len()
-    );
+        let consumer_ids = graph.find_consumers(&direct_affected_ids, transitive);
+
+        let mut affected_non_test_files: HashSet<String> = HashSet::new();
+        for entity in result.entities.values() {
+            let is_affected =
+                direct_affected_ids.contains(&entity.id) || consumer_ids.contains(&entity.id);
+            if is_affected
+                && matches_project_filter(root_path, &entity.file_path, project_filter)
+                && entity.file_path.ends_with(".ts")
+                && !is_test_file(&entity.file_path)
+                && !removed_paths.contains(entity.file_path.as_str())
+            {
+                affected_non_test_files.insert(entity.file_path.clone());
+            }
+        }
 
-    if affected_non_test_files.is_empty() {
-        println!("No affected non-test TypeScript files found.");
-        return Ok(());
-    }
+        sorted_affected_files = affected_non_test_files.iter().cloned().collect();
+        sorted_affected_files.sort();
 
-    let mut sorted_affected_files: Vec<String> = affected_non_test_files.iter().cloned().collect();
-    sorted_affected_files.sort();
+        let scoped_entities: HashMap<String, Entity> = result
+            .entities
+            .into_iter()
+            .filter(|(_, entity)| affected_non_test_files.contains(&entity.file_path))
+            .collect();
 
-    println!("Showing first 10 affected files:");
-    for file_path in sorted_affected_files.iter().take(10) {
-        println!("{}", file_path);
-    }
-    if sorted_affected_files.len() > 10 {
+        reports = mem_leaks::analyze(&source, &scoped_entities, entity_type_filters, strict);
+    }
+
+    if format == OutputFormat::Sarif {
+        println!("{}", leaks_to_sarif(root_path, &source, &reports)?);
+    } else if !format.is_text() {
+        output::emit(
+            format,
+            &AffectedMemLeaksReport {
+                base: base_ref.to_string(),
+                changed_files: changed_files.len(),
+                files: sorted_affected_files,
+                reports: &reports,
+                total: reports.len(),
+            },
+        )?;
+    } else if changed_files.is_empty() {
         println!(
-            "...and more ({} additional files)",
-            sorted_affected_files.len() - 10
+            "No changes found between {} and '{}'.",
+            range.head_name(),
+            base_ref
+        );
+        return Ok(());
+    } else {
+        println!(
+            "Affected non-test source files scanned: {}",
+            sorted_affected_files.len()
         );
-    }
 
-    let scoped_entities: HashMap<String, Entity> = result
-        .entities
-        .into_iter()
-        .filter(|(_, entity)| affected_non_test_files.contains(&entity.file_path))
-        .collect();
+        if sorted_affected_files.is_empty() {
+            println!("No affected non-test TypeScript files found.");
+            return Ok(());
+        }
+
+        println!("Showing first 10 affected files:");
+        for file_path in sorted_affected_files.iter().take(10) {
+            println!("{}", file_path);
+        }
+        if sorted_affected_files.len() > 10 {
+            println!(
+                "...and more ({} additional files)",
+                sorted_affected_files.len() - 10
+            );
+        }
 
-    let report_count =
-        analyze_mem_leaks_and_print(&scoped_entities, entity_type_filters, max_findings, strict)?;
+        mem_leaks::print_reports(&reports, max_findings);
+    }
 
-    if fail_on_findings && report_count > 0 {
+    if fail_on_findings && !reports.is_empty() {
         anyhow::bail!(
             "Found memory leak risks in {} entities (failing due to --fail-on-findings)",
-            report_count
+            reports.len()
         );
     }
 
@@ -754,7 +2102,11 @@ pub fn affected_mem_leaks(
 
 #[cfg(test)]
 mod tests {
-    use super::parser::{Parser, strip_comments, worker_filename_to_entity_name};
+    use super::config::StingConfig;
+    use super::parser::{
+        Parser, strip_comments, unchanged_exports, worker_filename_to_entity_name,
+    };
+    use super::source::FILESYSTEM;
     use std::path::Path;
 
     #[test]
@@ -763,7 +2115,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/bar.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -777,7 +2129,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 3);
@@ -796,7 +2148,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 3);
@@ -811,7 +2163,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -825,7 +2177,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/bar.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -838,7 +2190,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -855,7 +2207,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -869,7 +2221,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -883,7 +2235,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -897,7 +2249,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -910,7 +2262,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -923,7 +2275,7 @@ mod tests {
         let root_path = Path::new("/project");
         let file_path = "/project/apps/web/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -933,6 +2285,95 @@ mod tests {
         assert!(!imports[0].path.contains("@awork"));
     }
 
+    #[test]
+    fn test_extract_tsconfig_paths_alias_import() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        fs::write(
+            root_path.join("tsconfig.base.json"),
+            r#"{
+  "compilerOptions": {
+    "baseUrl": ".",
+    "paths": {
+      "@org/feature-*": ["libs/feature-*/src/index.ts"]
+    }
+  }
+}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root_path.join("libs/feature-users/src")).unwrap();
+        fs::write(root_path.join("libs/feature-users/src/index.ts"), "").unwrap();
+
+        let content = r#"import { UsersFacade } from '@org/feature-users';
+import { Observable } from 'rxjs';"#;
+        let file_path = root_path.join("apps/web/src/app.ts");
+
+        let parser = Parser::new(&root_path, &StingConfig::default(), &FILESYSTEM);
+        let imports = parser.extract_imports(content, file_path.to_str().unwrap());
+
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].name, "UsersFacade");
+        assert_eq!(
+            imports[0].path,
+            root_path
+                .join("libs/feature-users/src/index.ts")
+                .to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_extract_tsconfig_paths_uses_first_existing_target() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        fs::write(
+            root_path.join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@app/*": ["generated/*", "src/*"] } } }"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root_path.join("src/models")).unwrap();
+        fs::write(root_path.join("src/models/user.ts"), "").unwrap();
+
+        let content = r#"import { User } from '@app/models/user';"#;
+        let file_path = root_path.join("src/app.ts");
+
+        let parser = Parser::new(&root_path, &StingConfig::default(), &FILESYSTEM);
+        let imports = parser.extract_imports(content, file_path.to_str().unwrap());
+
+        assert_eq!(imports.len(), 1);
+        assert_eq!(
+            imports[0].path,
+            root_path.join("src/models/user.ts").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_tsconfig_without_matching_alias_falls_back_to_legacy_alias() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        fs::write(
+            root_path.join("tsconfig.json"),
+            r#"{ "compilerOptions": { "paths": { "@org/*": ["libs/*"] } } }"#,
+        )
+        .unwrap();
+
+        let content = r#"import { Model } from '@awork/models';
+import { Button } from '@vendor/ui';"#;
+        let file_path = root_path.join("src/app.ts");
+
+        let parser = Parser::new(&root_path, &StingConfig::default(), &FILESYSTEM);
+        let imports = parser.extract_imports(content, file_path.to_str().unwrap());
+
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].name, "Model");
+        assert!(imports[0].path.contains("libs/shared/src/lib/models"));
+    }
+
     #[test]
     fn test_skip_external_package_imports() {
         let content = r#"import { useState } from 'react';
@@ -941,7 +2382,7 @@ import { Foo } from './local';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -956,7 +2397,7 @@ import Default from './default';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 4);
@@ -972,7 +2413,7 @@ import Default from './default';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/components/button.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -987,7 +2428,7 @@ import Default from './default';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/bar.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1003,7 +2444,7 @@ import Default from './default';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -1017,7 +2458,7 @@ import Default from './default';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/bar.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1059,6 +2500,18 @@ import Default from './default';"#;
         assert_eq!(result, r#"const a = "/* not a comment */";"#);
     }
 
+    #[test]
+    fn test_declaration_lines_after_jsdoc_header() {
+        use super::parser::{JSDOC_HEADER, declaration_lines};
+
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("a.ts");
+        std::fs::write(&file, format!("{JSDOC_HEADER}export class A {{}}\n")).unwrap();
+
+        let lines = declaration_lines(&FILESYSTEM, file.to_str().unwrap());
+        assert_eq!(lines.get("A"), Some(&13));
+    }
+
     #[test]
     fn test_skip_commented_import() {
         let content = r#"// import { Foo } from './foo';
@@ -1066,7 +2519,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1080,7 +2533,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1097,7 +2550,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1115,7 +2568,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/app-routing.module.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1138,7 +2591,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/app-routing.module.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -1152,7 +2605,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/app-routing.module.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1165,12 +2618,16 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/apps/web/src/app/service.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
         assert_eq!(imports[0].name, "createUiAnnotator");
-        assert!(imports[0].path.contains("libs/ui-annotator/src/lib/create-ui-annotator"));
+        assert!(
+            imports[0]
+                .path
+                .contains("libs/ui-annotator/src/lib/create-ui-annotator")
+        );
     }
 
     #[test]
@@ -1182,7 +2639,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/app/service.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
@@ -1190,6 +2647,642 @@ import { Bar } from './bar';"#;
         assert_eq!(imports[1].name, "Bar");
     }
 
+    #[test]
+    fn test_extract_named_reexports() {
+        use super::parser::ReExport;
+
+        let content = r#"export { Foo, Bar as Baz } from './foo';
+export type { Model } from './model';"#;
+        let root_path = Path::new("/project");
+        let file_path = "/project/libs/x/index.ts";
+
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
+        let reexports = parser.extract_reexports(content, file_path);
+
+        assert_eq!(reexports.len(), 3);
+        assert!(matches!(
+            &reexports[0],
+            ReExport::Named { exported, imported, path }
+                if exported == "Foo" && imported == "Foo" && path.ends_with("foo.ts")
+        ));
+        assert!(matches!(
+            &reexports[1],
+            ReExport::Named { exported, imported, .. } if exported == "Baz" && imported == "Bar"
+        ));
+        assert!(matches!(
+            &reexports[2],
+            ReExport::Named { exported, path, .. } if exported == "Model" && path.ends_with("model.ts")
+        ));
+    }
+
+    #[test]
+    fn test_extract_star_reexports() {
+        use super::parser::ReExport;
+
+        let content = r#"export * from './lib/services';
+export * as utils from './lib/utils';
+// export * from './commented';"#;
+        let root_path = Path::new("/project");
+        let file_path = "/project/libs/x/index.ts";
+
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
+        let reexports = parser.extract_reexports(content, file_path);
+
+        assert_eq!(reexports.len(), 2);
+        assert!(matches!(
+            &reexports[0],
+            ReExport::All { path } if path.ends_with("lib/services.ts")
+        ));
+        assert!(matches!(
+            &reexports[1],
+            ReExport::Namespace { exported, path } if exported == "utils" && path.ends_with("lib/utils.ts")
+        ));
+    }
+
+    #[test]
+    fn test_extract_reexports_skips_external_packages() {
+        let content = r#"export * from 'rxjs';
+export { Injectable } from '@angular/core';"#;
+        let root_path = Path::new("/project");
+        let file_path = "/project/libs/x/index.ts";
+
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
+        let reexports = parser.extract_reexports(content, file_path);
+
+        assert!(reexports.is_empty());
+    }
+
+    #[test]
+    fn test_scan_uses_sting_toml_layout() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let write = |rel: &str, content: &str| {
+            let path = root_path.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write(
+            "sting.toml",
+            r#"
+[projects.admin]
+root = "apps/admin"
+exclude = ["**/legacy/**"]
+
+[projects.shared]
+root = "packages/shared"
+
+[aliases]
+"@shared/*" = "packages/shared/src/*"
+
+[entities.decorators]
+Store = "service"
+"#,
+        );
+        write(
+            "packages/shared/src/user.store.ts",
+            "@Store()\nexport class UserStore {}\n",
+        );
+        write(
+            "apps/admin/src/app.ts",
+            "import { UserStore } from '@shared/user.store';\nexport class App {\n  store: UserStore;\n}\n",
+        );
+        write("apps/admin/src/legacy/old.ts", "export class Old {}\n");
+        write("apps/web/src/ignored.ts", "export class Ignored {}\n");
+
+        let config = StingConfig::load(&root_path, &FILESYSTEM).unwrap();
+        let result = super::scan_and_parse_files(&root_path, &config, &FILESYSTEM, false).unwrap();
+
+        let store = result
+            .entities
+            .values()
+            .find(|e| e.name == "UserStore")
+            .unwrap();
+        assert!(matches!(store.entity_type, super::EntityType::Service));
+        assert!(store.used);
+
+        let app = result.entities.values().find(|e| e.name == "App").unwrap();
+        assert_eq!(app.deps.len(), 1);
+        assert_eq!(app.deps[0].id, store.id);
+
+        assert!(!result.entities.values().any(|e| e.name == "Old"));
+        assert!(!result.entities.values().any(|e| e.name == "Ignored"));
+    }
+
+    #[test]
+    fn test_scan_resolves_imports_through_barrels() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let write = |rel: &str, content: &str| {
+            let path = root_path.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write(
+            "libs/x/index.ts",
+            "export * from './src';
+",
+        );
+        write(
+            "libs/x/src/index.ts",
+            "export { FooService } from './foo.service';
+",
+        );
+        write(
+            "libs/x/src/foo.service.ts",
+            "@Injectable()\nexport class FooService {}\n",
+        );
+        write(
+            "apps/web/src/app.component.ts",
+            "import { FooService } from '../../../libs/x';\n\n@Component({})\nexport class AppComponent {\n  constructor(foo: FooService) {}\n}\n",
+        );
+
+        let result =
+            super::scan_and_parse_files(&root_path, &StingConfig::default(), &FILESYSTEM, false)
+                .unwrap();
+
+        let service_path = root_path
+            .join("libs/x/src/foo.service.ts")
+            .to_string_lossy()
+            .to_string();
+        let service = result
+            .entities
+            .values()
+            .find(|e| e.name == "FooService" && e.file_path == service_path)
+            .unwrap();
+        assert!(service.used);
+
+        let app = result
+            .entities
+            .values()
+            .find(|e| e.name == "AppComponent")
+            .unwrap();
+        assert_eq!(app.deps.len(), 1);
+        assert_eq!(app.deps[0].id, service.id);
+
+        assert!(
+            !result
+                .entities
+                .values()
+                .any(|e| e.name == "FooService" && e.file_path != service_path)
+        );
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_unchanged_exports_follow_references() {
+        let temp = tempfile::tempdir().unwrap();
+        let file_path = temp.path().join("models.ts");
+        std::fs::write(
+            &file_path,
+            "const PREFIX = 'x';\n\nexport function label() {\n  return PREFIX;\n}\n\nexport interface User {}\n",
+        )
+        .unwrap();
+        let file_path = file_path.to_str().unwrap();
+
+        // Changing the private constant changes the function using it
+        let unchanged = unchanged_exports(&FILESYSTEM, file_path, &[1..2]);
+        assert_eq!(
+            unchanged,
+            std::collections::HashSet::from(["User".to_string()])
+        );
+
+        let unchanged = unchanged_exports(&FILESYSTEM, file_path, &[7..8]);
+        assert_eq!(
+            unchanged,
+            std::collections::HashSet::from(["label".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_scan_collects_test_imports() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let write = |rel: &str, content: &str| {
+            let path = root_path.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write("sting.toml", "[projects.core]\nroot = \"core\"\n");
+        write("core/a.ts", "export class A {}\n");
+        write("core/a.spec.ts", "import { A } from './a';\nnew A();\n");
+        write(
+            "core/tests/flow.spec.ts",
+            "import { A } from '../a';\nexport class Page {}\nnew A();\n",
+        );
+
+        let scan_options = super::ScanOptions::default();
+        let config = super::load_config(&root_path, &FILESYSTEM, &scan_options).unwrap();
+        let result = super::scan_and_parse_files(&root_path, &config, &FILESYSTEM, false).unwrap();
+
+        // Tests below excluded directories are no entities
+        let mut names: Vec<&str> = result.entities.values().map(|e| e.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["A"]);
+
+        let mut tests: Vec<&String> = result.test_imports.keys().collect();
+        tests.sort();
+        assert_eq!(
+            tests,
+            vec![
+                &root_path
+                    .join("core/a.spec.ts")
+                    .to_string_lossy()
+                    .to_string(),
+                &root_path
+                    .join("core/tests/flow.spec.ts")
+                    .to_string_lossy()
+                    .to_string()
+            ]
+        );
+        assert!(
+            result
+                .test_imports
+                .values()
+                .all(|imports| imports.len() == 1 && imports[0].name == "A")
+        );
+    }
+
+    #[test]
+    fn test_scan_reads_files_at_revision() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let write = |rel: &str, content: &str| {
+            let path = root_path.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write("sting.toml", "[projects.core]\nroot = \"core\"\n");
+        write(
+            "core/a.ts",
+            "import { B } from './b';\nexport class A {\n  b: B;\n}\n",
+        );
+        write("core/b.ts", "export class B {}\n");
+
+        let repo = git2::Repository::init(&root_path).unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::now("Test", "test@test.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
+            .unwrap();
+
+        // The working directory no longer matches the commit
+        fs::remove_file(root_path.join("sting.toml")).unwrap();
+        write("core/a.ts", "export class A {}\n");
+        write("core/c.ts", "export class C {}\n");
+
+        let scan_options = super::ScanOptions {
+            rev: Some("HEAD".to_string()),
+            ..Default::default()
+        };
+        let source = super::open_source(&root_path, &scan_options).unwrap();
+        let config = super::load_config(&root_path, &source, &scan_options).unwrap();
+        let result = super::scan_and_parse_files(&root_path, &config, &source, false).unwrap();
+
+        let mut names: Vec<&str> = result.entities.values().map(|e| e.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["A", "B"]);
+
+        let a = result.entities.values().find(|e| e.name == "A").unwrap();
+        assert_eq!(a.deps.len(), 1);
+        assert_eq!(a.deps[0].name, "B");
+    }
+
+    #[test]
+    fn test_parse_attributes_imports_per_entity() {
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let file = root_path.join("entities.ts");
+        fs::write(
+            &file,
+            r#"import { A } from './a';
+import { B as LocalB } from './b';
+import { C } from './c';
+
+function helper() {
+  return new LocalB();
+}
+
+export class UsesA {
+  a: A;
+}
+
+export const usesHelper = () => helper();
+
+export function usesSibling() {
+  return new UsesA();
+}
+
+export const lazy = () => import('./d').then((m) => m.D);
+
+registerGlobal(C);
+"#,
+        )
+        .unwrap();
+
+        let parser = Parser::new(&root_path, &StingConfig::default(), &FILESYSTEM);
+        let result = parser.parse(file.to_str().unwrap()).unwrap();
+        let deps_of = |name: &str| {
+            let entity = result.entities.iter().find(|e| e.name == name).unwrap();
+            let mut names: Vec<String> = entity.deps.iter().map(|d| d.name.clone()).collect();
+            names.sort();
+            names
+        };
+
+        // C is only used by a top-level statement: file-level fallback
+        assert_eq!(deps_of("UsesA"), vec!["A", "C"]);
+        assert_eq!(deps_of("usesHelper"), vec!["B", "C"]);
+        assert_eq!(deps_of("usesSibling"), vec!["C", "UsesA"]);
+        assert_eq!(deps_of("lazy"), vec!["C", "D"]);
+    }
+
+    #[test]
+    fn test_parse_records_import_kinds() {
+        use crate::entity::EdgeKind;
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let file = root_path.join("routes.ts");
+        fs::write(
+            &file,
+            r#"import type { User } from './user';
+import { type Role, Guard } from './auth';
+
+export const routes = [
+  { path: 'a', canActivate: [Guard], loadComponent: () => import('./a').then((m) => m.AComponent) },
+];
+
+export async function load(user: User, role: Role) {
+  const { Chart } = await import('./chart');
+  return new Worker(new URL('./calc.worker', import.meta.url));
+}
+"#,
+        )
+        .unwrap();
+
+        let parser = Parser::new(&root_path, &StingConfig::default(), &FILESYSTEM);
+        let result = parser.parse(file.to_str().unwrap()).unwrap();
+        let kinds_of = |name: &str| {
+            let entity = result.entities.iter().find(|e| e.name == name).unwrap();
+            let mut kinds: Vec<(String, EdgeKind)> = entity
+                .deps
+                .iter()
+                .map(|d| (d.name.clone(), d.kind))
+                .collect();
+            kinds.sort_by(|a, b| a.0.cmp(&b.0));
+            kinds
+        };
+
+        assert_eq!(
+            kinds_of("routes"),
+            vec![
+                ("AComponent".to_string(), EdgeKind::Lazy),
+                ("Guard".to_string(), EdgeKind::Import),
+            ]
+        );
+        assert_eq!(
+            kinds_of("load"),
+            vec![
+                ("CalcWorker".to_string(), EdgeKind::Worker),
+                ("Chart".to_string(), EdgeKind::Dynamic),
+                ("Role".to_string(), EdgeKind::Type),
+                ("User".to_string(), EdgeKind::Type),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edge_filter_drops_skipped_kinds() {
+        use crate::entity::{EdgeKind, Entity, EntityType, ImportInfo};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let dep = |name: &str, kind: EdgeKind| ImportInfo {
+            kind,
+            ..ImportInfo::new(name.to_string(), "/b.ts".to_string())
+        };
+        let entity = Entity::new(
+            "A".to_string(),
+            EntityType::Class,
+            "/a.ts".to_string(),
+            Arc::new(vec![dep("B", EdgeKind::Type), dep("C", EdgeKind::Import)]),
+        );
+        let mut result = super::ScanResult {
+            entities: HashMap::from([(entity.id.clone(), entity)]),
+            test_imports: HashMap::from([(
+                "/a.spec.ts".to_string(),
+                vec![dep("B", EdgeKind::Type)],
+            )]),
+            routes: Vec::new(),
+            reexports: HashMap::new(),
+        };
+
+        let filter = super::EdgeFilter {
+            skip: vec![EdgeKind::Type],
+            ..Default::default()
+        };
+        filter.apply(&mut result);
+
+        let deps: Vec<&str> = result
+            .entities
+            .values()
+            .flat_map(|e| e.deps.iter().map(|d| d.name.as_str()))
+            .collect();
+        assert_eq!(deps, vec!["C"]);
+        assert!(result.test_imports["/a.spec.ts"].is_empty());
+        assert!(
+            !super::EdgeFilter {
+                only: vec![EdgeKind::Di],
+                ..Default::default()
+            }
+            .allows(EdgeKind::Import)
+        );
+    }
+
+    #[test]
+    fn test_scan_builds_route_tree() {
+        use crate::graph::DependencyGraph;
+        use crate::routes;
+        use std::collections::HashSet;
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let write = |rel: &str, content: &str| {
+            let path = root_path.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write(
+            "apps/web/src/app.routes.ts",
+            r#"import { Routes } from '@angular/router';
+import { ShellComponent } from './shell.component';
+import { authGuard } from './auth.guard';
+
+export const routes: Routes = [
+  { path: '', component: ShellComponent },
+  {
+    path: 'admin',
+    canActivate: [authGuard],
+    loadChildren: () => import('./admin/admin.routes').then((m) => m.ADMIN_ROUTES),
+  },
+  { path: 'legacy', loadChildren: () => import('./legacy/legacy.module').then((m) => m.LegacyModule) },
+];
+"#,
+        );
+        write(
+            "apps/web/src/shell.component.ts",
+            "@Component({})\nexport class ShellComponent {}\n",
+        );
+        write(
+            "apps/web/src/auth.guard.ts",
+            "export const authGuard = () => true;\n",
+        );
+        write(
+            "apps/web/src/admin/admin.routes.ts",
+            r#"import { Routes } from '@angular/router';
+import { UsersComponent } from './users.component';
+
+export const ADMIN_ROUTES: Routes = [
+  { path: '', loadComponent: () => import('./admin.component') },
+  { path: 'users', component: UsersComponent },
+];
+"#,
+        );
+        write(
+            "apps/web/src/admin/admin.component.ts",
+            "@Component({})\nexport default class AdminComponent {}\n",
+        );
+        write(
+            "apps/web/src/admin/users.component.ts",
+            "import { UserApi } from './user.api';\n\n@Component({})\nexport class UsersComponent {\n  constructor(api: UserApi) {}\n}\n",
+        );
+        write(
+            "apps/web/src/admin/user.api.ts",
+            "@Injectable()\nexport class UserApi {}\n",
+        );
+        write(
+            "apps/web/src/legacy/legacy.module.ts",
+            "import { LegacyRoutingModule } from './legacy-routing.module';\n\n@NgModule({ imports: [LegacyRoutingModule] })\nexport class LegacyModule {}\n",
+        );
+        write(
+            "apps/web/src/legacy/legacy-routing.module.ts",
+            r#"import { LegacyComponent } from './legacy.component';
+
+const routes: Routes = [{ path: 'old', component: LegacyComponent }];
+
+@NgModule({ imports: [RouterModule.forChild(routes)] })
+export class LegacyRoutingModule {}
+"#,
+        );
+        write(
+            "apps/web/src/legacy/legacy.component.ts",
+            "@Component({})\nexport class LegacyComponent {}\n",
+        );
+
+        let result =
+            super::scan_and_parse_files(&root_path, &StingConfig::default(), &FILESYSTEM, false)
+                .unwrap();
+        let tree = routes::route_tree(&result.routes, &result.entities);
+        let nodes: Vec<(&str, Option<&str>, bool)> = routes::flatten(&tree)
+            .into_iter()
+            .map(|n| (n.path.as_str(), n.component.as_deref(), n.lazy))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                ("/", Some("ShellComponent"), false),
+                ("/admin", None, false),
+                ("/admin", Some("AdminComponent"), true),
+                ("/admin/users", Some("UsersComponent"), true),
+                ("/legacy", None, false),
+                ("/legacy/old", Some("LegacyComponent"), true),
+            ]
+        );
+        assert_eq!(tree[1].guards, vec!["authGuard"]);
+
+        let api = result
+            .entities
+            .values()
+            .find(|e| e.name == "UserApi")
+            .unwrap();
+        let graph = DependencyGraph::from_entities(&result.entities);
+        let (affected, by_entity) =
+            routes::affected_routes(&tree, &graph, &HashSet::from([api.id.clone()]));
+        let paths: Vec<&str> = affected.iter().map(|n| n.path.as_str()).collect();
+        assert_eq!(paths, vec!["/admin/users"]);
+        assert_eq!(by_entity[&api.id], vec!["/admin/users"]);
+    }
+
+    #[test]
+    fn test_parse_marks_injected_dependencies() {
+        use crate::entity::EdgeKind;
+        use std::fs;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root_path = temp.path().canonicalize().unwrap();
+        let file = root_path.join("user.component.ts");
+        fs::write(
+            &file,
+            r#"import { AuthService } from './auth.service';
+import { User } from './user';
+import { Logger as Log } from './logger';
+
+export const API_URL = new InjectionToken<string>('api');
+
+@Component({ providers: [{ provide: Log, useClass: ConsoleLogger }] })
+export class UserComponent {
+  private url = inject(API_URL);
+  user: User;
+  constructor(private auth: AuthService) {}
+}
+"#,
+        )
+        .unwrap();
+
+        let parser = Parser::new(&root_path, &StingConfig::default(), &FILESYSTEM);
+        let result = parser.parse(file.to_str().unwrap()).unwrap();
+        let component = result
+            .entities
+            .iter()
+            .find(|e| e.name == "UserComponent")
+            .unwrap();
+        let mut deps: Vec<(&str, EdgeKind)> = component
+            .deps
+            .iter()
+            .map(|d| (d.name.as_str(), d.kind))
+            .collect();
+        deps.sort_by_key(|(name, _)| *name);
+
+        assert_eq!(
+            deps,
+            vec![
+                ("API_URL", EdgeKind::Di),
+                ("AuthService", EdgeKind::Di),
+                ("Logger", EdgeKind::Di),
+                ("User", EdgeKind::Import),
+            ]
+        );
+    }
+
     #[test]
     fn test_is_test_file_spec_ts() {
         assert!(super::is_test_file("/path/to/foo.spec.ts"));
@@ -1201,6 +3294,7 @@ import { Bar } from './bar';"#;
         assert!(super::is_test_file("/path/to/foo.test.ts"));
         assert!(super::is_test_file("foo.test.ts"));
         assert!(super::is_test_file("/path/to/foo.e2e.ts"));
+        assert!(super::is_test_file("/path/to/foo-test.ts"));
     }
 
     #[test]
@@ -1230,7 +3324,7 @@ import { Bar } from './bar';"#;
         let mut dirs: HashSet<String> = HashSet::new();
         dirs.insert(temp_dir.to_string_lossy().to_string());
 
-        let result = super::find_test_files_in_directories(&dirs);
+        let result = super::find_test_files_in_directories(&super::source::FILESYSTEM, &dirs);
 
         assert_eq!(result.len(), 2);
         assert!(result.iter().any(|p| p.ends_with("foo.spec.ts")));
@@ -1248,7 +3342,7 @@ import { Bar } from './bar';"#;
         let mut dirs: HashSet<String> = HashSet::new();
         dirs.insert("/nonexistent/path/that/does/not/exist".to_string());
 
-        let result = super::find_test_files_in_directories(&dirs);
+        let result = super::find_test_files_in_directories(&super::source::FILESYSTEM, &dirs);
 
         assert!(result.is_empty());
     }
@@ -1289,7 +3383,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/components/planning.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1304,7 +3398,7 @@ import { Bar } from './bar';"#;
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 1);
@@ -1320,11 +3414,64 @@ const worker2 = new Worker(new URL('./worker-b.worker', import.meta.url));
         let root_path = Path::new("/project");
         let file_path = "/project/src/index.ts";
 
-        let parser = Parser::new(root_path);
+        let parser = Parser::new(root_path, &StingConfig::default(), &FILESYSTEM);
         let imports = parser.extract_imports(content, file_path);
 
         assert_eq!(imports.len(), 2);
         assert_eq!(imports[0].name, "WorkerAWorker");
         assert_eq!(imports[1].name, "WorkerBWorker");
     }
+
+    #[test]
+    fn test_resolve_entities_by_name_and_file() {
+        use super::entity::{Entity, EntityType};
+        use super::resolve_entities;
+        use super::search::EntityFilter;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        std::fs::write(root.join("a.ts"), "").unwrap();
+        let a_file = root.join("a.ts").to_str().unwrap().to_string();
+
+        let mut entities = HashMap::new();
+        for (name, file) in [
+            ("Api", a_file.as_str()),
+            ("ApiClient", a_file.as_str()),
+            ("Api", "/elsewhere/b.ts"),
+        ] {
+            let entity = Entity::new(
+                name.to_string(),
+                EntityType::Class,
+                file.to_string(),
+                Arc::new(Vec::new()),
+            );
+            entities.insert(entity.id.clone(), entity);
+        }
+
+        let filter = EntityFilter::new(&root, &[], None, None).unwrap();
+        let names = |query: &str| -> Vec<String> {
+            resolve_entities(&root, &FILESYSTEM, &entities, query, &filter)
+                .unwrap()
+                .iter()
+                .map(|e| e.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names("ApiClient"), vec!["ApiClient"]);
+        assert_eq!(names("a.ts"), vec!["Api", "ApiClient"]);
+
+        let ambiguous =
+            resolve_entities(&root, &FILESYSTEM, &entities, "Api", &filter).unwrap_err();
+        assert!(ambiguous.to_string().contains("matches 2 entities"));
+
+        let missing =
+            resolve_entities(&root, &FILESYSTEM, &entities, "ApiClnt", &filter).unwrap_err();
+        assert!(missing.to_string().contains("Did you mean"));
+
+        let filter = EntityFilter::new(&root, &[], None, Some("a.ts")).unwrap();
+        let narrowed = resolve_entities(&root, &FILESYSTEM, &entities, "Api", &filter).unwrap();
+        assert_eq!(narrowed[0].file_path, a_file);
+    }
 }
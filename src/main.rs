@@ -4,7 +4,7 @@ mod skill_installer;
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use args::{Commands, SkillCommands, StingArgs};
+use args::{CacheCommands, Commands, SkillCommands, StingArgs};
 use clap::Parser;
 use skill_installer::install_skill;
 
@@ -33,57 +33,149 @@ fn entity_type_filters_from(types: &[args::GraphEntityType]) -> Vec<String> {
         .collect()
 }
 
+fn edge_filter_from(args: &args::EdgeFilterArgs) -> sting::EdgeFilter {
+    let kinds = |kinds: &[args::EdgeKind]| {
+        kinds
+            .iter()
+            .map(|kind| match kind {
+                args::EdgeKind::Import => sting::EdgeKind::Import,
+                args::EdgeKind::Type => sting::EdgeKind::Type,
+                args::EdgeKind::Lazy => sting::EdgeKind::Lazy,
+                args::EdgeKind::Dynamic => sting::EdgeKind::Dynamic,
+                args::EdgeKind::Worker => sting::EdgeKind::Worker,
+                args::EdgeKind::Template => sting::EdgeKind::Template,
+                args::EdgeKind::Di => sting::EdgeKind::Di,
+            })
+            .collect()
+    };
+
+    sting::EdgeFilter {
+        only: kinds(&args.edge_kind),
+        skip: kinds(&args.skip_edge_kind),
+    }
+}
+
 fn main() -> Result<()> {
     let cli = StingArgs::parse();
 
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .context("Unable to set up the thread pool")?;
+    }
+
+    let scan_options = sting::ScanOptions {
+        include: cli.scan.include.clone(),
+        exclude: cli.scan.exclude.clone(),
+        no_ignore: cli.scan.no_ignore,
+        verbose: cli.scan.verbose,
+        no_cache: cli.scan.no_cache,
+        rev: cli.scan.rev.clone(),
+    };
+    let format = match cli.format {
+        args::OutputFormat::Text => sting::OutputFormat::Text,
+        args::OutputFormat::Json => sting::OutputFormat::Json,
+        args::OutputFormat::Ndjson => sting::OutputFormat::Ndjson,
+        args::OutputFormat::Sarif => sting::OutputFormat::Sarif,
+        args::OutputFormat::Markdown => sting::OutputFormat::Markdown,
+    };
+
     match &cli.command {
         Commands::QueryAll(args) => {
             let path = canonicalize_path(&args.path)?;
 
-            sting::query_all(&path)
+            sting::query_all(&path, &scan_options, format)
                 .with_context(|| format!("Unable to query in path: {}", path.display()))?
         }
         Commands::Query(args) => {
             let path = canonicalize_path(&args.path)?;
 
-            sting::query(&path, &args.query)
-                .with_context(|| format!("Unable to query in path: {}", path.display()))?
+            let entity_type_filters = entity_type_filters_from(&args.entity_type);
+
+            sting::query(
+                &path,
+                &scan_options,
+                format,
+                &args.query,
+                &entity_type_filters,
+                args.project.as_deref(),
+                args.file.as_deref(),
+                args.limit,
+            )
+            .with_context(|| format!("Unable to query in path: {}", path.display()))?
+        }
+        Commands::Explain(args) => {
+            let path = canonicalize_path(&args.path)?;
+
+            sting::explain(
+                &path,
+                &scan_options,
+                format,
+                &args.entity,
+                args.file.as_deref(),
+                args.base.as_deref(),
+                args.max_cycles,
+                args.max_depth,
+                args.max_findings,
+                args.strict,
+            )
+            .with_context(|| format!("Unable to explain entity in path: {}", path.display()))?;
         }
         Commands::Unused(args) => {
             let path = canonicalize_path(&args.path)?;
 
-            sting::unused(&path, args.fail_on_findings).with_context(|| {
-                format!("Unable to find unused entities in path: {}", path.display())
-            })?
+            sting::unused(&path, &scan_options, format, args.fail_on_findings).with_context(
+                || format!("Unable to find unused entities in path: {}", path.display()),
+            )?
         }
         Commands::Graph(args) => {
             let path = canonicalize_path(&args.path)?;
 
             let entity_type_filters = entity_type_filters_from(&args.entity_type);
 
-            let json = sting::graph_json(&path, &entity_type_filters).with_context(|| {
-                format!("Unable to generate graph for path: {}", path.display())
-            })?;
+            let json = sting::graph_json(&path, &scan_options, format, &entity_type_filters)
+                .with_context(|| {
+                    format!("Unable to generate graph for path: {}", path.display())
+                })?;
 
             println!("{}", json);
         }
         Commands::Affected(args) => {
             let path = canonicalize_path(&args.path)?;
 
-            let project_filter = args.project.as_ref().map(|p| match p {
-                args::ProjectType::Web => "apps/web/",
-                args::ProjectType::Mobile => "apps/mobile/",
-                args::ProjectType::Libs => "libs/",
-            });
+            let scope = if args.include_uncommitted {
+                sting::ChangeScope::Uncommitted
+            } else if args.staged {
+                sting::ChangeScope::Staged
+            } else {
+                sting::ChangeScope::Committed
+            };
 
             sting::affected(
                 &path,
-                &args.base,
-                args.transitive,
-                args.paths,
-                args.tests,
-                args.summary,
-                project_filter,
+                &scan_options,
+                format,
+                &sting::AffectedOptions {
+                    base: args.base.clone(),
+                    head: args.head.clone(),
+                    scope,
+                    rename_threshold: args.rename_threshold,
+                    precise: args.precise,
+                    transitive: args.transitive,
+                    paths_only: args.paths,
+                    tests_only: args.tests,
+                    test_dirs: args.test_dirs,
+                    runner: args.runner.map(|runner| match runner {
+                        args::TestRunner::Jest => sting::TestRunner::Jest,
+                        args::TestRunner::Vitest => sting::TestRunner::Vitest,
+                        args::TestRunner::Nx => sting::TestRunner::Nx,
+                    }),
+                    routes_only: args.routes,
+                    summary: args.summary,
+                    project: args.project.clone(),
+                    edges: edge_filter_from(&args.edges),
+                },
             )
             .with_context(|| {
                 format!(
@@ -92,24 +184,54 @@ fn main() -> Result<()> {
                 )
             })?;
         }
+        Commands::GraphDiff(args) => {
+            let path = canonicalize_path(&args.path)?;
+
+            sting::graph_diff(
+                &path,
+                &scan_options,
+                format,
+                &args.base,
+                args.head.as_deref(),
+                args.max_cycles,
+                args.max_depth,
+            )
+            .with_context(|| format!("Unable to diff the graph in path: {}", path.display()))?;
+        }
         Commands::Chain(args) => {
             let path = canonicalize_path(&args.path)?;
 
             sting::chain(
                 &path,
+                &scan_options,
+                format,
                 &args.start,
                 &args.end,
                 args.shortest,
                 args.max_paths,
                 args.max_depth,
+                &edge_filter_from(&args.edges),
             )
             .with_context(|| format!("Unable to find chain in path: {}", path.display()))?;
         }
         Commands::Cycles(args) => {
             let path = canonicalize_path(&args.path)?;
 
-            sting::cycles(&path, args.max_cycles, args.max_depth)
-                .with_context(|| format!("Unable to detect cycles in path: {}", path.display()))?;
+            sting::cycles(
+                &path,
+                &scan_options,
+                format,
+                args.max_cycles,
+                args.max_depth,
+                &edge_filter_from(&args.edges),
+            )
+            .with_context(|| format!("Unable to detect cycles in path: {}", path.display()))?;
+        }
+        Commands::Routes(args) => {
+            let path = canonicalize_path(&args.path)?;
+
+            sting::routes(&path, &scan_options, format)
+                .with_context(|| format!("Unable to list routes in path: {}", path.display()))?;
         }
         Commands::Rank(args) => {
             let path = canonicalize_path(&args.path)?;
@@ -118,9 +240,10 @@ fn main() -> Result<()> {
 
             match args.by {
                 args::RankBy::Deps => {
-                    sting::rank_by_deps(&path, &entity_type_filters).with_context(|| {
-                        format!("Unable to rank entities in path: {}", path.display())
-                    })?;
+                    sting::rank_by_deps(&path, &scan_options, format, &entity_type_filters)
+                        .with_context(|| {
+                            format!("Unable to rank entities in path: {}", path.display())
+                        })?;
                 }
             }
         }
@@ -129,25 +252,30 @@ fn main() -> Result<()> {
 
             let entity_type_filters = entity_type_filters_from(&args.entity_type);
 
-            sting::mem_leaks(&path, &entity_type_filters, args.max_findings, args.strict)
-                .with_context(|| {
-                    format!("Unable to analyze memory leaks in path: {}", path.display())
-                })?;
+            sting::mem_leaks(
+                &path,
+                &scan_options,
+                format,
+                &entity_type_filters,
+                args.max_findings,
+                args.strict,
+            )
+            .with_context(|| {
+                format!("Unable to analyze memory leaks in path: {}", path.display())
+            })?;
         }
         Commands::AffectedMemLeaks(args) => {
             let path = canonicalize_path(&args.path)?;
-            let project_filter = args.project.as_ref().map(|p| match p {
-                args::ProjectType::Web => "apps/web/",
-                args::ProjectType::Mobile => "apps/mobile/",
-                args::ProjectType::Libs => "libs/",
-            });
             let entity_type_filters = entity_type_filters_from(&args.entity_type);
 
             sting::affected_mem_leaks(
                 &path,
+                &scan_options,
+                format,
                 &args.base,
+                args.rename_threshold,
                 args.transitive,
-                project_filter,
+                args.project.as_deref(),
                 &entity_type_filters,
                 args.max_findings,
                 args.strict,
@@ -160,6 +288,15 @@ fn main() -> Result<()> {
                 )
             })?;
         }
+        Commands::Cache(args) => match &args.command {
+            CacheCommands::Clear(clear_args) => {
+                let path = canonicalize_path(&clear_args.path)?;
+
+                sting::clear_cache(&path).with_context(|| {
+                    format!("Unable to clear the cache in path: {}", path.display())
+                })?;
+            }
+        },
         Commands::Skill(args) => match &args.command {
             SkillCommands::Install(install_args) => {
                 install_skill(install_args.path.as_deref(), install_args.yes)?;
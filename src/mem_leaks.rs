@@ -1,14 +1,16 @@
 use std::collections::{HashMap, HashSet};
-use std::fs;
+use std::path::Path;
 
-use anyhow::Result;
 use regex::Regex;
+use serde::Serialize;
 
 use crate::entity::{Entity, EntityType};
-use crate::parser::strip_comments;
+use crate::parser::{Declaration, SourceFile, Token, code_only, tokenize};
+use crate::source::FileSource;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
-enum Severity {
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
     Low,
     Medium,
     High,
@@ -24,20 +26,23 @@ impl std::fmt::Display for Severity {
     }
 }
 
-#[derive(Clone, Debug)]
-struct LeakFinding {
-    severity: Severity,
-    line: usize,
-    kind: &'static str,
-    message: String,
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct LeakFinding {
+    pub severity: Severity,
+    pub line: usize,
+    pub kind: &'static str,
+    pub message: String,
 }
 
-#[derive(Debug)]
-struct EntityLeakReport {
-    name: String,
-    entity_type: String,
-    file_path: String,
-    findings: Vec<LeakFinding>,
+#[derive(Debug, Serialize)]
+pub(crate) struct EntityLeakReport {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    #[serde(rename = "file")]
+    pub file_path: String,
+    pub severity: Severity,
+    pub findings: Vec<LeakFinding>,
 }
 
 #[derive(Clone, Debug)]
@@ -72,12 +77,14 @@ impl AutoUnsubscribeConfig {
     }
 }
 
-pub(crate) fn analyze_and_print(
+/// Analyzes the files of the given entities, returning the entities with
+/// findings, most findings first.
+pub(crate) fn analyze(
+    source: &FileSource,
     entities: &HashMap<String, Entity>,
     entity_type_filters: &[String],
-    max_findings: usize,
     strict: bool,
-) -> Result<usize> {
+) -> Vec<EntityLeakReport> {
     let mut entities_by_file: HashMap<String, Vec<&Entity>> = HashMap::new();
 
     for entity in entities.values() {
@@ -95,7 +102,7 @@ pub(crate) fn analyze_and_print(
     let mut reports: Vec<EntityLeakReport> = Vec::new();
 
     for (file_path, file_entities) in entities_by_file {
-        let content = match fs::read_to_string(&file_path) {
+        let content = match source.read_to_string(Path::new(&file_path)) {
             Ok(c) => c,
             Err(e) => {
                 eprintln!("Warning: Could not read file {}: {}", file_path, e);
@@ -103,7 +110,6 @@ pub(crate) fn analyze_and_print(
             }
         };
 
-        let content = strip_comments(&content);
         let mut findings_by_entity = analyze_file_by_entity(&content, &file_entities, strict);
 
         for entity in file_entities {
@@ -117,36 +123,39 @@ pub(crate) fn analyze_and_print(
                 name: entity.name.clone(),
                 entity_type: entity.entity_type.to_string(),
                 file_path: entity.file_path.clone(),
+                severity: top_severity(&findings),
                 findings,
             });
         }
     }
 
-    if reports.is_empty() {
-        println!("No potential memory leaks detected.");
-        return Ok(0);
-    }
-
     reports.sort_by(|a, b| {
         b.findings
             .len()
             .cmp(&a.findings.len())
-            .then(top_severity(&b.findings).cmp(&top_severity(&a.findings)))
+            .then(b.severity.cmp(&a.severity))
             .then(a.name.cmp(&b.name))
+            .then(a.file_path.cmp(&b.file_path))
     });
 
-    let report_count = reports.len();
+    reports
+}
+
+pub(crate) fn print_reports(reports: &[EntityLeakReport], max_findings: usize) {
+    if reports.is_empty() {
+        println!("No potential memory leaks detected.");
+        return;
+    }
 
     println!(
         "Found potential memory leak risks in {} entities:\n",
-        report_count
+        reports.len()
     );
     for report in reports {
-        let top = top_severity(&report.findings);
         println!(
             "{}\t{}\t{}\t{}\t{}",
             report.findings.len(),
-            top,
+            report.severity,
             report.name,
             report.entity_type,
             report.file_path
@@ -166,8 +175,6 @@ pub(crate) fn analyze_and_print(
             );
         }
     }
-
-    Ok(report_count)
 }
 
 fn top_severity(findings: &[LeakFinding]) -> Severity {
@@ -178,50 +185,86 @@ fn top_severity(findings: &[LeakFinding]) -> Severity {
         .unwrap_or(Severity::Low)
 }
 
+/// Observers that keep observing until they are disconnected.
+const OBSERVERS: &[&str] = &[
+    "MutationObserver",
+    "ResizeObserver",
+    "IntersectionObserver",
+    "PerformanceObserver",
+];
+
+/// Finds the leaks of each entity within its declaration. Comments and
+/// template literal text are blanked first, so that neither yields
+/// findings while lines stay those of the file.
 fn analyze_file_by_entity(
     content: &str,
     file_entities: &[&Entity],
     strict: bool,
 ) -> HashMap<String, Vec<LeakFinding>> {
     let mut result: HashMap<String, Vec<LeakFinding>> = HashMap::new();
-    let total_lines = content.lines().count().max(1);
 
-    let mut declarations: Vec<(usize, &Entity)> = file_entities
+    let source = SourceFile::parse(content);
+    let code = code_only(content, &source.tokens);
+    let exported = source.exported_declarations();
+
+    let declarations: Vec<(&Entity, Option<&Declaration>)> = file_entities
         .iter()
-        .filter_map(|entity| find_entity_decl_line(content, entity).map(|line| (line, *entity)))
+        .filter_map(|entity| match entity.entity_type {
+            EntityType::Worker => Some((*entity, None)),
+            EntityType::Unknown => None,
+            _ => exported
+                .iter()
+                .find(|(name, _)| *name == entity.name)
+                .map(|(_, decl)| (*entity, Some(*decl))),
+        })
         .collect();
 
-    declarations.sort_by_key(|(line, _)| *line);
-
     if declarations.is_empty() {
         if file_entities.len() == 1 {
-            let auto_unsubscribe =
-                extract_auto_unsubscribe_config(content, 1, &file_entities[0].entity_type);
-            let findings = detect_segment_leaks(content, 1, true, auto_unsubscribe.as_ref(), strict);
-            result.insert(file_entities[0].id.clone(), findings);
+            let findings = detect_segment_leaks(&code, 1, true, None, strict);
+            result.insert(
+                file_entities[0].id.clone(),
+                without_worker_listeners(content, findings),
+            );
         }
         return result;
     }
 
-    let lines: Vec<&str> = content.lines().collect();
-
-    for (idx, (start_line, entity)) in declarations.iter().enumerate() {
-        let end_line = declarations
-            .get(idx + 1)
-            .map(|(line, _)| line.saturating_sub(1))
-            .unwrap_or(total_lines);
-
-        if *start_line == 0 || *start_line > end_line || *start_line > lines.len() {
-            continue;
-        }
+    // Each entity also gets the code up to the next one: top-level
+    // statements and helpers belong to the closest preceding entity, the
+    // code before the first one to the first. A worker is its whole file.
+    let mut segments: Vec<(&Entity, Option<&Declaration>, usize)> = declarations
+        .into_iter()
+        .filter_map(|(entity, declaration)| match declaration {
+            None => Some((entity, None, 0)),
+            Some(decl) => source
+                .tokens
+                .get(decl.tokens.start)
+                .map(|first| (entity, Some(decl), first.start)),
+        })
+        .collect();
+    segments.sort_by_key(|(_, _, start)| *start);
+    let starts: Vec<usize> = segments.iter().map(|(_, _, start)| *start).collect();
+
+    for (idx, (entity, declaration, start)) in segments.into_iter().enumerate() {
+        let end = starts.get(idx + 1).copied().unwrap_or(code.len());
+        let start = if idx == 0 { 0 } else { start };
+        let base_line = code[..start].matches('\n').count() + 1;
+
+        let auto_unsubscribe = declaration.and_then(|decl| {
+            let first = source.tokens[decl.tokens.start].start;
+            let decorators = &code[first..decl.span.start.max(first)];
+            extract_auto_unsubscribe_config(decorators, decl, &entity.entity_type)
+        });
 
-        let start_idx = start_line - 1;
-        let end_idx = end_line.min(lines.len());
-        let segment = lines[start_idx..end_idx].join("\n");
-        let auto_unsubscribe =
-            extract_auto_unsubscribe_config(content, *start_line, &entity.entity_type);
-        let findings =
-            detect_segment_leaks(&segment, *start_line, false, auto_unsubscribe.as_ref(), strict);
+        let findings = detect_segment_leaks(
+            &code[start..end],
+            base_line,
+            false,
+            auto_unsubscribe.as_ref(),
+            strict,
+        );
+        let findings = without_worker_listeners(content, findings);
         if !findings.is_empty() {
             result.insert(entity.id.clone(), findings);
         }
@@ -230,35 +273,16 @@ fn analyze_file_by_entity(
     result
 }
 
-fn find_entity_decl_line(content: &str, entity: &Entity) -> Option<usize> {
-    if matches!(entity.entity_type, EntityType::Worker) {
-        return Some(1);
+/// Drops the listener findings of a web worker, whose listeners live as long
+/// as the worker. The triple-slash directive is a comment, so it is looked
+/// up in the file content.
+fn without_worker_listeners(content: &str, mut findings: Vec<LeakFinding>) -> Vec<LeakFinding> {
+    if content.contains("<reference lib=\"webworker\"")
+        || content.contains("<reference lib='webworker'")
+    {
+        findings.retain(|f| f.kind != "dom-listener");
     }
-
-    let escaped = regex::escape(&entity.name);
-    let pattern = match entity.entity_type {
-        EntityType::Class
-        | EntityType::Component
-        | EntityType::Service
-        | EntityType::Directive
-        | EntityType::Pipe => {
-            format!(r"(?m)^\s*export\s+(?:abstract\s+)?class\s+{}\b", escaped)
-        }
-        EntityType::Enum => format!(r"(?m)^\s*export\s+enum\s+{}\b", escaped),
-        EntityType::Type => format!(r"(?m)^\s*export\s+type\s+{}\b", escaped),
-        EntityType::Interface => format!(r"(?m)^\s*export\s+interface\s+{}\b", escaped),
-        EntityType::Function => format!(
-            r"(?m)^\s*export\s+function\s+{}\b|^\s*export\s+(?:const|let|var)\s+{}\b",
-            escaped, escaped
-        ),
-        EntityType::Const => format!(r"(?m)^\s*export\s+(?:const|let|var)\s+{}\b", escaped),
-        EntityType::Worker => return Some(1),
-        EntityType::Unknown => return None,
-    };
-
-    let re = Regex::new(&pattern).ok()?;
-    let m = re.find(content)?;
-    Some(line_number_at(content, m.start()))
+    findings
 }
 
 fn detect_segment_leaks(
@@ -268,41 +292,111 @@ fn detect_segment_leaks(
     auto_unsubscribe: Option<&AutoUnsubscribeConfig>,
     strict: bool,
 ) -> Vec<LeakFinding> {
-    let mut findings = Vec::new();
-
-    findings.extend(detect_rxjs_subscriptions(
+    let tokens = tokenize(segment);
+    let site = Site {
         segment,
+        tokens: &tokens,
         base_line,
         uncertain_assignment,
-        auto_unsubscribe,
-        strict,
-    ));
-    findings.extend(detect_dom_listeners(
-        segment,
-        base_line,
-        uncertain_assignment,
-    ));
-    findings.extend(detect_timers(segment, base_line, uncertain_assignment, strict));
-    findings.extend(detect_observers(segment, base_line, uncertain_assignment));
-    findings.extend(detect_streams(segment, base_line, uncertain_assignment));
+    };
+    let mut findings = Vec::new();
+
+    findings.extend(detect_rxjs_subscriptions(&site, auto_unsubscribe, strict));
+    findings.extend(detect_dom_listeners(&site));
+    findings.extend(detect_timers(&site, strict));
+    findings.extend(detect_observers(&site));
+    findings.extend(detect_streams(&site));
 
     findings
 }
 
-fn detect_rxjs_subscriptions(
-    segment: &str,
+/// Code of a declaration, with its tokens. Token offsets index `segment`,
+/// token lines count from `base_line`.
+struct Site<'a> {
+    segment: &'a str,
+    tokens: &'a [Token<'a>],
     base_line: usize,
     uncertain_assignment: bool,
+}
+
+impl Site<'_> {
+    /// Indices of the tokens calling `name`, as in `name(`; with `method`
+    /// only as a method, as in `.name(`.
+    fn calls(&self, name: &str, method: bool) -> Vec<usize> {
+        let tokens = self.tokens;
+        (0..tokens.len())
+            .filter(|i| tokens[*i].is(name) && tokens.get(i + 1).is_some_and(|t| t.is("(")))
+            .filter(|i| !method || (*i > 0 && (tokens[i - 1].is(".") || tokens[i - 1].is("?."))))
+            .collect()
+    }
+
+    /// Indices of the class names of `new` expressions constructing one of
+    /// `classes`.
+    fn constructions(&self, classes: &[&str]) -> Vec<usize> {
+        let tokens = self.tokens;
+        (1..tokens.len())
+            .filter(|i| tokens[i - 1].is("new") && classes.iter().any(|c| tokens[*i].is(c)))
+            .filter(|i| tokens.get(i + 1).is_some_and(|t| t.is("(")))
+            .collect()
+    }
+
+    /// Byte range of a call from its name, or from the dot of a method, to
+    /// the opening parenthesis.
+    fn call_range(&self, idx: usize, method: bool) -> (usize, usize) {
+        let start = if method { idx - 1 } else { idx };
+        (self.tokens[start].start, self.tokens[idx + 1].end)
+    }
+
+    fn line(&self, idx: usize) -> usize {
+        self.base_line + self.tokens[idx].line - 1
+    }
+
+    fn finding(
+        &self,
+        idx: usize,
+        severity: Severity,
+        kind: &'static str,
+        message: String,
+    ) -> LeakFinding {
+        LeakFinding {
+            severity: adjusted_severity(severity, self.uncertain_assignment),
+            line: self.line(idx),
+            kind,
+            message,
+        }
+    }
+
+    /// Event name and handler of `addEventListener('event', handler` or
+    /// `removeEventListener`, with the index of the handler token.
+    fn listener_args(&self, idx: usize) -> Option<(&str, &str, usize)> {
+        let tokens = self.tokens;
+        let event = tokens
+            .get(idx + 2)?
+            .string_value()
+            .filter(|e| !e.is_empty())?;
+        if !tokens.get(idx + 3)?.is(",") {
+            return None;
+        }
+        let handler = if tokens.get(idx + 4)?.is("function") {
+            idx + 5
+        } else {
+            idx + 4
+        };
+        tokens
+            .get(handler)
+            .filter(|t| t.is_ident())
+            .map(|t| (event, t.text, handler))
+    }
+}
+
+fn detect_rxjs_subscriptions(
+    site: &Site,
     auto_unsubscribe: Option<&AutoUnsubscribeConfig>,
     strict: bool,
 ) -> Vec<LeakFinding> {
-    let subscribe_re = Regex::new(r"\.subscribe\s*\(").expect("valid subscribe regex");
-    let has_cleanup = Regex::new(r"\.unsubscribe\s*\(")
-        .expect("valid unsubscribe regex")
-        .is_match(segment)
-        || Regex::new(r"takeUntilDestroyed\s*\(")
-            .expect("valid takeUntilDestroyed regex")
-            .is_match(segment);
+    let segment = site.segment;
+    let has_cleanup = !site.calls("unsubscribe", true).is_empty()
+        || !site.calls("takeUntilDestroyed", false).is_empty();
 
     let mut out = Vec::new();
     if has_cleanup {
@@ -313,46 +407,41 @@ fn detect_rxjs_subscriptions(
         .map(|cfg| collect_auto_managed_subscription_ranges(segment, cfg))
         .unwrap_or_default();
 
-    for m in subscribe_re.find_iter(segment) {
-        if is_api_subscription(segment, m.start(), m.end())
-            || is_assigned_api_observable_subscription(segment, m.start(), m.end())
-            || is_clipboard_subscription(segment, m.start(), m.end())
-            || is_angular_to_observable_with_injector_subscription(segment, m.start(), m.end())
-            || is_promise_from_subscription(segment, m.start(), m.end())
-            || is_fork_join_subscription(segment, m.start(), m.end())
-            || is_modal_render_subscription(segment, m.start(), m.end())
-            || is_modal_service_result_subscription(segment, m.start(), m.end())
-            || is_submit_action_subscription(segment, m.start(), m.end())
-            || is_modal_or_popup_output_subscription(segment, m.start(), m.end())
-            || is_output_to_observable_subscription(segment, m.start(), m.end())
-            || (!strict && is_default_safe_finite_subscription(segment, m.start(), m.end()))
+    for idx in site.calls("subscribe", true) {
+        let (start, end) = site.call_range(idx, true);
+        if is_api_subscription(segment, start, end)
+            || is_assigned_api_observable_subscription(segment, start, end)
+            || is_clipboard_subscription(segment, start, end)
+            || is_angular_to_observable_with_injector_subscription(segment, start, end)
+            || is_promise_from_subscription(segment, start, end)
+            || is_fork_join_subscription(segment, start, end)
+            || is_modal_render_subscription(segment, start, end)
+            || is_modal_service_result_subscription(segment, start, end)
+            || is_submit_action_subscription(segment, start, end)
+            || is_modal_or_popup_output_subscription(segment, start, end)
+            || is_output_to_observable_subscription(segment, start, end)
+            || (!strict && is_default_safe_finite_subscription(segment, start, end))
         {
             continue;
         }
 
         if handled_by_auto
             .iter()
-            .any(|(start, end)| m.start() >= *start && m.end() <= *end)
+            .any(|(range_start, range_end)| start >= *range_start && end <= *range_end)
             || auto_unsubscribe.is_some_and(|cfg| {
-                is_auto_unsubscribe_array_push_subscription(segment, m.start(), m.end(), cfg)
+                is_auto_unsubscribe_array_push_subscription(segment, start, end, cfg)
             })
         {
             continue;
         }
 
-        let line = base_line + line_number_at(segment, m.start()) - 1;
         let message = if auto_unsubscribe.is_some() {
             "Subscription appears without proven cleanup; @AutoUnsubscribe is present but this subscription is not clearly tracked.".to_string()
         } else {
             "Subscription appears without cleanup (unsubscribe/takeUntilDestroyed).".to_string()
         };
 
-        out.push(LeakFinding {
-            severity: adjusted_severity(Severity::High, uncertain_assignment),
-            line,
-            kind: "rxjs-subscription",
-            message,
-        });
+        out.push(site.finding(idx, Severity::High, "rxjs-subscription", message));
     }
 
     out
@@ -424,8 +513,8 @@ fn is_fork_join_subscription(segment: &str, subscribe_start: usize, subscribe_en
         return true;
     }
 
-    let method_call_re = Regex::new(r"this\.([A-Za-z_$][A-Za-z0-9_$]*)\s*\(")
-        .expect("valid this method call regex");
+    let method_call_re =
+        Regex::new(r"this\.([A-Za-z_$][A-Za-z0-9_$]*)\s*\(").expect("valid this method call regex");
     for cap in method_call_re.captures_iter(statement) {
         let method_name = &cap[1];
         let method_decl_pattern = format!(
@@ -443,7 +532,11 @@ fn is_fork_join_subscription(segment: &str, subscribe_start: usize, subscribe_en
     false
 }
 
-fn is_promise_from_subscription(segment: &str, subscribe_start: usize, subscribe_end: usize) -> bool {
+fn is_promise_from_subscription(
+    segment: &str,
+    subscribe_start: usize,
+    subscribe_end: usize,
+) -> bool {
     let statement = statement_containing(segment, subscribe_start, subscribe_end);
 
     let from_re = Regex::new(r"\bfrom\s*\(").expect("valid from regex");
@@ -455,7 +548,6 @@ fn is_promise_from_subscription(segment: &str, subscribe_start: usize, subscribe
 fn is_api_subscription(segment: &str, subscribe_start: usize, subscribe_end: usize) -> bool {
     let statement = statement_containing(segment, subscribe_start, subscribe_end);
 
-
     let service_call_re =
         Regex::new(r"(?:[A-Za-z_$][A-Za-z0-9_$]*\s*\.\s*)*[A-Za-z_$][A-Za-z0-9_$]*Service[A-Za-z0-9_$]*\s*\.\s*([A-Za-z_$][A-Za-z0-9_$]*)(?:\s*<[^>]+>)?\s*\(")
             .expect("valid service call regex");
@@ -483,8 +575,9 @@ fn is_api_subscription(segment: &str, subscribe_start: usize, subscribe_end: usi
         return true;
     }
 
-    let http_re = Regex::new(r"(?:this\.)?http\.(?:get|post|put|patch|delete|request)(?:\s*<[^>]+>)?\s*\(")
-        .expect("valid http regex");
+    let http_re =
+        Regex::new(r"(?:this\.)?http\.(?:get|post|put|patch|delete|request)(?:\s*<[^>]+>)?\s*\(")
+            .expect("valid http regex");
     if http_re.is_match(statement) {
         return true;
     }
@@ -510,15 +603,20 @@ fn is_api_subscription(segment: &str, subscribe_start: usize, subscribe_end: usi
     false
 }
 
-fn is_modal_render_subscription(segment: &str, subscribe_start: usize, subscribe_end: usize) -> bool {
+fn is_modal_render_subscription(
+    segment: &str,
+    subscribe_start: usize,
+    subscribe_end: usize,
+) -> bool {
     let statement = statement_containing(segment, subscribe_start, subscribe_end);
 
-    let modal_render_re = Regex::new(
-        r"[A-Za-z_$][A-Za-z0-9_$]*(?:Modal|ModalComponent)\.renderModal\s*\(",
-    )
-    .expect("valid modal render regex");
+    let modal_render_re =
+        Regex::new(r"[A-Za-z_$][A-Za-z0-9_$]*(?:Modal|ModalComponent)\.renderModal\s*\(")
+            .expect("valid modal render regex");
 
-    modal_render_re.find(statement).is_some_and(|m| m.start() < subscribe_start)
+    modal_render_re
+        .find(statement)
+        .is_some_and(|m| m.start() < subscribe_start)
 }
 
 fn is_modal_service_result_subscription(
@@ -590,11 +688,12 @@ fn is_default_safe_finite_subscription(
     subscribe_end: usize,
 ) -> bool {
     let statement = statement_containing(segment, subscribe_start, subscribe_end);
-    let finite_re = Regex::new(r"\b(?:take\s*\(\s*1\s*\)|first\s*\(|last\s*\()").expect(
-        "valid finite operator regex",
-    );
+    let finite_re = Regex::new(r"\b(?:take\s*\(\s*1\s*\)|first\s*\(|last\s*\()")
+        .expect("valid finite operator regex");
 
-    finite_re.find(statement).is_some_and(|m| m.start() < subscribe_start)
+    finite_re
+        .find(statement)
+        .is_some_and(|m| m.start() < subscribe_start)
 }
 
 fn statement_containing(segment: &str, start: usize, end: usize) -> &str {
@@ -664,8 +763,8 @@ fn is_auto_unsubscribe_array_push_subscription(
     config: &AutoUnsubscribeConfig,
 ) -> bool {
     let statement = statement_containing(segment, subscribe_start, subscribe_end);
-    let push_re = Regex::new(r"this\.([A-Za-z_$][A-Za-z0-9_$]*)\.push\s*\(")
-        .expect("valid array push regex");
+    let push_re =
+        Regex::new(r"this\.([A-Za-z_$][A-Za-z0-9_$]*)\.push\s*\(").expect("valid array push regex");
 
     push_re.captures_iter(statement).any(|cap| {
         let Some(m) = cap.get(0) else {
@@ -737,9 +836,11 @@ fn collect_auto_managed_subscription_ranges(
     ranges
 }
 
+/// Options of the `@AutoUnsubscribe` decorator among `decorators`, the
+/// code of the decorators of `declaration`.
 fn extract_auto_unsubscribe_config(
-    content: &str,
-    declaration_line: usize,
+    decorators: &str,
+    declaration: &Declaration,
     entity_type: &EntityType,
 ) -> Option<AutoUnsubscribeConfig> {
     if !matches!(
@@ -749,22 +850,17 @@ fn extract_auto_unsubscribe_config(
             | EntityType::Service
             | EntityType::Directive
             | EntityType::Pipe
-    ) {
-        return None;
-    }
-
-    let lines: Vec<&str> = content.lines().collect();
-    if declaration_line <= 1 || declaration_line > lines.len() {
+    ) || !declaration
+        .decorators
+        .iter()
+        .any(|d| d == "AutoUnsubscribe")
+    {
         return None;
     }
 
-    let declaration_idx = declaration_line - 1;
-    let decorator_search_start = declaration_idx.saturating_sub(25);
-    let decorator_region = lines[decorator_search_start..declaration_idx].join("\n");
-
     let decorator_re =
         Regex::new(r"(?s)@AutoUnsubscribe\s*(?:\((.*?)\))?").expect("valid decorator regex");
-    let captures = decorator_re.captures(&decorator_region)?;
+    let captures = decorator_re.captures(decorators)?;
 
     let mut config = AutoUnsubscribeConfig::default();
     let options = captures.get(1).map(|m| m.as_str()).unwrap_or("");
@@ -800,89 +896,71 @@ fn extract_auto_unsubscribe_config(
     Some(config)
 }
 
-fn detect_dom_listeners(
-    segment: &str,
-    base_line: usize,
-    uncertain_assignment: bool,
-) -> Vec<LeakFinding> {
-    let add_named_re = Regex::new(
-        r#"addEventListener\s*\(\s*['\"]([^'\"]+)['\"]\s*,\s*(?:function\s+)?([A-Za-z_$][A-Za-z0-9_$]*)"#,
-    )
-    .expect("valid addEventListener regex");
-    let remove_named_re = Regex::new(
-        r#"removeEventListener\s*\(\s*['\"]([^'\"]+)['\"]\s*,\s*([A-Za-z_$][A-Za-z0-9_$]*)"#,
-    )
-    .expect("valid removeEventListener regex");
-    let add_any_re = Regex::new(r"addEventListener\s*\(").expect("valid generic add regex");
+fn detect_dom_listeners(site: &Site) -> Vec<LeakFinding> {
+    let segment = site.segment;
+    let adds = site.calls("addEventListener", false);
 
-    let mut removed_pairs: HashMap<(String, String), usize> = HashMap::new();
-    for cap in remove_named_re.captures_iter(segment) {
-        let event = cap[1].to_string();
-        let handler = cap[2].to_string();
-        *removed_pairs.entry((event, handler)).or_insert(0) += 1;
+    let mut removed_pairs: HashMap<(&str, &str), usize> = HashMap::new();
+    for idx in site.calls("removeEventListener", false) {
+        if let Some((event, handler, _)) = site.listener_args(idx) {
+            *removed_pairs.entry((event, handler)).or_insert(0) += 1;
+        }
     }
 
-    if segment.contains("<reference lib=\"webworker\"")
-        || segment.contains("<reference lib='webworker'")
-        || (Regex::new(r#"addEventListener\s*\(\s*['\"]message['\"]"#)
-            .expect("valid worker message listener regex")
-            .is_match(segment)
-            && segment.contains("postMessage("))
+    if adds.iter().any(|idx| {
+        site.tokens
+            .get(idx + 2)
+            .and_then(|t| t.string_value())
+            .is_some_and(|event| event == "message")
+    }) && segment.contains("postMessage(")
     {
         return Vec::new();
     }
 
     let mut findings = Vec::new();
-    for cap in add_named_re.captures_iter(segment) {
-        let event = cap[1].to_string();
-        let handler = cap[2].to_string();
-        let key = (event.clone(), handler.clone());
+    for idx in adds {
+        let Some((event, handler, handler_idx)) = site.listener_args(idx) else {
+            let (start, end) = site.call_range(idx, false);
+            if is_listener_on_local_created_element(segment, start, end) {
+                continue;
+            }
+            findings.push(
+                site.finding(
+                    idx,
+                    Severity::Medium,
+                    "dom-listener",
+                    "addEventListener with anonymous/non-identifiable handler; cleanup uncertain."
+                        .to_string(),
+                ),
+            );
+            continue;
+        };
 
-        if let Some(count) = removed_pairs.get_mut(&key)
+        if let Some(count) = removed_pairs.get_mut(&(event, handler))
             && *count > 0
         {
             *count -= 1;
             continue;
         }
 
-        let m = cap.get(0).expect("capture 0 exists");
-        if listener_removes_itself(segment, m.start(), m.end(), &event, &handler)
-            || listener_removed_via_property(segment, m.start(), &event, &handler)
-            || is_listener_on_local_created_element(segment, m.start(), m.end())
+        let start = site.tokens[idx].start;
+        let end = site.tokens[handler_idx].end;
+        if listener_removes_itself(segment, start, end, event, handler)
+            || listener_removed_via_property(segment, start, event, handler)
+            || is_listener_on_local_created_element(segment, start, end)
         {
             continue;
         }
 
-        let line = base_line + line_number_at(segment, m.start()) - 1;
-        findings.push(LeakFinding {
-            severity: adjusted_severity(Severity::High, uncertain_assignment),
-            line,
-            kind: "dom-listener",
-            message: format!(
+        findings.push(site.finding(
+            idx,
+            Severity::High,
+            "dom-listener",
+            format!(
                 "addEventListener('{}', {}) has no matching removeEventListener.",
                 event, handler
             ),
-        });
-    }
-
-    let named_add_count = add_named_re.captures_iter(segment).count();
-    let any_add_count = add_any_re.find_iter(segment).count();
-    if any_add_count > named_add_count {
-        for m in add_any_re.find_iter(segment).skip(named_add_count) {
-            if is_listener_on_local_created_element(segment, m.start(), m.end()) {
-                continue;
-            }
-
-            let line = base_line + line_number_at(segment, m.start()) - 1;
-            findings.push(LeakFinding {
-                severity: adjusted_severity(Severity::Medium, uncertain_assignment),
-                line,
-                kind: "dom-listener",
-                message:
-                    "addEventListener with anonymous/non-identifiable handler; cleanup uncertain."
-                        .to_string(),
-            });
-        }
+        ));
     }
 
     findings
@@ -905,7 +983,9 @@ fn listener_removes_itself(
         return false;
     };
 
-    remove_re.find(statement).is_some_and(|m| m.start() > add_end)
+    remove_re
+        .find(statement)
+        .is_some_and(|m| m.start() > add_end)
 }
 
 fn listener_removed_via_property(
@@ -961,74 +1041,53 @@ fn is_listener_on_local_created_element(segment: &str, add_start: usize, add_end
         Err(_) => return false,
     };
 
-    create_re.find(segment).is_some_and(|m| m.start() < add_start)
+    create_re
+        .find(segment)
+        .is_some_and(|m| m.start() < add_start)
 }
 
-fn detect_timers(
-    segment: &str,
-    base_line: usize,
-    uncertain_assignment: bool,
-    strict: bool,
-) -> Vec<LeakFinding> {
-    let set_interval_re = Regex::new(r"setInterval\s*\(").expect("valid setInterval regex");
-    let clear_interval_re = Regex::new(r"clearInterval\s*\(").expect("valid clearInterval regex");
-    let set_timeout_re = Regex::new(r"setTimeout\s*\(").expect("valid setTimeout regex");
-    let clear_timeout_re = Regex::new(r"clearTimeout\s*\(").expect("valid clearTimeout regex");
-
-    let interval_count = set_interval_re.find_iter(segment).count();
-    let cleared_interval_count = clear_interval_re.find_iter(segment).count();
-    let timeout_count = set_timeout_re.find_iter(segment).count();
-    let cleared_timeout_count = clear_timeout_re.find_iter(segment).count();
+fn detect_timers(site: &Site, strict: bool) -> Vec<LeakFinding> {
+    let intervals = site.calls("setInterval", false);
+    let cleared_intervals = site.calls("clearInterval", false).len();
+    let timeouts = site.calls("setTimeout", false);
+    let cleared_timeouts = site.calls("clearTimeout", false).len();
 
     let mut findings = Vec::new();
-    if interval_count > cleared_interval_count {
-        for m in set_interval_re
-            .find_iter(segment)
-            .take(interval_count - cleared_interval_count)
-        {
-            let line = base_line + line_number_at(segment, m.start()) - 1;
-            findings.push(LeakFinding {
-                severity: adjusted_severity(Severity::High, uncertain_assignment),
-                line,
-                kind: "timer-interval",
-                message: "setInterval appears without clearInterval.".to_string(),
-            });
-        }
+    for idx in intervals
+        .iter()
+        .take(intervals.len().saturating_sub(cleared_intervals))
+    {
+        findings.push(site.finding(
+            *idx,
+            Severity::High,
+            "timer-interval",
+            "setInterval appears without clearInterval.".to_string(),
+        ));
     }
 
-    if strict && timeout_count > cleared_timeout_count {
-        for m in set_timeout_re
-            .find_iter(segment)
-            .take(timeout_count - cleared_timeout_count)
+    if strict {
+        for idx in timeouts
+            .iter()
+            .take(timeouts.len().saturating_sub(cleared_timeouts))
         {
-            let line = base_line + line_number_at(segment, m.start()) - 1;
-            findings.push(LeakFinding {
-                severity: adjusted_severity(Severity::Medium, uncertain_assignment),
-                line,
-                kind: "timer-timeout",
-                message: "setTimeout appears without clearTimeout (strict mode).".to_string(),
-            });
+            findings.push(site.finding(
+                *idx,
+                Severity::Medium,
+                "timer-timeout",
+                "setTimeout appears without clearTimeout (strict mode).".to_string(),
+            ));
         }
     }
 
     findings
 }
 
-fn detect_observers(
-    segment: &str,
-    base_line: usize,
-    uncertain_assignment: bool,
-) -> Vec<LeakFinding> {
-    let observer_new_re = Regex::new(
-        r"new\s+(MutationObserver|ResizeObserver|IntersectionObserver|PerformanceObserver)\s*\(",
-    )
-    .expect("valid observer constructor regex");
-    let disconnect_re = Regex::new(r"\.disconnect\s*\(").expect("valid disconnect regex");
+fn detect_observers(site: &Site) -> Vec<LeakFinding> {
+    let segment = site.segment;
+    let created = site.constructions(OBSERVERS);
+    let disconnected_count = site.calls("disconnect", true).len();
 
-    let created_count = observer_new_re.find_iter(segment).count();
-    let disconnected_count = disconnect_re.find_iter(segment).count();
-
-    if created_count <= disconnected_count
+    if created.len() <= disconnected_count
         || Regex::new(r"(?s)return\s+function\s+unsubscribe\s*\([^)]*\)\s*\{.*?\.disconnect\s*\(")
             .expect("valid observer unsubscribe cleanup regex")
             .is_match(segment)
@@ -1039,63 +1098,48 @@ fn detect_observers(
         return Vec::new();
     }
 
-    let mut findings = Vec::new();
-    for cap in observer_new_re
-        .captures_iter(segment)
-        .take(created_count - disconnected_count)
-    {
-        let m = cap.get(0).expect("capture 0 exists");
-        let line = base_line + line_number_at(segment, m.start()) - 1;
-        let observer_type = cap[1].to_string();
-
-        findings.push(LeakFinding {
-            severity: adjusted_severity(Severity::High, uncertain_assignment),
-            line,
-            kind: "observer",
-            message: format!("{} appears without disconnect().", observer_type),
-        });
-    }
-
-    findings
+    created
+        .iter()
+        .take(created.len() - disconnected_count)
+        .map(|idx| {
+            site.finding(
+                *idx,
+                Severity::High,
+                "observer",
+                format!("{} appears without disconnect().", site.tokens[*idx].text),
+            )
+        })
+        .collect()
 }
 
-fn detect_streams(segment: &str, base_line: usize, uncertain_assignment: bool) -> Vec<LeakFinding> {
-    let ws_re = Regex::new(r"new\s+WebSocket\s*\(").expect("valid websocket regex");
-    let es_re = Regex::new(r"new\s+EventSource\s*\(").expect("valid eventsource regex");
-    let close_re = Regex::new(r"\.close\s*\(").expect("valid close regex");
-
-    let mut findings = Vec::new();
-
-    let ws_count = ws_re.find_iter(segment).count();
-    let es_count = es_re.find_iter(segment).count();
-    let close_count = close_re.find_iter(segment).count();
+fn detect_streams(site: &Site) -> Vec<LeakFinding> {
+    let web_sockets = site.constructions(&["WebSocket"]);
+    let event_sources = site.constructions(&["EventSource"]);
+    let close_count = site.calls("close", true).len();
 
-    let created_streams = ws_count + es_count;
+    let created_streams = web_sockets.len() + event_sources.len();
     if created_streams <= close_count {
-        return findings;
-    }
-
-    for m in ws_re.find_iter(segment) {
-        let line = base_line + line_number_at(segment, m.start()) - 1;
-        findings.push(LeakFinding {
-            severity: adjusted_severity(Severity::High, uncertain_assignment),
-            line,
-            kind: "websocket",
-            message: "WebSocket appears without close().".to_string(),
-        });
-    }
-    for m in es_re.find_iter(segment) {
-        let line = base_line + line_number_at(segment, m.start()) - 1;
-        findings.push(LeakFinding {
-            severity: adjusted_severity(Severity::High, uncertain_assignment),
-            line,
-            kind: "eventsource",
-            message: "EventSource appears without close().".to_string(),
-        });
+        return Vec::new();
     }
 
-    findings
-        .into_iter()
+    let web_socket_findings = web_sockets.iter().map(|idx| {
+        site.finding(
+            *idx,
+            Severity::High,
+            "websocket",
+            "WebSocket appears without close().".to_string(),
+        )
+    });
+    let event_source_findings = event_sources.iter().map(|idx| {
+        site.finding(
+            *idx,
+            Severity::High,
+            "eventsource",
+            "EventSource appears without close().".to_string(),
+        )
+    });
+    web_socket_findings
+        .chain(event_source_findings)
         .take(created_streams - close_count)
         .collect()
 }
@@ -1110,13 +1154,12 @@ fn adjusted_severity(base: Severity, uncertain_assignment: bool) -> Severity {
     }
 }
 
-fn line_number_at(content: &str, byte_idx: usize) -> usize {
-    content[..byte_idx].bytes().filter(|b| *b == b'\n').count() + 1
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{AutoUnsubscribeConfig, detect_segment_leaks};
+    use std::sync::Arc;
+
+    use super::{AutoUnsubscribeConfig, analyze_file_by_entity, detect_segment_leaks};
+    use crate::entity::{Entity, EntityType};
 
     #[test]
     fn flags_unmanaged_subscription() {
@@ -1268,4 +1311,94 @@ mod tests {
         let findings = detect_segment_leaks(content, 1, false, None, false);
         assert!(findings.iter().any(|f| f.kind == "rxjs-subscription"));
     }
+
+    #[test]
+    fn reports_file_lines_after_block_comments() {
+        let content = r#"/**
+ * Polls the server.
+ *
+ * Never call setInterval(() => {}) here.
+ */
+@Component({ selector: 'app-poller' })
+export class PollerComponent {
+  /* ngOnInit
+     runs once */
+  ngOnInit() {
+    setInterval(() => this.poll(), 1000);
+  }
+}
+"#;
+        let entity = Entity::new(
+            "PollerComponent".to_string(),
+            EntityType::Component,
+            "apps/web/src/poller.component.ts".to_string(),
+            Arc::new(Vec::new()),
+        );
+        let findings = analyze_file_by_entity(content, &[&entity], false);
+        let lines: Vec<usize> = findings[&entity.id].iter().map(|f| f.line).collect();
+        assert_eq!(lines, vec![11]);
+    }
+
+    #[test]
+    fn reads_auto_unsubscribe_options_from_decorators() {
+        let content = r#"@AutoUnsubscribe({ arrayName: 'subs' })
+@Component({ selector: 'app-list' })
+export class ListComponent {
+  ngOnInit() {
+    this.subs.push(this.items$.subscribe());
+  }
+}
+"#;
+        let entity = Entity::new(
+            "ListComponent".to_string(),
+            EntityType::Component,
+            "apps/web/src/list.component.ts".to_string(),
+            Arc::new(Vec::new()),
+        );
+        let findings = analyze_file_by_entity(content, &[&entity], false);
+        assert!(!findings.contains_key(&entity.id));
+    }
+
+    #[test]
+    fn analyzes_code_outside_of_exported_declarations() {
+        let content = r#"export class Poller {}
+
+function listen() {
+  window.addEventListener('resize', onResize);
+}
+
+setInterval(() => listen(), 1000);
+"#;
+        let entity = Entity::new(
+            "Poller".to_string(),
+            EntityType::Class,
+            "apps/web/src/poller.ts".to_string(),
+            Arc::new(Vec::new()),
+        );
+        let findings = analyze_file_by_entity(content, &[&entity], false);
+        let found: Vec<(&str, usize)> = findings[&entity.id]
+            .iter()
+            .map(|f| (f.kind, f.line))
+            .collect();
+        assert_eq!(found, vec![("dom-listener", 4), ("timer-interval", 7)]);
+    }
+
+    #[test]
+    fn skips_listeners_of_web_workers() {
+        let content = r#"/// <reference lib="webworker" />
+export class Handler {
+  start() {
+    addEventListener('message', onMessage);
+  }
+}
+"#;
+        let entity = Entity::new(
+            "Handler".to_string(),
+            EntityType::Class,
+            "apps/web/src/handler.ts".to_string(),
+            Arc::new(Vec::new()),
+        );
+        let findings = analyze_file_by_entity(content, &[&entity], false);
+        assert!(!findings.contains_key(&entity.id));
+    }
 }
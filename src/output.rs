@@ -0,0 +1,568 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::entity::{EdgeKind, Entity};
+use crate::graph::DependencyGraph;
+use crate::graph_diff::GraphDiff;
+use crate::mem_leaks::{EntityLeakReport, LeakFinding};
+use crate::routes::RouteNode;
+use crate::runner::TestTarget;
+use crate::search::MatchKind;
+
+/// Version of the JSON and NDJSON schemas. Bumped on any incompatible
+/// change to a report; adding fields is not one.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+/// How command results are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text, the format each command always had
+    #[default]
+    Text,
+    /// A single JSON document per command
+    Json,
+    /// One JSON object per line and record, followed by a summary line
+    Ndjson,
+    /// SARIF 2.1.0 log of findings, for `unused`, `cycles` and the
+    /// memory leak commands
+    Sarif,
+    /// Markdown for pull request comments, for `graph-diff`
+    Markdown,
+}
+
+impl OutputFormat {
+    pub fn is_text(self) -> bool {
+        self == OutputFormat::Text
+    }
+}
+
+/// Machine-readable result of a command.
+///
+/// In JSON the report is printed as one object with `schema` and
+/// `version` added. In NDJSON every element of the fields listed in
+/// `RECORDS` becomes one line tagged with `record`, and the remaining
+/// fields form a final `summary` record.
+pub(crate) trait Report: Serialize {
+    /// Schema name, e.g. `sting.unused`
+    const SCHEMA: &'static str;
+    /// List fields and the record type of their elements in NDJSON
+    const RECORDS: &'static [(&'static str, &'static str)];
+}
+
+/// Prints a report as JSON or NDJSON. Text output is up to each command.
+pub(crate) fn emit<R: Report>(format: OutputFormat, report: &R) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for line in render(format, report)? {
+        writeln!(stdout, "{}", line)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn render<R: Report>(format: OutputFormat, report: &R) -> Result<Vec<String>> {
+    let Value::Object(fields) = serde_json::to_value(report)? else {
+        anyhow::bail!("Report {} is not a JSON object", R::SCHEMA);
+    };
+
+    match format {
+        OutputFormat::Text => Ok(Vec::new()),
+        OutputFormat::Sarif => anyhow::bail!(
+            "SARIF output is only available for unused, cycles, mem-leaks and affected-mem-leaks"
+        ),
+        OutputFormat::Markdown => anyhow::bail!("Markdown output is only available for graph-diff"),
+        OutputFormat::Json => {
+            let mut document = header::<R>();
+            document.extend(fields);
+            Ok(vec![serde_json::to_string_pretty(&document)?])
+        }
+        OutputFormat::Ndjson => {
+            let mut lines = Vec::new();
+            let mut summary = header::<R>();
+            summary.insert("record".to_string(), Value::from("summary"));
+
+            for (key, value) in fields {
+                let record_type = R::RECORDS
+                    .iter()
+                    .find(|(field, _)| *field == key)
+                    .map(|(_, record_type)| *record_type);
+
+                match (record_type, value) {
+                    (Some(record_type), Value::Array(elements)) => {
+                        for element in elements {
+                            let mut record = header::<R>();
+                            record.insert("record".to_string(), Value::from(record_type));
+                            match element {
+                                Value::Object(element) => record.extend(element),
+                                element => {
+                                    record.insert("value".to_string(), element);
+                                }
+                            }
+                            lines.push(serde_json::to_string(&record)?);
+                        }
+                    }
+                    (_, value) => {
+                        summary.insert(key, value);
+                    }
+                }
+            }
+
+            lines.push(serde_json::to_string(&summary)?);
+            Ok(lines)
+        }
+    }
+}
+
+fn header<R: Report>() -> Map<String, Value> {
+    let mut map = Map::new();
+    map.insert("schema".to_string(), Value::from(R::SCHEMA));
+    map.insert("version".to_string(), Value::from(SCHEMA_VERSION));
+    map
+}
+
+/// An entity as it appears in reports.
+#[derive(Debug, Serialize)]
+pub(crate) struct EntityRecord {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub file: String,
+}
+
+impl From<&Entity> for EntityRecord {
+    fn from(entity: &Entity) -> Self {
+        EntityRecord {
+            id: entity.id.clone(),
+            name: entity.name.clone(),
+            entity_type: entity.entity_type.to_string(),
+            file: entity.file_path.clone(),
+        }
+    }
+}
+
+/// An entity with its usage and resolved dependencies.
+#[derive(Debug, Serialize)]
+pub(crate) struct EntityDetails {
+    #[serde(flatten)]
+    pub entity: EntityRecord,
+    pub used: bool,
+    pub deps: Vec<DependencyRecord>,
+}
+
+impl From<&Entity> for EntityDetails {
+    fn from(entity: &Entity) -> Self {
+        EntityDetails {
+            entity: entity.into(),
+            used: entity.used,
+            deps: entity
+                .deps
+                .iter()
+                .map(|dep| DependencyRecord {
+                    id: dep.id.clone(),
+                    name: dep.name.clone(),
+                    file: dep.path.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct DependencyRecord {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+}
+
+/// `sting.query-all`: every entity of the workspace.
+#[derive(Serialize)]
+pub(crate) struct QueryAllReport {
+    pub entities: Vec<EntityDetails>,
+    pub total: usize,
+}
+
+impl Report for QueryAllReport {
+    const SCHEMA: &'static str = "sting.query-all";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("entities", "entity")];
+}
+
+/// `sting.query`: entities matching the query by name, best match first.
+#[derive(Serialize)]
+pub(crate) struct QueryReport {
+    pub query: String,
+    pub matches: Vec<QueryMatch>,
+    /// Number of matches before `--limit` was applied
+    pub total: usize,
+}
+
+impl Report for QueryReport {
+    const SCHEMA: &'static str = "sting.query";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("matches", "match")];
+}
+
+/// A matched entity with its direct dependencies and consumers.
+#[derive(Debug, Serialize)]
+pub(crate) struct QueryMatch {
+    #[serde(flatten)]
+    pub entity: EntityRecord,
+    #[serde(rename = "match")]
+    pub match_kind: MatchKind,
+    pub deps: Vec<EntityRecord>,
+    pub consumers: Vec<EntityRecord>,
+}
+
+/// `sting.explain`: the neighbourhood of the explained entities.
+#[derive(Serialize)]
+pub(crate) struct ExplainReport {
+    pub query: String,
+    pub entities: Vec<EntityExplanation>,
+}
+
+impl Report for ExplainReport {
+    const SCHEMA: &'static str = "sting.explain";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("entities", "entity")];
+}
+
+/// Everything known about one entity.
+#[derive(Debug, Serialize)]
+pub(crate) struct EntityExplanation {
+    #[serde(flatten)]
+    pub entity: EntityRecord,
+    /// Line of the declaration, if it could be located
+    pub line: Option<usize>,
+    pub used: bool,
+    pub deps: Neighbourhood,
+    pub consumers: Neighbourhood,
+    /// Cycles the entity takes part in
+    pub cycles: Vec<CycleRecord>,
+    pub leaks: Vec<LeakFinding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affected: Option<AffectedStatus>,
+}
+
+/// Entities one hop away, and all entities reachable including those.
+#[derive(Debug, Serialize)]
+pub(crate) struct Neighbourhood {
+    pub direct: Vec<EntityRecord>,
+    pub transitive: Vec<EntityRecord>,
+}
+
+/// Whether an entity is affected by the changes since `base`.
+#[derive(Debug, Serialize)]
+pub(crate) struct AffectedStatus {
+    pub base: String,
+    /// `direct` when the entity's file changed, `consumer` when it depends
+    /// on an entity in a changed file, `none` otherwise
+    pub relation: &'static str,
+    /// Change of the entity's own file, for `direct`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change: Option<&'static str>,
+    /// Dependencies in changed files, for `consumer`
+    pub changed_deps: Vec<EntityRecord>,
+}
+
+/// `sting.unused`: entities no other file imports.
+#[derive(Serialize)]
+pub(crate) struct UnusedReport {
+    pub entities: Vec<EntityRecord>,
+    pub unused: usize,
+    pub total: usize,
+}
+
+impl Report for UnusedReport {
+    const SCHEMA: &'static str = "sting.unused";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("entities", "entity")];
+}
+
+/// `sting.graph`: nodes and links of the dependency graph.
+impl Report for DependencyGraph {
+    const SCHEMA: &'static str = "sting.graph";
+    const RECORDS: &'static [(&'static str, &'static str)] =
+        &[("nodes", "node"), ("links", "link")];
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ChangedFileRecord {
+    pub path: String,
+    /// Path before a rename, or the origin of a copy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// `added`, `modified`, `deleted`, `renamed` or `copied`
+    pub change: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct AffectedEntityRecord {
+    #[serde(flatten)]
+    pub entity: EntityRecord,
+    /// `direct` for entities of changed files, `consumer` for their dependents
+    pub relation: &'static str,
+    pub reason: String,
+    /// Paths of the routes the entity ends up in, only computed with
+    /// `--routes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routes: Option<Vec<String>>,
+}
+
+/// A route to re-test, with its full path.
+#[derive(Debug, Serialize)]
+pub(crate) struct RouteRecord {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    pub file: String,
+    pub lazy: bool,
+}
+
+impl From<&RouteNode> for RouteRecord {
+    fn from(node: &RouteNode) -> Self {
+        RouteRecord {
+            path: node.path.clone(),
+            component: node.component.clone(),
+            file: node.file.clone(),
+            lazy: node.lazy,
+        }
+    }
+}
+
+/// `sting.affected`: entities affected by the changes since `base`.
+#[derive(Serialize)]
+pub(crate) struct AffectedReport {
+    pub base: String,
+    pub changed_files: Vec<ChangedFileRecord>,
+    pub entities: Vec<AffectedEntityRecord>,
+    pub files: Vec<String>,
+    pub directories: Vec<String>,
+    /// Only computed with `--tests`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tests: Option<Vec<String>>,
+    /// Only computed with `--runner`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_targets: Option<Vec<TestTarget>>,
+    /// Only computed with `--routes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routes: Option<Vec<RouteRecord>>,
+}
+
+impl Report for AffectedReport {
+    const SCHEMA: &'static str = "sting.affected";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[
+        ("changed_files", "changed_file"),
+        ("entities", "entity"),
+        ("files", "file"),
+        ("directories", "directory"),
+        ("tests", "test"),
+        ("test_targets", "test_target"),
+        ("routes", "route"),
+    ];
+}
+
+/// `sting.routes`: the route tree, one record per root route.
+#[derive(Serialize)]
+pub(crate) struct RoutesReport<'a> {
+    pub routes: &'a [RouteNode],
+    pub count: usize,
+}
+
+impl Report for RoutesReport<'_> {
+    const SCHEMA: &'static str = "sting.routes";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("routes", "route")];
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ChainPath {
+    pub entities: Vec<EntityRecord>,
+}
+
+/// `sting.chain`: dependency paths from `start` to `end`.
+#[derive(Serialize)]
+pub(crate) struct ChainReport {
+    pub start: String,
+    pub end: String,
+    pub paths: Vec<ChainPath>,
+    /// Whether `--max-paths` cut the search short
+    pub limited: bool,
+}
+
+impl Report for ChainReport {
+    const SCHEMA: &'static str = "sting.chain";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("paths", "path")];
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CycleRecord {
+    pub entities: Vec<EntityRecord>,
+}
+
+/// `sting.cycles`: circular dependencies.
+#[derive(Serialize)]
+pub(crate) struct CyclesReport {
+    pub cycles: Vec<CycleRecord>,
+    pub count: usize,
+    /// Whether `--max-cycles` cut the search short
+    pub limited: bool,
+}
+
+impl Report for CyclesReport {
+    const SCHEMA: &'static str = "sting.cycles";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("cycles", "cycle")];
+}
+
+/// `sting.graph-diff`: how the dependency graph changed between two
+/// revisions.
+#[derive(Serialize)]
+pub(crate) struct GraphDiffReport {
+    pub base: String,
+    /// None when the working directory was compared
+    pub head: Option<String>,
+    #[serde(flatten)]
+    pub diff: GraphDiff,
+}
+
+impl Report for GraphDiffReport {
+    const SCHEMA: &'static str = "sting.graph-diff";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[
+        ("added_entities", "added_entity"),
+        ("removed_entities", "removed_entity"),
+        ("added_edges", "added_edge"),
+        ("removed_edges", "removed_edge"),
+        ("new_cycles", "new_cycle"),
+        ("resolved_cycles", "resolved_cycle"),
+        ("dependency_changes", "dependency_change"),
+    ];
+}
+
+/// A dependency of `source` on `target`.
+#[derive(Debug, Serialize)]
+pub(crate) struct EdgeRecord {
+    pub source: EntityRecord,
+    pub target: EntityRecord,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct DependencyCountChange {
+    #[serde(flatten)]
+    pub entity: EntityRecord,
+    pub before: usize,
+    pub after: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct RankRecord {
+    #[serde(flatten)]
+    pub entity: EntityRecord,
+    pub count: usize,
+}
+
+/// `sting.rank`: entities ordered by a metric, lowest first.
+#[derive(Serialize)]
+pub(crate) struct RankReport {
+    pub by: &'static str,
+    pub entities: Vec<RankRecord>,
+}
+
+impl Report for RankReport {
+    const SCHEMA: &'static str = "sting.rank";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("entities", "entity")];
+}
+
+/// `sting.mem-leaks`: entities with potential memory leaks and all of
+/// their findings.
+#[derive(Serialize)]
+pub(crate) struct MemLeaksReport<'a> {
+    pub reports: &'a [EntityLeakReport],
+    pub total: usize,
+}
+
+impl Report for MemLeaksReport<'_> {
+    const SCHEMA: &'static str = "sting.mem-leaks";
+    const RECORDS: &'static [(&'static str, &'static str)] = &[("reports", "report")];
+}
+
+/// `sting.affected-mem-leaks`: memory leak findings of affected files.
+#[derive(Serialize)]
+pub(crate) struct AffectedMemLeaksReport<'a> {
+    pub base: String,
+    pub changed_files: usize,
+    pub files: Vec<String>,
+    pub reports: &'a [EntityLeakReport],
+    pub total: usize,
+}
+
+impl Report for AffectedMemLeaksReport<'_> {
+    const SCHEMA: &'static str = "sting.affected-mem-leaks";
+    const RECORDS: &'static [(&'static str, &'static str)] =
+        &[("files", "file"), ("reports", "report")];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct TestReport {
+        entities: Vec<TestEntity>,
+        files: Vec<String>,
+        total: usize,
+    }
+
+    #[derive(Serialize)]
+    struct TestEntity {
+        name: String,
+    }
+
+    impl Report for TestReport {
+        const SCHEMA: &'static str = "sting.test";
+        const RECORDS: &'static [(&'static str, &'static str)] =
+            &[("entities", "entity"), ("files", "file")];
+    }
+
+    fn report() -> TestReport {
+        TestReport {
+            entities: vec![
+                TestEntity {
+                    name: "A".to_string(),
+                },
+                TestEntity {
+                    name: "B".to_string(),
+                },
+            ],
+            files: vec!["a.ts".to_string()],
+            total: 2,
+        }
+    }
+
+    #[test]
+    fn test_json_adds_schema_and_version() {
+        let lines = render(OutputFormat::Json, &report()).unwrap();
+        assert_eq!(lines.len(), 1);
+
+        let value: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(value["schema"], "sting.test");
+        assert_eq!(value["version"], SCHEMA_VERSION);
+        assert_eq!(value["entities"][1]["name"], "B");
+        assert_eq!(value["total"], 2);
+    }
+
+    #[test]
+    fn test_ndjson_emits_one_line_per_record() {
+        let lines = render(OutputFormat::Ndjson, &report()).unwrap();
+        let values: Vec<Value> = lines
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[0]["record"], "entity");
+        assert_eq!(values[0]["name"], "A");
+        assert_eq!(values[0]["schema"], "sting.test");
+        assert_eq!(values[2]["record"], "file");
+        assert_eq!(values[2]["value"], "a.ts");
+        assert_eq!(values[3]["record"], "summary");
+        assert_eq!(values[3]["total"], 2);
+        assert!(values[3].get("entities").is_none());
+    }
+}
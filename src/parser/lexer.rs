@@ -0,0 +1,477 @@
+/// Kind of a lexical token. Keywords are lexed as identifiers and told
+/// apart by their text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Ident,
+    String,
+    /// A literal chunk of a template string, up to a `${` or the closing backtick
+    Template,
+    Number,
+    Regex,
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    /// Whether a line break separates this token from the previous one
+    pub newline_before: bool,
+}
+
+impl<'a> Token<'a> {
+    /// Returns true for an identifier/keyword or punctuator with the given text.
+    pub fn is(&self, text: &str) -> bool {
+        matches!(self.kind, TokenKind::Ident | TokenKind::Punct) && self.text == text
+    }
+
+    pub fn is_ident(&self) -> bool {
+        self.kind == TokenKind::Ident
+    }
+
+    /// The contents of a string literal without its quotes.
+    pub fn string_value(&self) -> Option<&'a str> {
+        if self.kind != TokenKind::String || self.text.len() < 2 {
+            return None;
+        }
+        Some(&self.text[1..self.text.len() - 1])
+    }
+
+    /// The contents of a string literal or of a template literal without
+    /// substitutions.
+    pub fn literal_value(&self) -> Option<&'a str> {
+        let is_template = self.kind == TokenKind::Template
+            && self.text.len() >= 2
+            && self.text.starts_with('`')
+            && self.text.ends_with('`');
+        if is_template {
+            return Some(&self.text[1..self.text.len() - 1]);
+        }
+        self.string_value()
+    }
+}
+
+/// Keywords after which a `/` starts a regular expression, not a division.
+const REGEX_PRECEDING_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+const MULTI_CHAR_PUNCTUATORS: &[&str] = &["...", "=>", "?.", "??"];
+
+/// Splits TypeScript source into tokens. Comments and whitespace are
+/// dropped; strings, template literals and regular expressions become
+/// single tokens so their contents never look like code.
+pub(crate) fn tokenize(source: &str) -> Vec<Token<'_>> {
+    Lexer::new(source).run()
+}
+
+/// The source with comments and the text of template literals blanked
+/// out, for matching code patterns. Line breaks and byte offsets are those
+/// of the source.
+pub(crate) fn code_only(source: &str, tokens: &[Token]) -> String {
+    fn blank(out: &mut String, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\n' | '\r' | '\t' => out.push(c),
+                c => out.extend(std::iter::repeat_n(' ', c.len_utf8())),
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(source.len());
+    let mut pos = 0;
+    for token in tokens {
+        blank(&mut out, &source[pos..token.start]);
+        if token.kind == TokenKind::Template {
+            // Keep the delimiters: a backtick or `}` before, a backtick or `${` after
+            let open = 1;
+            let close = if token.text.ends_with("${") { 2 } else { 1 };
+            let close = close.min(token.text.len() - open);
+            out.push_str(&token.text[..open]);
+            blank(&mut out, &token.text[open..token.text.len() - close]);
+            out.push_str(&token.text[token.text.len() - close..]);
+        } else {
+            out.push_str(token.text);
+        }
+        pos = token.end;
+    }
+    blank(&mut out, &source[pos..]);
+
+    out
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    line: usize,
+    newline_before: bool,
+    brace_depth: usize,
+    /// Brace depths at which `${` template expressions were opened
+    template_stack: Vec<usize>,
+    tokens: Vec<Token<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Lexer {
+            source,
+            pos: 0,
+            line: 1,
+            newline_before: false,
+            brace_depth: 0,
+            template_stack: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> Vec<Token<'a>> {
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\n' => {
+                    self.line += 1;
+                    self.newline_before = true;
+                    self.pos += 1;
+                }
+                c if c.is_whitespace() => self.pos += c.len_utf8(),
+                '/' if self.peek(1) == Some('/') => self.skip_line_comment(),
+                '/' if self.peek(1) == Some('*') => self.skip_block_comment(),
+                '/' if self.regex_allowed() => self.lex_regex(),
+                '\'' | '"' => self.lex_string(c),
+                '`' => {
+                    let start = self.pos;
+                    let line = self.line;
+                    self.pos += 1;
+                    self.lex_template_chunk(start, line);
+                }
+                '}' if self.template_stack.last() == Some(&self.brace_depth) => {
+                    self.template_stack.pop();
+                    let start = self.pos;
+                    let line = self.line;
+                    self.pos += 1;
+                    self.lex_template_chunk(start, line);
+                }
+                c if c.is_ascii_digit()
+                    || (c == '.' && self.peek(1).is_some_and(|n| n.is_ascii_digit())) =>
+                {
+                    self.lex_number()
+                }
+                c if is_ident_start(c) => self.lex_ident(),
+                _ => self.lex_punct(c),
+            }
+        }
+
+        self.tokens
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.source[self.pos..].chars().nth(offset)
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, line: usize) {
+        self.tokens.push(Token {
+            kind,
+            text: &self.source[start..self.pos],
+            start,
+            end: self.pos,
+            line,
+            newline_before: self.newline_before,
+        });
+        self.newline_before = false;
+    }
+
+    fn skip_line_comment(&mut self) {
+        while let Some(c) = self.peek(0) {
+            if c == '\n' {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn skip_block_comment(&mut self) {
+        self.pos += 2;
+        while let Some(c) = self.peek(0) {
+            if c == '*' && self.peek(1) == Some('/') {
+                self.pos += 2;
+                return;
+            }
+            if c == '\n' {
+                self.line += 1;
+                self.newline_before = true;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn regex_allowed(&self) -> bool {
+        match self.tokens.last() {
+            None => true,
+            Some(prev) => match prev.kind {
+                TokenKind::Punct => !matches!(prev.text, ")" | "]" | "}"),
+                TokenKind::Ident => REGEX_PRECEDING_KEYWORDS.contains(&prev.text),
+                TokenKind::Template => prev.text.ends_with("${"),
+                _ => false,
+            },
+        }
+    }
+
+    fn lex_regex(&mut self) {
+        let start = self.pos;
+        let line = self.line;
+        self.pos += 1;
+        let mut in_class = false;
+
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\n' => break,
+                '\\' => {
+                    self.pos += 1;
+                    if let Some(next) = self.peek(0) {
+                        self.pos += next.len_utf8();
+                    }
+                    continue;
+                }
+                '[' => in_class = true,
+                ']' => in_class = false,
+                '/' if !in_class => {
+                    self.pos += 1;
+                    while self.peek(0).is_some_and(|f| f.is_ascii_alphabetic()) {
+                        self.pos += 1;
+                    }
+                    break;
+                }
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+
+        self.push(TokenKind::Regex, start, line);
+    }
+
+    fn lex_string(&mut self, quote: char) {
+        let start = self.pos;
+        let line = self.line;
+        self.pos += 1;
+
+        while let Some(c) = self.peek(0) {
+            self.pos += c.len_utf8();
+            match c {
+                '\\' => {
+                    if let Some(next) = self.peek(0) {
+                        if next == '\n' {
+                            self.line += 1;
+                        }
+                        self.pos += next.len_utf8();
+                    }
+                }
+                '\n' => {
+                    // Unterminated string, stop at the line end
+                    self.line += 1;
+                    break;
+                }
+                c if c == quote => break,
+                _ => {}
+            }
+        }
+
+        self.push(TokenKind::String, start, line);
+    }
+
+    /// Lexes template text up to the closing backtick or the next `${`,
+    /// starting right after the backtick or the `}` that ended an expression.
+    fn lex_template_chunk(&mut self, start: usize, line: usize) {
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\\' => {
+                    self.pos += 1;
+                    if let Some(next) = self.peek(0) {
+                        if next == '\n' {
+                            self.line += 1;
+                        }
+                        self.pos += next.len_utf8();
+                    }
+                }
+                '`' => {
+                    self.pos += 1;
+                    break;
+                }
+                '$' if self.peek(1) == Some('{') => {
+                    self.pos += 2;
+                    self.template_stack.push(self.brace_depth);
+                    break;
+                }
+                '\n' => {
+                    self.line += 1;
+                    self.pos += 1;
+                }
+                _ => self.pos += c.len_utf8(),
+            }
+        }
+
+        self.push(TokenKind::Template, start, line);
+    }
+
+    fn lex_number(&mut self) {
+        let start = self.pos;
+        let line = self.line;
+
+        while let Some(c) = self.peek(0) {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        self.push(TokenKind::Number, start, line);
+    }
+
+    fn lex_ident(&mut self) {
+        let start = self.pos;
+        let line = self.line;
+
+        while let Some(c) = self.peek(0) {
+            if is_ident_part(c) {
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+
+        self.push(TokenKind::Ident, start, line);
+    }
+
+    fn lex_punct(&mut self, c: char) {
+        let start = self.pos;
+        let line = self.line;
+
+        if let Some(punct) = MULTI_CHAR_PUNCTUATORS
+            .iter()
+            .find(|p| self.source[self.pos..].starts_with(*p))
+        {
+            self.pos += punct.len();
+        } else {
+            self.pos += c.len_utf8();
+            match c {
+                '{' => self.brace_depth += 1,
+                '}' => self.brace_depth = self.brace_depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        self.push(TokenKind::Punct, start, line);
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_ident_part(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(source: &str) -> Vec<&str> {
+        tokenize(source).iter().map(|t| t.text).collect()
+    }
+
+    #[test]
+    fn test_tokenize_skips_comments() {
+        assert_eq!(
+            texts("const a = 1; // export class Foo\n/* export class Bar */ let b"),
+            vec!["const", "a", "=", "1", ";", "let", "b"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_template_with_expressions() {
+        let tokens = tokenize("const s = `export class ${name} {}`;");
+        let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Ident,
+                TokenKind::Ident,
+                TokenKind::Punct,
+                TokenKind::Template,
+                TokenKind::Ident,
+                TokenKind::Template,
+                TokenKind::Punct,
+            ]
+        );
+        assert_eq!(tokens[3].text, "`export class ${");
+        assert_eq!(tokens[5].text, "} {}`");
+    }
+
+    #[test]
+    fn test_tokenize_nested_braces_in_template_expression() {
+        assert_eq!(
+            texts("`a${ fn({ x: 1 }) }b` + c"),
+            vec![
+                "`a${", "fn", "(", "{", "x", ":", "1", "}", ")", "}b`", "+", "c"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_regex_and_division() {
+        let tokens = tokenize("const r = /'\\/[/]/g; const d = a / b / c;");
+        assert_eq!(tokens[3].kind, TokenKind::Regex);
+        assert_eq!(tokens[3].text, "/'\\/[/]/g");
+        assert!(tokens[7..].iter().all(|t| t.kind != TokenKind::Regex));
+    }
+
+    #[test]
+    fn test_tokenize_tracks_lines_and_newlines() {
+        let tokens = tokenize("a\n/* x\ny */ b\n\nc");
+        assert_eq!(tokens[0].line, 1);
+        assert_eq!(tokens[1].line, 3);
+        assert!(tokens[1].newline_before);
+        assert_eq!(tokens[2].line, 5);
+    }
+
+    #[test]
+    fn test_tokenize_strings_with_escapes() {
+        let tokens = tokenize(r#"f('it\'s', "a \"b\"")"#);
+        assert_eq!(tokens[2].string_value(), Some(r"it\'s"));
+        assert_eq!(tokens[4].string_value(), Some(r#"a \"b\""#));
+    }
+
+    #[test]
+    fn test_code_only_blanks_comments_and_template_text() {
+        let source = "a(); /* x\ny */ b(`s ${c} t`); // d";
+        let code = code_only(source, &tokenize(source));
+
+        assert_eq!(code, "a();     \n     b(`  ${c}  `);     ");
+        assert_eq!(code.len(), source.len());
+    }
+
+    #[test]
+    fn test_tokenize_multi_char_punctuators() {
+        assert_eq!(
+            texts("(...args) => a?.b ?? c"),
+            vec!["(", "...", "args", ")", "=>", "a", "?.", "b", "??", "c"]
+        );
+    }
+}
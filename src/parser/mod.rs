@@ -0,0 +1,834 @@
+mod lexer;
+mod routes;
+mod syntax;
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::angular::{ANGULAR_DECORATORS, AngularMetadata, parse_template};
+use crate::config::StingConfig;
+use crate::entity::{EdgeKind, Entity, EntityType, ImportInfo};
+use crate::source::{FileSource, normalize};
+use crate::tsconfig::TsConfigPaths;
+
+pub(crate) use lexer::{Token, code_only, tokenize};
+use routes::RouteResolver;
+pub(crate) use routes::{Route, RouteTable};
+pub(crate) use syntax::{Declaration, DeclarationKind, SourceFile};
+use syntax::{DynamicImportKind, ExportDecl};
+
+/// A re-export statement of a module, with the source path already resolved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) enum ReExport {
+    /// `export { imported as exported } from 'path'`
+    Named {
+        exported: String,
+        imported: String,
+        path: String,
+    },
+    /// `export * as exported from 'path'`
+    Namespace { exported: String, path: String },
+    /// `export * from 'path'`
+    All { path: String },
+}
+
+/// Where an import is referenced from the importing file's code.
+enum ImportUse {
+    /// Through a local binding; `import { A as B }` binds `B`
+    Binding(String),
+    /// At a token, for dynamic imports and workers
+    Token(usize),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct FileParseResult {
+    pub entities: Vec<Entity>,
+    pub imports: Vec<ImportInfo>,
+    pub reexports: Vec<ReExport>,
+    pub routes: Vec<RouteTable>,
+    /// Module paths the imports of the file may resolve to, without
+    /// extension. Adding or removing a file at one of them can change
+    /// how the imports resolve.
+    pub modules: Vec<String>,
+}
+
+pub(crate) struct Parser<'a> {
+    root_path: &'a Path,
+    source: &'a FileSource,
+    ts_paths: Option<TsConfigPaths>,
+    decorators: HashMap<String, EntityType>,
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser that reads files and resolves imports in `source`.
+    pub fn new(root_path: &'a Path, config: &StingConfig, source: &'a FileSource) -> Self {
+        let mut ts_paths = TsConfigPaths::load(root_path, source);
+
+        // Aliases from sting.toml win over the tsconfig
+        if !config.aliases.is_empty() {
+            ts_paths
+                .get_or_insert_with(TsConfigPaths::default)
+                .add_aliases(root_path, &config.aliases);
+        }
+
+        Parser {
+            root_path,
+            source,
+            ts_paths,
+            decorators: config.decorators.clone(),
+        }
+    }
+
+    pub fn parse(&self, file_path: &str) -> Result<FileParseResult> {
+        let content = self.source.read_to_string(Path::new(file_path))?;
+
+        Ok(self.parse_content(file_path, &content))
+    }
+
+    /// Everything besides the file content that parse results depend on,
+    /// used to invalidate cached results.
+    pub fn fingerprint(&self) -> String {
+        let mut decorators: Vec<_> = self.decorators.iter().collect();
+        decorators.sort_by(|a, b| a.0.cmp(b.0));
+        format!("{:?} {:?}", decorators, self.ts_paths)
+    }
+
+    pub fn parse_content(&self, file_path: &str, content: &str) -> FileParseResult {
+        let source = SourceFile::parse(content);
+        let mut entities = Vec::new();
+
+        let file_imports = self.imports_of(&source, file_path);
+        let imports: Vec<ImportInfo> = file_imports.iter().map(|(i, _)| i.clone()).collect();
+
+        // If this is a worker file, treat the entire file as a single Worker entity
+        if is_worker_file(file_path)
+            && let Some(worker_name) = worker_filename_to_entity_name(file_path)
+        {
+            entities.push(Entity::new(
+                worker_name,
+                EntityType::Worker,
+                file_path.to_string(),
+                Arc::new(imports.clone()),
+            ));
+            return FileParseResult {
+                entities,
+                imports,
+                reexports: Vec::new(),
+                routes: Vec::new(),
+                modules: self.modules_of(&source, file_path),
+            };
+        }
+
+        let exported = source.exported_declarations();
+        let entity_deps = attribute_imports(&source, file_path, &exported, &file_imports);
+
+        for ((name, decl), deps) in exported.iter().zip(entity_deps) {
+            if entities.iter().any(|e: &Entity| e.name == *name) {
+                continue;
+            }
+
+            let mut entity = Entity::new(
+                name.to_string(),
+                self.entity_type_of(decl),
+                file_path.to_string(),
+                Arc::new(deps),
+            );
+
+            // Exported entities used locally in the same file are not unused
+            entity.used = source.is_referenced(&decl.name);
+            entity.angular = angular_metadata(&source, decl, file_path).map(Arc::new);
+            entities.push(entity);
+        }
+
+        let reexports = self.reexports_of(&source, file_path);
+        let routes = self.routes_of(&source, file_path);
+
+        FileParseResult {
+            entities,
+            imports,
+            reexports,
+            routes,
+            modules: self.modules_of(&source, file_path),
+        }
+    }
+
+    /// Module paths the import specifiers of the file are looked up at,
+    /// see [`FileParseResult::modules`].
+    fn modules_of(&self, source: &SourceFile, file_path: &str) -> Vec<String> {
+        let specifiers = source
+            .imports
+            .iter()
+            .map(|import| import.source.clone())
+            .chain(source.exports.iter().filter_map(|export| match export {
+                ExportDecl::Named { source, .. } => source.clone(),
+                ExportDecl::All { source, .. } => Some(source.clone()),
+                ExportDecl::Default { .. } => None,
+            }))
+            .chain(source.dynamic_imports.iter().map(|import| {
+                if import.kind == DynamicImportKind::Worker {
+                    worker_module(&import.source)
+                } else {
+                    import.source.clone()
+                }
+            }));
+
+        let mut modules: Vec<String> = specifiers
+            .flat_map(|specifier| self.import_candidates(file_path, &specifier))
+            .map(|path| normalize(&path).to_string_lossy().to_string())
+            .collect();
+        modules.sort();
+        modules.dedup();
+        modules
+    }
+
+    /// Route arrays of the file, with the entities they name resolved like
+    /// imports.
+    fn routes_of(&self, source: &SourceFile, file_path: &str) -> Vec<RouteTable> {
+        let mut locals: HashMap<&str, ImportInfo> = HashMap::new();
+        for (name, decl) in source.exported_declarations() {
+            locals.insert(
+                &decl.name,
+                ImportInfo::new(name.to_string(), file_path.to_string()),
+            );
+        }
+        for import in &source.imports {
+            let Some(resolved_path) = self.resolve_import_path(file_path, &import.source) else {
+                continue;
+            };
+            for specifier in &import.specifiers {
+                let name = match specifier.imported.as_str() {
+                    "default" | "*" => &specifier.local,
+                    _ => &specifier.imported,
+                };
+                locals.insert(
+                    &specifier.local,
+                    ImportInfo::new(name.clone(), resolved_path.clone()),
+                );
+            }
+        }
+
+        let local = |name: &str| locals.get(name).cloned();
+        let lazy = |specifier: &str, name: &str| {
+            let path = self.resolve_import_path(file_path, specifier)?;
+            Some(ImportInfo {
+                kind: EdgeKind::Lazy,
+                ..ImportInfo::new(name.to_string(), path)
+            })
+        };
+
+        routes::route_tables(
+            source,
+            file_path,
+            &RouteResolver {
+                local: &local,
+                lazy: &lazy,
+            },
+        )
+    }
+
+    #[cfg(test)]
+    pub fn extract_reexports(&self, content: &str, file_path: &str) -> Vec<ReExport> {
+        self.reexports_of(&SourceFile::parse(content), file_path)
+    }
+
+    fn reexports_of(&self, source: &SourceFile, file_path: &str) -> Vec<ReExport> {
+        let mut reexports = Vec::new();
+
+        for export in &source.exports {
+            match export {
+                ExportDecl::Named {
+                    specifiers,
+                    source: Some(import_path),
+                    ..
+                } => {
+                    let Some(resolved_path) = self.resolve_import_path(file_path, import_path)
+                    else {
+                        continue;
+                    };
+
+                    for specifier in specifiers {
+                        reexports.push(ReExport::Named {
+                            exported: specifier.exported.clone(),
+                            imported: specifier.local.clone(),
+                            path: resolved_path.clone(),
+                        });
+                    }
+                }
+                ExportDecl::All {
+                    namespace,
+                    source: import_path,
+                } => {
+                    let Some(resolved_path) = self.resolve_import_path(file_path, import_path)
+                    else {
+                        continue;
+                    };
+
+                    match namespace {
+                        Some(namespace) => reexports.push(ReExport::Namespace {
+                            exported: namespace.clone(),
+                            path: resolved_path,
+                        }),
+                        None => reexports.push(ReExport::All {
+                            path: resolved_path,
+                        }),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        reexports
+    }
+
+    #[cfg(test)]
+    pub fn extract_imports(&self, content: &str, file_path: &str) -> Vec<ImportInfo> {
+        self.imports_of(&SourceFile::parse(content), file_path)
+            .into_iter()
+            .map(|(import, _)| import)
+            .collect()
+    }
+
+    fn imports_of(&self, source: &SourceFile, file_path: &str) -> Vec<(ImportInfo, ImportUse)> {
+        let mut imports = Vec::new();
+
+        for import in &source.imports {
+            let Some(resolved_path) = self.resolve_import_path(file_path, &import.source) else {
+                continue;
+            };
+
+            for specifier in &import.specifiers {
+                // Default and namespace imports are tracked by their local name
+                let name = match specifier.imported.as_str() {
+                    "default" | "*" => &specifier.local,
+                    _ => &specifier.imported,
+                };
+                let kind = if specifier.type_only {
+                    EdgeKind::Type
+                } else {
+                    EdgeKind::Import
+                };
+                imports.push((
+                    ImportInfo {
+                        kind,
+                        ..ImportInfo::new(name.clone(), resolved_path.clone())
+                    },
+                    ImportUse::Binding(specifier.local.clone()),
+                ));
+            }
+        }
+
+        for import in &source.dynamic_imports {
+            if import.kind == DynamicImportKind::Worker {
+                let worker_path = worker_module(&import.source);
+                if let Some(resolved_path) = self.resolve_import_path(file_path, &worker_path)
+                    && let Some(worker_name) = worker_filename_to_entity_name(&resolved_path)
+                {
+                    imports.push((
+                        ImportInfo {
+                            kind: EdgeKind::Worker,
+                            ..ImportInfo::new(worker_name, resolved_path)
+                        },
+                        ImportUse::Token(import.token),
+                    ));
+                }
+                continue;
+            }
+
+            let Some(resolved_path) = self.resolve_import_path(file_path, &import.source) else {
+                continue;
+            };
+            let kind = match import.kind {
+                DynamicImportKind::Lazy => EdgeKind::Lazy,
+                _ => EdgeKind::Dynamic,
+            };
+            for name in &import.names {
+                imports.push((
+                    ImportInfo {
+                        kind,
+                        ..ImportInfo::new(name.clone(), resolved_path.clone())
+                    },
+                    ImportUse::Token(import.token),
+                ));
+            }
+        }
+
+        imports
+    }
+
+    /// Maps a declaration to its entity type. Known decorators take
+    /// precedence over the declaration kind; the closest one wins.
+    fn entity_type_of(&self, decl: &Declaration) -> EntityType {
+        if let Some(entity_type) = decl
+            .decorators
+            .iter()
+            .rev()
+            .find_map(|name| self.decorators.get(name))
+        {
+            return entity_type.clone();
+        }
+
+        match decl.kind {
+            DeclarationKind::Class => EntityType::Class,
+            DeclarationKind::Interface => EntityType::Interface,
+            DeclarationKind::TypeAlias => EntityType::Type,
+            DeclarationKind::Enum => EntityType::Enum,
+            DeclarationKind::Function => EntityType::Function,
+            DeclarationKind::Variable if decl.function_initializer => EntityType::Function,
+            DeclarationKind::Variable | DeclarationKind::Namespace => EntityType::Const,
+        }
+    }
+
+    /// Resolves an import specifier to a file path. Relative specifiers are
+    /// resolved against the importing file, everything else through the
+    /// tsconfig `paths`/`baseUrl`. Returns None for external packages.
+    fn resolve_import_path(&self, importing_file: &str, import_source: &str) -> Option<String> {
+        if import_source.starts_with("./") || import_source.starts_with("../") {
+            let importing_dir = Path::new(importing_file).parent()?;
+            return Some(resolve_module_path(
+                self.source,
+                &importing_dir.join(import_source),
+            ));
+        }
+
+        let legacy_alias = || {
+            resolve_legacy_alias(import_source, self.root_path)
+                .map(|base_path| resolve_module_path(self.source, &base_path))
+        };
+        let Some(ts_paths) = &self.ts_paths else {
+            return legacy_alias();
+        };
+
+        let candidates = ts_paths.candidates(import_source);
+
+        // Like tsc, the first mapped target that exists wins
+        if let Some(resolved) = candidates
+            .mapped
+            .iter()
+            .find_map(|c| probe_module_path(self.source, c))
+        {
+            return Some(resolved);
+        }
+
+        if let Some(resolved) = candidates
+            .base_url
+            .as_deref()
+            .and_then(|c| probe_module_path(self.source, c))
+        {
+            return Some(resolved);
+        }
+
+        // A mapped alias that points nowhere is still a project import
+        candidates
+            .mapped
+            .first()
+            .map(|c| resolve_module_path(self.source, c))
+            .or_else(legacy_alias)
+    }
+
+    /// Base paths [`Self::resolve_import_path`] may probe for an import.
+    fn import_candidates(&self, importing_file: &str, import_source: &str) -> Vec<PathBuf> {
+        if import_source.starts_with("./") || import_source.starts_with("../") {
+            return Path::new(importing_file)
+                .parent()
+                .map(|dir| dir.join(import_source))
+                .into_iter()
+                .collect();
+        }
+
+        let mut candidates = Vec::new();
+        if let Some(ts_paths) = &self.ts_paths {
+            let paths = ts_paths.candidates(import_source);
+            candidates.extend(paths.mapped);
+            candidates.extend(paths.base_url);
+        }
+        candidates.extend(resolve_legacy_alias(import_source, self.root_path));
+        candidates
+    }
+}
+
+/// Specifier of the worker file a `new Worker(new URL(...))` points at.
+/// Worker paths may or may not have extensions, try .worker.ts first.
+fn worker_module(specifier: &str) -> String {
+    if specifier.ends_with(".worker") {
+        format!("{}.ts", specifier)
+    } else if !specifier.ends_with(".ts") {
+        format!("{}.worker.ts", specifier)
+    } else {
+        specifier.to_string()
+    }
+}
+
+/// Twelve lines of license header and documentation, for tests of line
+/// numbers: a declaration following it is on line 13.
+#[cfg(test)]
+pub(crate) const JSDOC_HEADER: &str = "/**
+ * Copyright (c) the authors.
+ *
+ * Licensed under the MIT license.
+ */
+
+/**
+ * Polls the server.
+ * Runs every second.
+ *
+ * @see PollerService
+ */
+";
+
+/// Start lines of the exported declarations of a file, by name. Empty if
+/// the file cannot be read.
+pub(crate) fn declaration_lines(source: &FileSource, file_path: &str) -> HashMap<String, usize> {
+    let Ok(content) = source.read_to_string(Path::new(file_path)) else {
+        return HashMap::new();
+    };
+    let source = SourceFile::parse(&content);
+    source
+        .exported_declarations()
+        .into_iter()
+        .map(|(name, decl)| (name.to_string(), decl.span.start_line))
+        .collect()
+}
+
+/// Names of the exported declarations of a file that `changed_lines`
+/// leave alone, see [`SourceFile::changed_declarations`]. A declaration
+/// also changes when it references a changed one. Empty when the file
+/// cannot be read or the change is not limited to declarations.
+pub(crate) fn unchanged_exports(
+    source: &FileSource,
+    file_path: &str,
+    changed_lines: &[Range<usize>],
+) -> HashSet<String> {
+    let Ok(content) = source.read_to_string(Path::new(file_path)) else {
+        return HashSet::new();
+    };
+    let source = SourceFile::parse(&content);
+    let Some(mut changed) = source.changed_declarations(changed_lines) else {
+        return HashSet::new();
+    };
+
+    loop {
+        let names: HashSet<&str> = changed
+            .iter()
+            .map(|idx| source.declarations[*idx].name.as_str())
+            .collect();
+        let dependents: Vec<usize> = (0..source.declarations.len())
+            .filter(|idx| !changed.contains(idx))
+            .filter(|idx| {
+                source
+                    .references(&source.declarations[*idx])
+                    .iter()
+                    .any(|name| names.contains(name))
+            })
+            .collect();
+        if dependents.is_empty() {
+            break;
+        }
+        changed.extend(dependents);
+    }
+
+    source
+        .exported_declarations()
+        .into_iter()
+        .filter(|(_, decl)| {
+            !changed
+                .iter()
+                .any(|idx| std::ptr::eq(&source.declarations[*idx], *decl))
+        })
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Strips single-line (//) and multi-line (/* */) comments from content.
+/// Preserves strings so that comment-like patterns inside strings are not stripped.
+pub(crate) fn strip_comments(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        if in_string.is_none() && (c == '"' || c == '\'' || c == '`') {
+            in_string = Some(c);
+            result.push(c);
+            continue;
+        }
+
+        if let Some(quote) = in_string {
+            result.push(c);
+            if c == '\\' {
+                if let Some(&next) = chars.peek() {
+                    result.push(next);
+                    chars.next();
+                }
+            } else if c == quote {
+                in_string = None;
+            }
+            continue;
+        }
+
+        if c == '/'
+            && let Some(&next) = chars.peek()
+        {
+            if next == '/' {
+                chars.next();
+                while let Some(&ch) = chars.peek() {
+                    if ch == '\n' {
+                        break;
+                    }
+                    chars.next();
+                }
+                continue;
+            } else if next == '*' {
+                chars.next();
+                while let Some(ch) = chars.next() {
+                    if ch == '*'
+                        && let Some(&peek) = chars.peek()
+                        && peek == '/'
+                    {
+                        chars.next();
+                        break;
+                    }
+                }
+                continue;
+            }
+        }
+
+        result.push(c);
+    }
+
+    result
+}
+
+/// Selector, pipe name, template and stylesheets of a declaration decorated with
+/// `@Component`, `@Directive` or `@Pipe`. Only literal values are read.
+fn angular_metadata(
+    source: &SourceFile,
+    decl: &Declaration,
+    file_path: &str,
+) -> Option<AngularMetadata> {
+    let decorator = decl
+        .decorators
+        .iter()
+        .rev()
+        .find(|name| ANGULAR_DECORATORS.contains(&name.as_str()))?;
+    let properties = source.decorator_properties(decl, decorator);
+    let value = |key: &str| {
+        properties
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| *value)
+    };
+    let is_pipe = decorator == "Pipe";
+    let dir = Path::new(file_path).parent()?;
+    let resolve = |url: &str| normalize(&dir.join(url)).to_string_lossy().to_string();
+
+    Some(AngularMetadata {
+        selector: value("selector").filter(|_| !is_pipe).map(str::to_string),
+        pipe_name: value("name").filter(|_| is_pipe).map(str::to_string),
+        template: value("template").map(parse_template),
+        template_url: value("templateUrl").map(resolve),
+        style_urls: properties
+            .iter()
+            .filter(|(key, _)| *key == "styleUrl" || *key == "styleUrls")
+            .map(|(_, url)| resolve(url))
+            .collect(),
+    })
+}
+
+/// Attributes the file's imports to the exported declarations that reference
+/// them, directly or through non-exported local helpers. References to
+/// other exported declarations of the file become same-file dependencies.
+/// Dependencies a declaration itself injects are of kind `di`.
+///
+/// Attribution falls back to the whole file for imports no declaration
+/// references, such as side-effect imports or imports only used by
+/// top-level statements: every entity of the file depends on them.
+fn attribute_imports(
+    source: &SourceFile,
+    file_path: &str,
+    exported: &[(&str, &Declaration)],
+    imports: &[(ImportInfo, ImportUse)],
+) -> Vec<Vec<ImportInfo>> {
+    let is_exported = |decl: &Declaration| exported.iter().any(|(_, d)| std::ptr::eq(*d, decl));
+
+    // Identifiers and statements reached from each exported declaration
+    let reached: Vec<(HashSet<&str>, HashSet<usize>)> = exported
+        .iter()
+        .map(|(_, decl)| {
+            let mut names = HashSet::new();
+            let mut statements = HashSet::from([decl.tokens.start]);
+            let mut pending = vec![*decl];
+
+            while let Some(current) = pending.pop() {
+                for name in source.references(current) {
+                    if !names.insert(name) {
+                        continue;
+                    }
+                    for helper in source.declarations.iter().filter(|d| d.name == name) {
+                        if !is_exported(helper) && statements.insert(helper.tokens.start) {
+                            pending.push(helper);
+                        }
+                    }
+                }
+            }
+
+            (names, statements)
+        })
+        .collect();
+
+    let injected: Vec<HashSet<&str>> = exported
+        .iter()
+        .map(|(_, decl)| source.injected_names(decl))
+        .collect();
+    let with_kind = |import: &ImportInfo, idx: usize, local: &str| {
+        let mut import = import.clone();
+        if import.kind == EdgeKind::Import && injected[idx].contains(local) {
+            import.kind = EdgeKind::Di;
+        }
+        import
+    };
+
+    let mut deps: Vec<Vec<ImportInfo>> = vec![Vec::new(); exported.len()];
+
+    for (import, import_use) in imports {
+        let users: Vec<usize> = reached
+            .iter()
+            .enumerate()
+            .filter(|(_, (names, statements))| match import_use {
+                ImportUse::Binding(local) => names.contains(local.as_str()),
+                ImportUse::Token(token) => source
+                    .declaration_at(*token)
+                    .is_some_and(|d| statements.contains(&d.tokens.start)),
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let local = match import_use {
+            ImportUse::Binding(local) => local.as_str(),
+            ImportUse::Token(_) => "",
+        };
+        if users.is_empty() {
+            (0..deps.len()).for_each(|idx| deps[idx].push(with_kind(import, idx, local)));
+        } else {
+            users
+                .into_iter()
+                .for_each(|idx| deps[idx].push(with_kind(import, idx, local)));
+        }
+    }
+
+    for (idx, (names, _)) in reached.iter().enumerate() {
+        for (name, decl) in exported {
+            if names.contains(decl.name.as_str()) && !std::ptr::eq(*decl, exported[idx].1) {
+                let import = ImportInfo::new(name.to_string(), file_path.to_string());
+                deps[idx].push(with_kind(&import, idx, &decl.name));
+            }
+        }
+    }
+
+    for entity_deps in &mut deps {
+        // Of a type-only and a value import of the same entity, the value wins
+        entity_deps.sort_by_key(|import| import.kind == EdgeKind::Type);
+        let mut seen = HashSet::new();
+        entity_deps.retain(|import| seen.insert(import.id.clone()));
+    }
+
+    deps
+}
+
+/// Hard-coded aliases used when no tsconfig path maps the import.
+fn resolve_legacy_alias(import_source: &str, root_path: &Path) -> Option<PathBuf> {
+    if import_source == "@ui-annotator" {
+        Some(root_path.join("libs/ui-annotator/src/lib/create-ui-annotator"))
+    } else {
+        import_source
+            .strip_prefix("@awork/")
+            .map(|rest| root_path.join("libs/shared/src/lib").join(rest))
+    }
+}
+
+/// Finds the file a module path refers to, trying TypeScript extensions and
+/// index files. Returns None when nothing exists in the source.
+fn probe_module_path(source: &FileSource, base_path: &Path) -> Option<String> {
+    let extensions = [".ts", ".tsx", "/index.ts", "/index.tsx"];
+
+    for ext in &extensions {
+        let full_path = if let Some(index_file) = ext.strip_prefix('/') {
+            base_path.join(index_file)
+        } else {
+            let path_str = base_path.to_string_lossy();
+            Path::new(&format!("{}{}", path_str, ext)).to_path_buf()
+        };
+
+        if source.exists(&full_path) {
+            return source
+                .canonicalize(&full_path)
+                .ok()?
+                .to_str()
+                .map(|s| s.to_string());
+        }
+    }
+
+    if source.is_file(base_path) {
+        return source
+            .canonicalize(base_path)
+            .ok()?
+            .to_str()
+            .map(|s| s.to_string());
+    }
+
+    None
+}
+
+/// Like `probe_module_path`, but falls back to the `.ts` path when the
+/// module does not exist in the source.
+fn resolve_module_path(source: &FileSource, base_path: &Path) -> String {
+    if let Some(resolved) = probe_module_path(source, base_path) {
+        return resolved;
+    }
+
+    // Missing files keep a clean path, so that imports of a file that was
+    // renamed or deleted still match its old path
+    let path_str = normalize(base_path).to_string_lossy().to_string();
+    if path_str.ends_with(".ts") || path_str.ends_with(".tsx") {
+        path_str
+    } else {
+        format!("{}.ts", path_str)
+    }
+}
+
+/// Checks if a file path is a worker file (ends with .worker.ts)
+fn is_worker_file(file_path: &str) -> bool {
+    file_path.ends_with(".worker.ts")
+}
+
+/// Converts a worker filename to PascalCase + "Worker" suffix.
+/// Examples:
+///   - "planner-overview.worker.ts" -> "PlannerOverviewWorker"
+///   - "my_worker.worker.ts" -> "MyWorkerWorker"
+///   - "simple.worker.ts" -> "SimpleWorker"
+pub(crate) fn worker_filename_to_entity_name(file_path: &str) -> Option<String> {
+    let path = Path::new(file_path);
+    let file_name = path.file_name()?.to_str()?;
+
+    // Strip the .worker.ts suffix
+    let base_name = file_name.strip_suffix(".worker.ts")?;
+
+    // Convert kebab-case and snake_case to PascalCase
+    let pascal_case: String = base_name
+        .split(['-', '_'])
+        .filter(|s| !s.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    Some(format!("{}Worker", pascal_case))
+}
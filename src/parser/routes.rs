@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+
+use super::syntax::SourceFile;
+use crate::entity::ImportInfo;
+
+/// Route properties whose values are guards.
+const GUARD_PROPERTIES: &[&str] = &[
+    "canActivate",
+    "canActivateChild",
+    "canMatch",
+    "canDeactivate",
+];
+
+/// Functions whose route array argument declares routes.
+const ROUTER_FUNCTIONS: &[&str] = &["forRoot", "forChild", "provideRouter"];
+
+/// A route array of a file, like `export const routes: Routes = [...]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RouteTable {
+    /// The variable holding the routes, `default` for a default export
+    pub name: String,
+    pub file: String,
+    pub routes: Vec<Route>,
+}
+
+/// A route as declared, with the entities it names resolved to the
+/// files declaring them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Route {
+    pub path: String,
+    pub redirect_to: Option<String>,
+    pub component: Option<ImportInfo>,
+    /// Lazy `loadComponent` target
+    pub load_component: Option<ImportInfo>,
+    /// Routes loaded by `loadChildren`, or imported routes given as
+    /// `children`: a route array, an NgModule or a standalone component
+    pub load_children: Option<ImportInfo>,
+    /// `canActivate`, `canActivateChild`, `canMatch` and `canDeactivate`
+    pub guards: Vec<ImportInfo>,
+    /// Values of `resolve`
+    pub resolvers: Vec<ImportInfo>,
+    pub children: Vec<Route>,
+}
+
+impl RouteTable {
+    /// Replaces every entity the routes name.
+    pub fn map_targets(&mut self, map: &impl Fn(&ImportInfo) -> ImportInfo) {
+        fn map_route(route: &mut Route, map: &impl Fn(&ImportInfo) -> ImportInfo) {
+            let targets = route
+                .component
+                .iter_mut()
+                .chain(route.load_component.iter_mut())
+                .chain(route.load_children.iter_mut())
+                .chain(route.guards.iter_mut())
+                .chain(route.resolvers.iter_mut());
+            for target in targets {
+                *target = map(target);
+            }
+            for child in &mut route.children {
+                map_route(child, map);
+            }
+        }
+
+        for route in &mut self.routes {
+            map_route(route, map);
+        }
+    }
+}
+
+/// Resolves the names used in routes: local identifiers, and the module
+/// specifier and export name of a lazy `import()`.
+pub(super) struct RouteResolver<'r> {
+    pub local: &'r dyn Fn(&str) -> Option<ImportInfo>,
+    pub lazy: &'r dyn Fn(&str, &str) -> Option<ImportInfo>,
+}
+
+/// Route arrays of a file: variables typed `Routes` or `Route[]`, arrays
+/// checked with `satisfies Routes` or `as Routes`, and arrays passed to
+/// `RouterModule.forRoot()`, `forChild()` or `provideRouter()`.
+pub(super) fn route_tables(
+    source: &SourceFile,
+    file_path: &str,
+    resolver: &RouteResolver,
+) -> Vec<RouteTable> {
+    let tokens = &source.tokens;
+    let mentions_routes = tokens
+        .iter()
+        .any(|t| t.is("Routes") || t.is("Route") || ROUTER_FUNCTIONS.iter().any(|f| t.is(f)));
+    if !mentions_routes {
+        return Vec::new();
+    }
+
+    let is = |idx: usize, text: &str| tokens.get(idx).is_some_and(|t| t.is(text));
+    let is_routes_type = |idx: usize| {
+        is(idx, "Routes") || (is(idx, "Route") && is(idx + 1, "[") && is(idx + 2, "]"))
+    };
+    let mut tables = Vec::new();
+    let mut idx = 0;
+
+    while idx < tokens.len() {
+        if !is(idx, "[") {
+            idx += 1;
+            continue;
+        }
+        let before = |n: usize, text: &str| idx >= n && is(idx - n, text);
+        let ident_before = |n: usize| {
+            idx.checked_sub(n)
+                .map(|i| &tokens[i])
+                .filter(|t| t.is_ident())
+                .map(|t| t.text)
+        };
+        let close = source.closing(idx, tokens.len());
+
+        // const routes: Routes = [...] / const routes: Route[] = [...]
+        let annotated = if before(1, "=") && before(2, "Routes") && before(3, ":") {
+            ident_before(4)
+        } else if before(1, "=")
+            && before(2, "]")
+            && before(3, "[")
+            && before(4, "Route")
+            && before(5, ":")
+        {
+            ident_before(6)
+        } else {
+            None
+        };
+        // [...] satisfies Routes / export default [...] as Route[]
+        let checked =
+            (is(close + 1, "satisfies") || is(close + 1, "as")) && is_routes_type(close + 2);
+        let checked_name = if !checked {
+            None
+        } else if before(1, "=") {
+            ident_before(2)
+        } else if before(1, "default") {
+            Some("default")
+        } else {
+            None
+        };
+        // RouterModule.forChild([...]) / provideRouter([...])
+        let passed = before(1, "(") && ROUTER_FUNCTIONS.iter().any(|f| before(2, f));
+        let passed_name = passed.then(|| {
+            source
+                .declaration_at(idx)
+                .map_or("default", |decl| decl.name.as_str())
+        });
+
+        match annotated.or(checked_name).or(passed_name) {
+            Some(name) => {
+                tables.push(RouteTable {
+                    name: name.to_string(),
+                    file: file_path.to_string(),
+                    routes: parse_routes(source, idx, close, resolver),
+                });
+                idx = close + 1;
+            }
+            None => idx += 1,
+        }
+    }
+
+    tables
+}
+
+/// Routes of the array literal between `open` and `close`.
+fn parse_routes(
+    source: &SourceFile,
+    open: usize,
+    close: usize,
+    resolver: &RouteResolver,
+) -> Vec<Route> {
+    let tokens = &source.tokens;
+    let mut routes = Vec::new();
+    let mut idx = open + 1;
+
+    while idx < close {
+        let token = &tokens[idx];
+        if token.is("{") {
+            let end = source.closing(idx, close);
+            routes.push(parse_route(source, idx, end, resolver));
+            idx = end + 1;
+        } else if token.is("[") || token.is("(") {
+            idx = source.closing(idx, close) + 1;
+        } else {
+            idx += 1;
+        }
+    }
+
+    routes
+}
+
+/// The route of the object literal between `open` and `close`.
+fn parse_route(source: &SourceFile, open: usize, close: usize, resolver: &RouteResolver) -> Route {
+    let tokens = &source.tokens;
+    let is = |idx: usize, text: &str| idx < close && tokens[idx].is(text);
+    let literal = |idx: usize| {
+        (idx < close)
+            .then(|| tokens[idx].literal_value())
+            .flatten()
+            .map(str::to_string)
+    };
+    let local = |idx: usize| {
+        (idx < close && tokens[idx].is_ident())
+            .then(|| (resolver.local)(tokens[idx].text))
+            .flatten()
+    };
+    // Identifiers of a value that are neither member names nor keys
+    let locals = |range: std::ops::Range<usize>| -> Vec<ImportInfo> {
+        range
+            .filter(|i| !source.is_member_name(*i) && !is(i + 1, ":"))
+            .filter_map(local)
+            .collect()
+    };
+
+    let mut route = Route::default();
+    let mut idx = open + 1;
+
+    while idx < close {
+        let token = &tokens[idx];
+        if token.is("{") || token.is("[") || token.is("(") {
+            idx = source.closing(idx, close) + 1;
+            continue;
+        }
+        if !(token.is_ident() || token.string_value().is_some()) || !is(idx + 1, ":") {
+            idx += 1;
+            continue;
+        }
+
+        let key = token.string_value().unwrap_or(token.text);
+        let value = idx + 2;
+        let end = value_end(source, value, close);
+
+        match key {
+            "path" => route.path = literal(value).unwrap_or_default(),
+            "redirectTo" => route.redirect_to = literal(value),
+            "component" => route.component = local(value),
+            "loadComponent" => route.load_component = lazy_target(source, value..end, resolver),
+            "loadChildren" => route.load_children = lazy_target(source, value..end, resolver),
+            "children" if is(value, "[") => {
+                route.children = parse_routes(source, value, source.closing(value, close), resolver)
+            }
+            "children" => route.load_children = local(value),
+            "resolve" => route.resolvers = locals(value..end),
+            key if GUARD_PROPERTIES.contains(&key) => route.guards = locals(value..end),
+            _ => {}
+        }
+        idx = end;
+    }
+
+    route
+}
+
+/// The target of `() => import('./x').then(m => m.X)`; a default export
+/// without `.then()`.
+fn lazy_target(
+    source: &SourceFile,
+    value: std::ops::Range<usize>,
+    resolver: &RouteResolver,
+) -> Option<ImportInfo> {
+    let tokens = &source.tokens;
+    let import = value
+        .clone()
+        .find(|i| tokens[*i].is("import") && tokens.get(i + 1).is_some_and(|t| t.is("(")))?;
+    let specifier = tokens.get(import + 2)?.string_value()?;
+    let name = (import + 3..value.end)
+        .rev()
+        .find(|i| source.is_member_name(*i) && tokens[*i].is_ident() && !tokens[*i].is("then"))
+        .map_or("default", |i| tokens[i].text);
+
+    (resolver.lazy)(specifier, name)
+}
+
+/// Index of the comma ending the property value starting at `start`, or
+/// `close`.
+fn value_end(source: &SourceFile, start: usize, close: usize) -> usize {
+    let mut idx = start;
+    while idx < close {
+        let token = &source.tokens[idx];
+        if token.is(",") {
+            return idx;
+        }
+        if token.is("{") || token.is("[") || token.is("(") {
+            idx = source.closing(idx, close);
+        }
+        idx += 1;
+    }
+    close
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables(content: &str) -> Vec<RouteTable> {
+        let source = SourceFile::parse(content);
+        let local = |name: &str| Some(ImportInfo::new(name.to_string(), "/local.ts".to_string()));
+        let lazy = |specifier: &str, name: &str| {
+            Some(ImportInfo::new(name.to_string(), specifier.to_string()))
+        };
+        route_tables(
+            &source,
+            "/app.routes.ts",
+            &RouteResolver {
+                local: &local,
+                lazy: &lazy,
+            },
+        )
+    }
+
+    #[test]
+    fn test_route_tables() {
+        let tables = tables(
+            r#"export const routes: Routes = [
+  { path: '', component: HomeComponent },
+  { path: 'old', redirectTo: 'new', pathMatch: 'full' },
+  {
+    path: 'admin',
+    canActivate: [authGuard, () => inject(Perms).can()],
+    resolve: { user: UserResolver },
+    loadChildren: () => import('./admin/admin.routes').then((m) => m.ADMIN_ROUTES),
+  },
+  {
+    path: 'users/:id',
+    children: [{ path: 'edit', loadComponent: () => import('./edit.component') }],
+  },
+];
+export default [{ path: 'x', component: X }] satisfies Route[];
+const ignored = [{ path: 'y' }];
+@NgModule({ imports: [RouterModule.forChild([{ path: 'z', children: ZONE_ROUTES }])] })
+export class ZoneModule {}"#,
+        );
+
+        let summary: Vec<(&str, usize)> = tables
+            .iter()
+            .map(|t| (t.name.as_str(), t.routes.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("routes", 4), ("default", 1), ("ZoneModule", 1)]
+        );
+
+        let routes = &tables[0].routes;
+        assert_eq!(routes[0].component.as_ref().unwrap().name, "HomeComponent");
+        assert_eq!(routes[1].redirect_to.as_deref(), Some("new"));
+
+        let admin = &routes[2];
+        let names =
+            |targets: &[ImportInfo]| targets.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&admin.guards), vec!["authGuard", "inject", "Perms"]);
+        assert_eq!(names(&admin.resolvers), vec!["UserResolver"]);
+        let children = admin.load_children.as_ref().unwrap();
+        assert_eq!(
+            (children.name.as_str(), children.path.as_str()),
+            ("ADMIN_ROUTES", "./admin/admin.routes")
+        );
+
+        let edit = &routes[3].children[0];
+        assert_eq!(edit.path, "edit");
+        assert_eq!(edit.load_component.as_ref().unwrap().name, "default");
+
+        let zone = &tables[2].routes[0];
+        assert_eq!(zone.load_children.as_ref().unwrap().name, "ZONE_ROUTES");
+    }
+}
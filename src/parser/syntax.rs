@@ -0,0 +1,1731 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::lexer::{Token, TokenKind, tokenize};
+
+/// Byte offsets and 1-based line numbers of a syntax node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeclarationKind {
+    Class,
+    Interface,
+    TypeAlias,
+    Enum,
+    Function,
+    Variable,
+    Namespace,
+}
+
+/// A top-level declaration such as a class, function or variable.
+#[derive(Debug, Clone)]
+pub(crate) struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    pub exported: bool,
+    /// Variables initialized with an arrow function or function expression
+    pub function_initializer: bool,
+    /// Decorator names, without arguments or namespace qualifiers
+    pub decorators: Vec<String>,
+    /// From the `export` or declaration keyword to the end of the
+    /// declaration, decorators excluded
+    pub span: Span,
+    /// Token indices of the declaration, decorators included
+    pub tokens: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ImportSpecifier {
+    /// Exported name in the source module, `default` or `*` for namespaces
+    pub imported: String,
+    pub local: String,
+    pub type_only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ImportDecl {
+    pub source: String,
+    pub specifiers: Vec<ImportSpecifier>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExportSpecifier {
+    pub local: String,
+    pub exported: String,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum ExportDecl {
+    /// `export { a, b as c }`, optionally `from 'source'`
+    Named {
+        specifiers: Vec<ExportSpecifier>,
+        source: Option<String>,
+    },
+    /// `export * from 'source'` or `export * as ns from 'source'`
+    All {
+        namespace: Option<String>,
+        source: String,
+    },
+    /// `export default local;`
+    Default { local: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DynamicImportKind {
+    /// `import('./x').then(m => m.Foo)`
+    Lazy,
+    /// `const { Foo } = await import('./x')`
+    Destructured,
+    /// `new Worker(new URL('./x.worker', import.meta.url))`
+    Worker,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DynamicImport {
+    pub source: String,
+    pub names: Vec<String>,
+    pub kind: DynamicImportKind,
+    /// Index of the first token of the import expression
+    pub token: usize,
+}
+
+/// Syntax tree of a TypeScript file, limited to what the analyses need:
+/// top-level declarations with spans, module imports and exports.
+pub(crate) struct SourceFile<'a> {
+    pub tokens: Vec<Token<'a>>,
+    pub declarations: Vec<Declaration>,
+    pub imports: Vec<ImportDecl>,
+    pub exports: Vec<ExportDecl>,
+    pub dynamic_imports: Vec<DynamicImport>,
+    /// Token ranges of imports and export lists, which declare nothing
+    module_statements: Vec<Range<usize>>,
+    /// Tokens that bind or export a name rather than reference it
+    binding_tokens: HashSet<usize>,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn parse(source: &'a str) -> Self {
+        let tokens = tokenize(source);
+        let mut parser = SyntaxParser {
+            tokens: &tokens,
+            pos: 0,
+            declarations: Vec::new(),
+            imports: Vec::new(),
+            exports: Vec::new(),
+            module_statements: Vec::new(),
+            binding_tokens: HashSet::new(),
+        };
+        parser.parse_module();
+
+        let SyntaxParser {
+            declarations,
+            imports,
+            exports,
+            module_statements,
+            binding_tokens,
+            ..
+        } = parser;
+        let dynamic_imports = find_dynamic_imports(&tokens);
+
+        SourceFile {
+            tokens,
+            declarations: merge_overloads(declarations),
+            imports,
+            exports,
+            dynamic_imports,
+            module_statements,
+            binding_tokens,
+        }
+    }
+
+    /// Declarations visible to other modules, with the name they are
+    /// exported under. Covers `export class`, `export { a as b }` lists
+    /// and `export default a`.
+    pub fn exported_declarations(&self) -> Vec<(&str, &Declaration)> {
+        let mut exported: Vec<(&str, &Declaration)> = self
+            .declarations
+            .iter()
+            .filter(|d| d.exported)
+            .map(|d| (d.name.as_str(), d))
+            .collect();
+
+        for export in &self.exports {
+            let locals: Vec<(&str, &str)> = match export {
+                ExportDecl::Named {
+                    specifiers,
+                    source: None,
+                    ..
+                } => specifiers
+                    .iter()
+                    .map(|s| (s.local.as_str(), s.exported.as_str()))
+                    .collect(),
+                ExportDecl::Default { local } => vec![(local.as_str(), local.as_str())],
+                _ => continue,
+            };
+
+            for (local, exported_name) in locals {
+                let Some(decl) = self.declarations.iter().find(|d| d.name == local) else {
+                    continue;
+                };
+                if !exported
+                    .iter()
+                    .any(|(name, d)| *name == exported_name && std::ptr::eq(*d, decl))
+                {
+                    exported.push((exported_name, decl));
+                }
+            }
+        }
+
+        exported.sort_by_key(|(_, d)| d.span.start);
+        exported
+    }
+
+    /// Whether an identifier is referenced anywhere besides its own
+    /// declaration and export statements. Member accesses like `x.name`
+    /// are not references.
+    pub fn is_referenced(&self, name: &str) -> bool {
+        self.tokens.iter().enumerate().any(|(idx, token)| {
+            token.is_ident()
+                && token.text == name
+                && !self.binding_tokens.contains(&idx)
+                && !self.is_member_name(idx)
+        })
+    }
+
+    /// Identifiers referenced inside a declaration, decorators included.
+    /// Its own name and member names are excluded.
+    pub fn references(&self, decl: &Declaration) -> HashSet<&'a str> {
+        decl.tokens
+            .clone()
+            .filter(|idx| {
+                let token = &self.tokens[*idx];
+                token.is_ident()
+                    && token.text != decl.name
+                    && !self.binding_tokens.contains(idx)
+                    && !self.is_member_name(*idx)
+            })
+            .map(|idx| self.tokens[idx].text)
+            .collect()
+    }
+
+    /// Indices of the declarations that the given lines fall into,
+    /// decorators included. Lines are 1-based; an empty range `n..n`
+    /// stands for lines removed right before line `n`. None when the lines
+    /// touch code outside of declarations, imports and export lists, or
+    /// lines were removed between statements.
+    pub fn changed_declarations(&self, lines: &[Range<usize>]) -> Option<HashSet<usize>> {
+        let mut changed = HashSet::new();
+
+        for range in lines {
+            if range.is_empty() {
+                let mut inside = false;
+                for (decl_idx, decl) in self.declarations.iter().enumerate() {
+                    if self.tokens[decl.tokens.start].line < range.start
+                        && range.start <= decl.span.end_line
+                    {
+                        changed.insert(decl_idx);
+                        inside = true;
+                    }
+                }
+                if !inside {
+                    return None;
+                }
+                continue;
+            }
+
+            for (idx, token) in self.tokens.iter().enumerate() {
+                // Template literals may span several lines
+                let last_line = token.line + token.text.matches('\n').count();
+                if token.line >= range.end || last_line < range.start {
+                    continue;
+                }
+
+                let mut inside = false;
+                for (decl_idx, decl) in self.declarations.iter().enumerate() {
+                    if decl.tokens.contains(&idx) {
+                        changed.insert(decl_idx);
+                        inside = true;
+                    }
+                }
+                if !inside && !self.module_statements.iter().any(|s| s.contains(&idx)) {
+                    return None;
+                }
+            }
+        }
+
+        Some(changed)
+    }
+
+    /// String properties of the object literal passed to a decorator of the
+    /// declaration, like `selector` in `@Component({ selector: 'app-x' })`.
+    /// Arrays of strings yield one pair per element; other values are
+    /// skipped.
+    pub fn decorator_properties(
+        &self,
+        decl: &Declaration,
+        decorator: &str,
+    ) -> Vec<(&'a str, &'a str)> {
+        let tokens = &self.tokens[decl.tokens.clone()];
+        let Some(open) = tokens
+            .windows(4)
+            .position(|w| w[0].is("@") && w[1].is(decorator) && w[2].is("(") && w[3].is("{"))
+        else {
+            return Vec::new();
+        };
+
+        let mut properties = Vec::new();
+        let mut depth = 0;
+        let mut idx = open + 3;
+
+        while let Some(token) = tokens.get(idx) {
+            idx += 1;
+            if token.is("{") || token.is("[") || token.is("(") {
+                depth += 1;
+                continue;
+            }
+            if token.is("}") || token.is("]") || token.is(")") {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                continue;
+            }
+
+            let is_key = depth == 1
+                && (token.is_ident() || token.string_value().is_some())
+                && tokens.get(idx).is_some_and(|t| t.is(":"));
+            if !is_key {
+                continue;
+            }
+            let key = token.string_value().unwrap_or(token.text);
+
+            if let Some(value) = tokens.get(idx + 1).and_then(|t| t.literal_value()) {
+                properties.push((key, value));
+                idx += 2;
+            } else if tokens.get(idx + 1).is_some_and(|t| t.is("[")) {
+                let mut end = idx + 2;
+                let mut values = Vec::new();
+                while let Some(element) = tokens.get(end) {
+                    if let Some(value) = element.literal_value() {
+                        values.push((key, value));
+                    } else if !element.is(",") {
+                        break;
+                    }
+                    end += 1;
+                }
+                if tokens.get(end).is_some_and(|t| t.is("]")) {
+                    properties.extend(values);
+                    idx = end + 1;
+                }
+            }
+        }
+
+        properties
+    }
+
+    /// Identifiers a declaration gets through Angular's dependency
+    /// injection: constructor parameter types, `@Inject()` and `inject()`
+    /// tokens, and what `providers`, `viewProviders` and `deps` arrays or
+    /// `provide`, `useClass`, `useExisting` and `useFactory` properties
+    /// name.
+    pub fn injected_names(&self, decl: &Declaration) -> HashSet<&'a str> {
+        let mut names = HashSet::new();
+        let end = decl.tokens.end;
+        let ident_at = |idx: usize| {
+            (idx < end)
+                .then(|| &self.tokens[idx])
+                .filter(|t| t.is_ident())
+                .map(|t| t.text)
+        };
+        let is_key = |idx: usize| idx + 1 < end && self.tokens[idx + 1].is(":");
+
+        let mut idx = decl.tokens.start;
+        while idx < end {
+            let token = &self.tokens[idx];
+            let calls = idx + 1 < end && self.tokens[idx + 1].is("(");
+
+            if (token.is("inject") || token.is("Inject")) && calls && !self.is_member_name(idx) {
+                names.extend(ident_at(idx + 2));
+            } else if token.is("constructor") && calls {
+                let close = self.closing(idx + 1, end);
+                names.extend(self.parameter_types(idx + 2..close));
+            } else if ["providers", "viewProviders", "deps"].contains(&token.text)
+                && is_key(idx)
+                && idx + 2 < end
+                && self.tokens[idx + 2].is("[")
+            {
+                let close = self.closing(idx + 2, end);
+                let mut item = idx + 3;
+                while item < close {
+                    // A `useValue` is a plain value, nothing is injected
+                    if self.tokens[item].is("useValue") && is_key(item) {
+                        item = self.value_end(item + 2, close);
+                        continue;
+                    }
+                    if !is_key(item) && !self.is_member_name(item) {
+                        names.extend(ident_at(item).filter(|name| !VALUE_KEYWORDS.contains(name)));
+                    }
+                    item += 1;
+                }
+                idx = close;
+            } else if ["provide", "useClass", "useExisting", "useFactory"].contains(&token.text)
+                && is_key(idx)
+            {
+                names.extend(ident_at(idx + 2).filter(|name| !VALUE_KEYWORDS.contains(name)));
+            }
+            idx += 1;
+        }
+
+        names
+    }
+
+    /// The first identifier of each parameter's type annotation in a
+    /// parameter list, like `Foo` in `private foo: Foo<Bar>`.
+    fn parameter_types(&self, params: Range<usize>) -> Vec<&'a str> {
+        let mut types = Vec::new();
+        let mut depth = 0;
+
+        for idx in params.clone() {
+            let token = &self.tokens[idx];
+            if token.is("(") || token.is("[") || token.is("{") || token.is("<") {
+                depth += 1;
+            } else if token.is(")") || token.is("]") || token.is("}") || token.is(">") {
+                depth -= 1;
+            } else if depth == 0
+                && token.is(":")
+                && let Some(next) = self
+                    .tokens
+                    .get(idx + 1)
+                    .filter(|_| params.contains(&(idx + 1)))
+                && next.is_ident()
+            {
+                types.push(next.text);
+            }
+        }
+
+        types
+    }
+
+    /// Index of the `,` or closing bracket ending the expression that
+    /// starts at `start`, or `end`.
+    fn value_end(&self, start: usize, end: usize) -> usize {
+        let mut depth = 0;
+        for idx in start..end {
+            let token = &self.tokens[idx];
+            if token.is("(") || token.is("[") || token.is("{") {
+                depth += 1;
+            } else if token.is(")") || token.is("]") || token.is("}") {
+                if depth == 0 {
+                    return idx;
+                }
+                depth -= 1;
+            } else if token.is(",") && depth == 0 {
+                return idx;
+            }
+        }
+        end
+    }
+
+    /// Index of the bracket closing the one at `open`, or `end`.
+    pub(super) fn closing(&self, open: usize, end: usize) -> usize {
+        let mut depth = 0;
+        for idx in open..end {
+            let token = &self.tokens[idx];
+            if token.is("(") || token.is("[") || token.is("{") {
+                depth += 1;
+            } else if token.is(")") || token.is("]") || token.is("}") {
+                depth -= 1;
+                if depth == 0 {
+                    return idx;
+                }
+            }
+        }
+        end
+    }
+
+    /// The declaration whose tokens contain the given token index.
+    pub fn declaration_at(&self, token: usize) -> Option<&Declaration> {
+        self.declarations
+            .iter()
+            .find(|decl| decl.tokens.contains(&token))
+    }
+
+    pub(super) fn is_member_name(&self, idx: usize) -> bool {
+        idx > 0 && (self.tokens[idx - 1].is(".") || self.tokens[idx - 1].is("?."))
+    }
+}
+
+/// Function overloads are separate declarations with the same name;
+/// merge them into one spanning all signatures and the implementation.
+fn merge_overloads(declarations: Vec<Declaration>) -> Vec<Declaration> {
+    let mut merged: Vec<Declaration> = Vec::with_capacity(declarations.len());
+
+    for decl in declarations {
+        if let Some(prev) = merged.last_mut()
+            && prev.kind == DeclarationKind::Function
+            && decl.kind == DeclarationKind::Function
+            && prev.name == decl.name
+        {
+            prev.span.end = decl.span.end;
+            prev.span.end_line = decl.span.end_line;
+            prev.tokens.end = decl.tokens.end;
+            prev.exported |= decl.exported;
+            continue;
+        }
+        merged.push(decl);
+    }
+
+    merged
+}
+
+/// Identifier keywords that can not start an expression statement on a new
+/// line; used to detect the end of a statement without a semicolon.
+const CONTINUATION_KEYWORDS: &[&str] = &[
+    "as",
+    "satisfies",
+    "instanceof",
+    "in",
+    "of",
+    "extends",
+    "implements",
+    "keyof",
+];
+
+/// Identifier keywords that are values or operators, not names of
+/// injected classes or tokens.
+const VALUE_KEYWORDS: &[&str] = &[
+    "new",
+    "true",
+    "false",
+    "null",
+    "undefined",
+    "this",
+    "function",
+    "return",
+    "typeof",
+];
+
+struct SyntaxParser<'t, 'a> {
+    tokens: &'t [Token<'a>],
+    pos: usize,
+    declarations: Vec<Declaration>,
+    imports: Vec<ImportDecl>,
+    exports: Vec<ExportDecl>,
+    module_statements: Vec<Range<usize>>,
+    binding_tokens: HashSet<usize>,
+}
+
+impl<'t, 'a> SyntaxParser<'t, 'a> {
+    fn peek(&self, offset: usize) -> Option<&'t Token<'a>> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn at(&self, offset: usize, text: &str) -> bool {
+        self.peek(offset).is_some_and(|t| t.is(text))
+    }
+
+    fn span(&self, tokens: Range<usize>) -> Span {
+        let first = &self.tokens[tokens.start];
+        let last = &self.tokens[tokens.end.max(tokens.start + 1) - 1];
+        Span {
+            start: first.start,
+            end: last.end,
+            start_line: first.line,
+            end_line: last.line,
+        }
+    }
+
+    fn parse_module(&mut self) {
+        while self.pos < self.tokens.len() {
+            let start = self.pos;
+            let decorators = self.parse_decorators();
+
+            if self.pos >= self.tokens.len() {
+                break;
+            }
+
+            if self.at(0, "import") && !self.at(1, "(") && !self.at(1, ".") {
+                self.parse_import();
+                self.module_statements.push(start..self.pos);
+            } else if self.at(0, "export") {
+                let exports = self.exports.len();
+                self.parse_export(start, decorators);
+                if self.exports.len() > exports {
+                    self.module_statements.push(start..self.pos);
+                }
+            } else if !self.parse_declaration(start, self.pos, decorators, false) {
+                self.skip_statement();
+            }
+
+            // Always make progress, even on unexpected input
+            if self.pos == start {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn parse_decorators(&mut self) -> Vec<String> {
+        let mut decorators = Vec::new();
+
+        while self.at(0, "@") && self.peek(1).is_some_and(|t| t.is_ident()) {
+            self.pos += 1;
+            let mut name = self.tokens[self.pos].text;
+            self.pos += 1;
+
+            while self.at(0, ".") && self.peek(1).is_some_and(|t| t.is_ident()) {
+                name = self.tokens[self.pos + 1].text;
+                self.pos += 2;
+            }
+
+            if self.at(0, "(") {
+                self.skip_balanced();
+            }
+
+            decorators.push(name.to_string());
+        }
+
+        decorators
+    }
+
+    fn parse_import(&mut self) {
+        self.pos += 1;
+
+        let mut type_only = false;
+        if self.at(0, "type") && !self.at(1, "from") && !self.at(1, ",") && !self.at(1, "=") {
+            type_only = true;
+            self.pos += 1;
+        }
+
+        let mut specifiers = Vec::new();
+
+        // Side-effect import: import './polyfills'
+        if let Some(source) = self.peek(0).and_then(|t| t.string_value()) {
+            self.pos += 1;
+            self.consume_semicolon();
+            self.imports.push(ImportDecl {
+                source: source.to_string(),
+                specifiers,
+            });
+            return;
+        }
+
+        if self.peek(0).is_some_and(|t| t.is_ident()) && !self.at(0, "from") || self.at(1, "from") {
+            // import x = require('...') is not an ES import
+            if self.at(1, "=") {
+                self.skip_statement();
+                return;
+            }
+            let local = self.tokens[self.pos].text;
+            specifiers.push(ImportSpecifier {
+                imported: "default".to_string(),
+                local: local.to_string(),
+                type_only,
+            });
+            self.pos += 1;
+            if self.at(0, ",") {
+                self.pos += 1;
+            }
+        }
+
+        if self.at(0, "*") && self.at(1, "as") {
+            if let Some(local) = self.peek(2).filter(|t| t.is_ident()) {
+                specifiers.push(ImportSpecifier {
+                    imported: "*".to_string(),
+                    local: local.text.to_string(),
+                    type_only,
+                });
+            }
+            self.pos += 3;
+        } else if self.at(0, "{") {
+            for (name, alias, inline_type) in self.parse_specifier_list() {
+                specifiers.push(ImportSpecifier {
+                    local: alias.unwrap_or(name).to_string(),
+                    imported: name.to_string(),
+                    type_only: type_only || inline_type,
+                });
+            }
+        }
+
+        if !self.at(0, "from") {
+            self.skip_statement();
+            return;
+        }
+        self.pos += 1;
+
+        let Some(source) = self.peek(0).and_then(|t| t.string_value()) else {
+            self.skip_statement();
+            return;
+        };
+        self.pos += 1;
+        self.skip_import_attributes();
+        self.consume_semicolon();
+
+        self.imports.push(ImportDecl {
+            source: source.to_string(),
+            specifiers,
+        });
+    }
+
+    /// Parses `{ a, type b, c as d }` and returns (name, alias, type-only)
+    /// triples. Leaves the position after the closing brace.
+    fn parse_specifier_list(&mut self) -> Vec<(&'a str, Option<&'a str>, bool)> {
+        let mut specifiers = Vec::new();
+        self.pos += 1;
+
+        while let Some(token) = self.peek(0) {
+            if token.is("}") {
+                self.pos += 1;
+                break;
+            }
+            if token.is(",") {
+                self.pos += 1;
+                continue;
+            }
+
+            let mut inline_type = false;
+            if token.is("type")
+                && self
+                    .peek(1)
+                    .is_some_and(|t| t.is_ident() || t.kind == TokenKind::String)
+                && !self.at(1, "as")
+            {
+                inline_type = true;
+                self.pos += 1;
+            } else if token.is("type") && self.at(1, "as") && self.at(2, "as") {
+                // `type as as x`: a type-only import of a binding named `as`
+                inline_type = true;
+                self.pos += 1;
+            }
+
+            let Some(name_token) = self.peek(0) else {
+                break;
+            };
+            let name = match name_token.kind {
+                TokenKind::Ident => name_token.text,
+                TokenKind::String => name_token.string_value().unwrap_or_default(),
+                _ => {
+                    self.pos += 1;
+                    continue;
+                }
+            };
+            self.binding_tokens.insert(self.pos);
+            self.pos += 1;
+
+            let mut alias = None;
+            if self.at(0, "as") {
+                if let Some(alias_token) = self.peek(1) {
+                    alias = Some(match alias_token.kind {
+                        TokenKind::String => alias_token.string_value().unwrap_or_default(),
+                        _ => alias_token.text,
+                    });
+                    self.binding_tokens.insert(self.pos + 1);
+                }
+                self.pos += 2;
+            }
+
+            specifiers.push((name, alias, inline_type));
+        }
+
+        specifiers
+    }
+
+    fn parse_export(&mut self, start: usize, mut decorators: Vec<String>) {
+        let export_pos = self.pos;
+        self.pos += 1;
+
+        // export * from './x' / export * as ns from './x'
+        if self.at(0, "type") && (self.at(1, "{") || self.at(1, "*")) {
+            self.pos += 1;
+        }
+
+        if self.at(0, "*") {
+            self.pos += 1;
+            let mut namespace = None;
+            if self.at(0, "as") {
+                namespace = self.peek(1).map(|t| t.text.to_string());
+                self.pos += 2;
+            }
+            if self.at(0, "from") {
+                if let Some(source) = self.peek(1).and_then(|t| t.string_value()) {
+                    self.exports.push(ExportDecl::All {
+                        namespace,
+                        source: source.to_string(),
+                    });
+                }
+                self.pos += 2;
+            }
+            self.skip_import_attributes();
+            self.consume_semicolon();
+            return;
+        }
+
+        if self.at(0, "{") {
+            let specifiers: Vec<ExportSpecifier> = self
+                .parse_specifier_list()
+                .into_iter()
+                .map(|(local, alias, _)| ExportSpecifier {
+                    local: local.to_string(),
+                    exported: alias.unwrap_or(local).to_string(),
+                })
+                .collect();
+
+            let mut source = None;
+            if self.at(0, "from") {
+                source = self
+                    .peek(1)
+                    .and_then(|t| t.string_value())
+                    .map(str::to_string);
+                self.pos += 2;
+                self.skip_import_attributes();
+            }
+            self.consume_semicolon();
+
+            self.exports.push(ExportDecl::Named { specifiers, source });
+            return;
+        }
+
+        // Decorators may also follow the export keyword
+        decorators.extend(self.parse_decorators());
+
+        if self.at(0, "default") {
+            self.pos += 1;
+            decorators.extend(self.parse_decorators());
+
+            if self.parse_declaration(start, export_pos, decorators, true) {
+                return;
+            }
+
+            if let Some(token) = self.peek(0).filter(|t| t.is_ident()) {
+                let ends_here = self
+                    .peek(1)
+                    .is_none_or(|next| next.is(";") || next.newline_before);
+                if ends_here {
+                    self.binding_tokens.insert(self.pos);
+                    self.exports.push(ExportDecl::Default {
+                        local: token.text.to_string(),
+                    });
+                    self.pos += 1;
+                    self.consume_semicolon();
+                    return;
+                }
+            }
+
+            self.skip_statement();
+            return;
+        }
+
+        if !self.parse_declaration(start, export_pos, decorators, true) {
+            // export = x, export import a = b, export as namespace x
+            self.skip_statement();
+        }
+    }
+
+    /// Parses a declaration at the current position. `start` is the first
+    /// token including decorators, `keyword_start` the `export` or first
+    /// modifier token. Returns false, leaving the position unchanged, when
+    /// no declaration starts here.
+    fn parse_declaration(
+        &mut self,
+        start: usize,
+        keyword_start: usize,
+        decorators: Vec<String>,
+        exported: bool,
+    ) -> bool {
+        let saved = self.pos;
+        let mut ambient = false;
+
+        loop {
+            if self.at(0, "declare") && self.peek(1).is_some_and(|t| t.is_ident()) {
+                ambient = true;
+                self.pos += 1;
+            } else if (self.at(0, "abstract") && self.at(1, "class"))
+                || (self.at(0, "async") && self.at(1, "function"))
+                || (self.at(0, "const") && self.at(1, "enum"))
+            {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        let Some(keyword) = self.peek(0) else {
+            self.pos = saved;
+            return false;
+        };
+
+        let mut declared: Vec<(usize, DeclarationKind, bool)> = Vec::new();
+
+        match keyword.text {
+            "class" if keyword.is_ident() => {
+                self.pos += 1;
+                let name = self.declaration_name();
+                self.skip_to_body(true);
+                if let Some(name_idx) = name {
+                    declared.push((name_idx, DeclarationKind::Class, false));
+                }
+            }
+            "interface" | "enum" if self.peek(1).is_some_and(|t| t.is_ident()) => {
+                let kind = if keyword.text == "enum" {
+                    DeclarationKind::Enum
+                } else {
+                    DeclarationKind::Interface
+                };
+                self.pos += 1;
+                let name = self.declaration_name();
+                self.skip_to_body(true);
+                if let Some(name_idx) = name {
+                    declared.push((name_idx, kind, false));
+                }
+            }
+            "namespace" | "module" if self.peek(1).is_some_and(|t| t.is_ident()) => {
+                self.pos += 1;
+                let name = self.declaration_name();
+                while self.at(0, ".") {
+                    self.pos += 2;
+                }
+                self.skip_to_body(true);
+                if let Some(name_idx) = name {
+                    declared.push((name_idx, DeclarationKind::Namespace, false));
+                }
+            }
+            "module" | "global" if ambient => {
+                // declare module 'x' { } / declare global { }
+                self.pos += 1;
+                if self.peek(0).is_some_and(|t| t.kind == TokenKind::String) {
+                    self.pos += 1;
+                }
+                self.skip_to_body(true);
+            }
+            "function" => {
+                self.pos += 1;
+                if self.at(0, "*") {
+                    self.pos += 1;
+                }
+                let name = self.declaration_name();
+                self.skip_function_rest();
+                if let Some(name_idx) = name {
+                    declared.push((name_idx, DeclarationKind::Function, false));
+                }
+            }
+            "type"
+                if self.peek(1).is_some_and(|t| t.is_ident())
+                    && (self.at(2, "=") || self.at(2, "<")) =>
+            {
+                self.pos += 1;
+                let name = self.declaration_name();
+                self.skip_statement();
+                if let Some(name_idx) = name {
+                    declared.push((name_idx, DeclarationKind::TypeAlias, false));
+                }
+            }
+            "const" | "let" | "var"
+                if self
+                    .peek(1)
+                    .is_some_and(|t| t.is_ident() || t.is("{") || t.is("[")) =>
+            {
+                self.pos += 1;
+                declared = self.parse_variable_declarators();
+            }
+            _ => {
+                self.pos = saved;
+                return false;
+            }
+        }
+
+        let end = self.pos.max(keyword_start + 1);
+        let span = self.span(keyword_start..end);
+
+        for (name_idx, kind, function_initializer) in declared {
+            self.binding_tokens.insert(name_idx);
+            self.declarations.push(Declaration {
+                name: self.tokens[name_idx].text.to_string(),
+                kind,
+                exported,
+                function_initializer,
+                decorators: decorators.clone(),
+                span,
+                tokens: start..end,
+            });
+        }
+
+        true
+    }
+
+    /// Consumes the identifier naming a declaration, if present.
+    fn declaration_name(&mut self) -> Option<usize> {
+        let token = self.peek(0)?;
+        if token.is_ident() && !matches!(token.text, "extends" | "implements") {
+            self.pos += 1;
+            Some(self.pos - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Skips a declaration header (generics, heritage clauses) and its
+    /// `{ ... }` body. Angle brackets are only balanced in type positions.
+    fn skip_to_body(&mut self, track_angles: bool) {
+        let mut angle_depth = 0usize;
+
+        while let Some(token) = self.peek(0) {
+            if token.is("<") && track_angles {
+                angle_depth += 1;
+            } else if token.is(">") && angle_depth > 0 {
+                angle_depth -= 1;
+            } else if token.is("(") || token.is("[") {
+                self.skip_balanced();
+                continue;
+            } else if token.is("{") {
+                if angle_depth == 0 {
+                    self.skip_balanced();
+                    return;
+                }
+                self.skip_balanced();
+                continue;
+            } else if token.is(";") || token.is("}") {
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skips generics, parameters, return type and the body (or the `;` of
+    /// an overload signature) of a function declaration.
+    fn skip_function_rest(&mut self) {
+        let mut angle_depth = 0usize;
+        let mut seen_params = false;
+
+        while let Some(token) = self.peek(0) {
+            if token.is("<") {
+                angle_depth += 1;
+            } else if token.is(">") && angle_depth > 0 {
+                angle_depth -= 1;
+            } else if token.is("(") || token.is("[") {
+                if angle_depth == 0 && token.is("(") {
+                    seen_params = true;
+                }
+                self.skip_balanced();
+                continue;
+            } else if token.is("{") {
+                let prev = &self.tokens[self.pos - 1];
+                let is_type_literal = angle_depth > 0
+                    || !seen_params
+                    || [":", "|", "&", ",", "=>", "?"].iter().any(|p| prev.is(p));
+                self.skip_balanced();
+                if !is_type_literal {
+                    return;
+                }
+                continue;
+            } else if token.is(";") {
+                self.pos += 1;
+                return;
+            } else if token.is("}")
+                || (seen_params && angle_depth == 0 && self.statement_ends_before(self.pos))
+            {
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Parses `a = 1, { b, c: d } = obj` after `const`/`let`/`var` and
+    /// returns the bound names with whether each is a function expression.
+    fn parse_variable_declarators(&mut self) -> Vec<(usize, DeclarationKind, bool)> {
+        let mut declared = Vec::new();
+
+        loop {
+            let mut names = Vec::new();
+
+            match self.peek(0) {
+                Some(token) if token.is_ident() => {
+                    names.push(self.pos);
+                    self.pos += 1;
+                }
+                Some(token) if token.is("{") || token.is("[") => {
+                    let pattern_start = self.pos;
+                    self.skip_balanced();
+                    names = self.pattern_bindings(pattern_start + 1..self.pos - 1);
+                }
+                _ => {
+                    self.skip_statement();
+                    return declared;
+                }
+            }
+
+            // Definite assignment `!` and the type annotation
+            if self.at(0, "!") {
+                self.pos += 1;
+            }
+            if self.at(0, ":") {
+                self.pos += 1;
+                let mut angle_depth = 0usize;
+                while let Some(token) = self.peek(0) {
+                    if token.is("<") {
+                        angle_depth += 1;
+                    } else if token.is(">") && angle_depth > 0 {
+                        angle_depth -= 1;
+                    } else if token.is("(") || token.is("[") || token.is("{") {
+                        self.skip_balanced();
+                        continue;
+                    } else if angle_depth == 0
+                        && (token.is("=")
+                            || token.is(",")
+                            || token.is(";")
+                            || token.is("}")
+                            || self.statement_ends_before(self.pos))
+                    {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            }
+
+            let mut function_initializer = false;
+            if self.at(0, "=") {
+                self.pos += 1;
+                function_initializer = self.is_function_expression();
+                self.skip_expression();
+            }
+
+            for name_idx in names {
+                declared.push((name_idx, DeclarationKind::Variable, function_initializer));
+            }
+
+            if self.at(0, ",") {
+                self.pos += 1;
+                continue;
+            }
+            self.consume_semicolon();
+            return declared;
+        }
+    }
+
+    /// Binding identifiers of a destructuring pattern. Property keys and
+    /// default values are skipped.
+    fn pattern_bindings(&self, range: Range<usize>) -> Vec<usize> {
+        let mut names = Vec::new();
+        let mut depth = 0usize;
+        let mut in_default = false;
+
+        for idx in range {
+            let token = &self.tokens[idx];
+            if token.is("{") || token.is("[") || token.is("(") {
+                depth += 1;
+                continue;
+            }
+            if token.is("}") || token.is("]") || token.is(")") {
+                depth = depth.saturating_sub(1);
+                continue;
+            }
+            if in_default {
+                if token.is(",") && depth == 0 {
+                    in_default = false;
+                }
+                continue;
+            }
+            if token.is("=") {
+                in_default = true;
+                continue;
+            }
+            let next_is_key = self.tokens.get(idx + 1).is_some_and(|t| t.is(":"));
+            if token.is_ident() && !next_is_key {
+                names.push(idx);
+            }
+        }
+
+        names
+    }
+
+    /// Whether the expression at the current position is an arrow function
+    /// or a function expression.
+    fn is_function_expression(&self) -> bool {
+        let mut idx = self.pos;
+        if self.tokens.get(idx).is_some_and(|t| t.is("async")) {
+            idx += 1;
+        }
+
+        let Some(token) = self.tokens.get(idx) else {
+            return false;
+        };
+
+        if token.is("function") {
+            return true;
+        }
+        if token.is_ident() {
+            return self.tokens.get(idx + 1).is_some_and(|t| t.is("=>"));
+        }
+        if token.is("<") || token.is("(") {
+            // Find the `=>` after the parameter list and optional return type
+            let mut depth = 0i32;
+            for t in &self.tokens[idx..] {
+                if t.is("(") || t.is("[") || t.is("{") || t.is("<") {
+                    depth += 1;
+                } else if t.is(")") || t.is("]") || t.is("}") || t.is(">") {
+                    depth -= 1;
+                } else if depth == 0 && t.is("=>") {
+                    return true;
+                } else if depth == 0 && (t.is(";") || t.is(",") || t.is("=")) {
+                    return false;
+                }
+                if depth < 0 {
+                    return false;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Skips an initializer expression up to a top-level `,`, `;` or the
+    /// end of the statement.
+    fn skip_expression(&mut self) {
+        while let Some(token) = self.peek(0) {
+            if token.is("(") || token.is("[") || token.is("{") {
+                self.skip_balanced();
+                continue;
+            }
+            if token.is(",") || token.is(";") || token.is(")") || token.is("]") || token.is("}") {
+                return;
+            }
+            if self.statement_ends_before(self.pos) {
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skips to the end of the current statement, including its `;`.
+    fn skip_statement(&mut self) {
+        let start = self.pos;
+        while let Some(token) = self.peek(0) {
+            if token.is("(") || token.is("[") || token.is("{") {
+                self.skip_balanced();
+                continue;
+            }
+            if token.is(";") {
+                self.pos += 1;
+                return;
+            }
+            if token.is(")") || token.is("]") || token.is("}") {
+                // Stray closing bracket: consume it to keep making progress
+                self.pos += 1;
+                return;
+            }
+            if self.pos > start && self.statement_ends_before(self.pos) {
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Automatic semicolon insertion heuristic: a new line ends the
+    /// statement when the previous token can end an expression and the
+    /// next one can not continue it.
+    fn statement_ends_before(&self, idx: usize) -> bool {
+        let Some(token) = self.tokens.get(idx) else {
+            return true;
+        };
+        if idx == 0 || !token.newline_before {
+            return false;
+        }
+
+        let prev = &self.tokens[idx - 1];
+        let prev_ends_expression = match prev.kind {
+            TokenKind::Punct => matches!(prev.text, ")" | "]" | "}"),
+            TokenKind::Template => prev.text.ends_with('`'),
+            TokenKind::Ident => !CONTINUATION_KEYWORDS.contains(&prev.text),
+            _ => true,
+        };
+        let starts_statement = match token.kind {
+            TokenKind::Ident => !CONTINUATION_KEYWORDS.contains(&token.text),
+            TokenKind::String | TokenKind::Number => true,
+            TokenKind::Punct => token.is("@"),
+            _ => false,
+        };
+
+        prev_ends_expression && starts_statement
+    }
+
+    /// Skips a bracketed group starting at the current `(`, `[` or `{`.
+    fn skip_balanced(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek(0) {
+            self.pos += 1;
+            if token.is("(") || token.is("[") || token.is("{") {
+                depth += 1;
+            } else if token.is(")") || token.is("]") || token.is("}") {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Skips `with { type: 'json' }` / `assert { ... }` import attributes.
+    fn skip_import_attributes(&mut self) {
+        if (self.at(0, "with") || self.at(0, "assert"))
+            && self.at(1, "{")
+            && !self.peek(0).is_some_and(|t| t.newline_before)
+        {
+            self.pos += 1;
+            self.skip_balanced();
+        }
+    }
+
+    fn consume_semicolon(&mut self) {
+        if self.at(0, ";") {
+            self.pos += 1;
+        }
+    }
+}
+
+/// Finds lazy imports, destructured dynamic imports and worker URLs
+/// anywhere in the file.
+fn find_dynamic_imports(tokens: &[Token]) -> Vec<DynamicImport> {
+    let mut found = Vec::new();
+    let is = |idx: usize, text: &str| tokens.get(idx).is_some_and(|t| t.is(text));
+    let string_at = |idx: usize| tokens.get(idx).and_then(|t| t.string_value());
+
+    for idx in 0..tokens.len() {
+        // import('./x').then(m => m.Foo) / .then((m) => m.Foo)
+        if is(idx, "import") && is(idx + 1, "(") && is(idx + 3, ")") {
+            let Some(source) = string_at(idx + 2) else {
+                continue;
+            };
+            if !(is(idx + 4, ".") && is(idx + 5, "then") && is(idx + 6, "(")) {
+                continue;
+            }
+
+            let mut param_idx = idx + 7;
+            let parenthesized = is(param_idx, "(");
+            if parenthesized {
+                param_idx += 1;
+            }
+            let Some(param) = tokens.get(param_idx).filter(|t| t.is_ident()) else {
+                continue;
+            };
+            let mut arrow_idx = param_idx + 1;
+            if parenthesized {
+                if !is(arrow_idx, ")") {
+                    continue;
+                }
+                arrow_idx += 1;
+            }
+
+            if is(arrow_idx, "=>")
+                && tokens.get(arrow_idx + 1).is_some_and(|t| t.is(param.text))
+                && is(arrow_idx + 2, ".")
+                && let Some(member) = tokens.get(arrow_idx + 3).filter(|t| t.is_ident())
+            {
+                found.push(DynamicImport {
+                    source: source.to_string(),
+                    names: vec![member.text.to_string()],
+                    kind: DynamicImportKind::Lazy,
+                    token: idx,
+                });
+            }
+            continue;
+        }
+
+        // const { Foo, Bar: Local } = await import('./x')
+        if (is(idx, "const") || is(idx, "let") || is(idx, "var")) && is(idx + 1, "{") {
+            let mut depth = 0usize;
+            let mut close = None;
+            for (offset, token) in tokens[idx + 1..].iter().enumerate() {
+                if token.is("{") || token.is("[") || token.is("(") {
+                    depth += 1;
+                } else if token.is("}") || token.is("]") || token.is(")") {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(idx + 1 + offset);
+                        break;
+                    }
+                }
+            }
+            let Some(close) = close else {
+                continue;
+            };
+
+            let mut import_idx = close + 1;
+            if !is(import_idx, "=") {
+                continue;
+            }
+            import_idx += 1;
+            if is(import_idx, "await") {
+                import_idx += 1;
+            }
+            if !(is(import_idx, "import") && is(import_idx + 1, "(")) {
+                continue;
+            }
+            let Some(source) = string_at(import_idx + 2) else {
+                continue;
+            };
+
+            found.push(DynamicImport {
+                source: source.to_string(),
+                names: destructured_keys(&tokens[idx + 2..close]),
+                kind: DynamicImportKind::Destructured,
+                token: idx,
+            });
+            continue;
+        }
+
+        // new Worker(new URL('./x.worker', import.meta.url))
+        if is(idx, "new")
+            && is(idx + 1, "Worker")
+            && is(idx + 2, "(")
+            && is(idx + 3, "new")
+            && is(idx + 4, "URL")
+            && is(idx + 5, "(")
+            && let Some(source) = string_at(idx + 6)
+        {
+            found.push(DynamicImport {
+                source: source.to_string(),
+                names: Vec::new(),
+                kind: DynamicImportKind::Worker,
+                token: idx,
+            });
+        }
+    }
+
+    found
+}
+
+/// Property keys of an object destructuring pattern (without braces).
+fn destructured_keys(tokens: &[Token]) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut depth = 0usize;
+    let mut at_property_start = true;
+
+    for token in tokens {
+        if token.is("{") || token.is("[") || token.is("(") {
+            depth += 1;
+        } else if token.is("}") || token.is("]") || token.is(")") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && token.is(",") {
+            at_property_start = true;
+            continue;
+        } else if depth == 0 && at_property_start && token.is_ident() {
+            keys.push(token.text.to_string());
+        }
+        // `...rest` is not a key of the imported module
+        at_property_start = false;
+    }
+
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported_names(source: &str) -> Vec<String> {
+        let file = SourceFile::parse(source);
+        file.exported_declarations()
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_exported_declarations_of_each_kind() {
+        let source = r#"
+export class A {}
+export abstract class B {}
+export interface C { a: string }
+export type D = { a: string };
+export enum E { X }
+export const enum F { Y }
+export function g() {}
+export async function h() {}
+export const i = 1;
+export let j = () => 1;
+export declare const k: number;
+"#;
+        assert_eq!(
+            exported_names(source),
+            vec!["A", "B", "C", "D", "E", "F", "g", "h", "i", "j", "k"]
+        );
+    }
+
+    #[test]
+    fn test_export_default_class_and_function() {
+        let file = SourceFile::parse(
+            "export default class Foo {}\nexport default function bar() {}\nexport default class {}",
+        );
+        let decls = file.exported_declarations();
+
+        assert_eq!(decls.len(), 2);
+        assert_eq!(decls[0].0, "Foo");
+        assert_eq!(decls[1].0, "bar");
+    }
+
+    #[test]
+    fn test_declaration_split_across_lines() {
+        let source = "export\n  abstract\n  class\n  Base<T extends { id: string }>\n  extends Other<T> {\n  run() {}\n}\n";
+        let file = SourceFile::parse(source);
+        let decls = file.exported_declarations();
+
+        assert_eq!(decls.len(), 1);
+        assert_eq!(decls[0].0, "Base");
+        assert_eq!(decls[0].1.kind, DeclarationKind::Class);
+        assert_eq!(decls[0].1.span.start_line, 1);
+        assert_eq!(decls[0].1.span.end_line, 7);
+    }
+
+    #[test]
+    fn test_local_export_list_exports_declarations() {
+        let source = "class A {}\nfunction b() {}\nconst c = 1;\nexport { A, b as renamed };\nexport default c;";
+        assert_eq!(exported_names(source), vec!["A", "renamed", "c"]);
+    }
+
+    #[test]
+    fn test_export_inside_template_string_is_ignored() {
+        let source = "const tpl = `\nexport class Fake {}\n${value}\nexport const alsoFake = 1;\n`;\nexport class Real {}";
+        assert_eq!(exported_names(source), vec!["Real"]);
+    }
+
+    #[test]
+    fn test_function_overloads_are_merged() {
+        let source = "export function f(a: string): string;\nexport function f(a: number): number;\nexport function f(a: any): any {\n  return a;\n}\n";
+        let file = SourceFile::parse(source);
+        let decls = file.exported_declarations();
+
+        assert_eq!(decls.len(), 1);
+        assert_eq!(decls[0].1.span.start_line, 1);
+        assert_eq!(decls[0].1.span.end_line, 5);
+    }
+
+    #[test]
+    fn test_function_with_object_return_type() {
+        let source =
+            "export function f(): { a: string } {\n  return { a: '' };\n}\nexport const after = 1;";
+        assert_eq!(exported_names(source), vec!["f", "after"]);
+    }
+
+    #[test]
+    fn test_statements_without_semicolons() {
+        let source = "export const a = 1\nexport type B = {\n  x: string\n}\nexport const c = foo(\n  1,\n  2\n)\nexport const d = 'd'";
+        let file = SourceFile::parse(source);
+        let decls = file.exported_declarations();
+        let names: Vec<&str> = decls.iter().map(|(n, _)| *n).collect();
+
+        assert_eq!(names, vec!["a", "B", "c", "d"]);
+        assert_eq!(decls[1].1.span.end_line, 4);
+        assert_eq!(decls[2].1.span.end_line, 8);
+    }
+
+    #[test]
+    fn test_variable_function_initializers() {
+        let source = "export const a = () => 1;\nexport const b = async (x: number): Promise<void> => {};\nexport const c = function () {};\nexport const d = x => x;\nexport const e = foo(() => 1);\nexport const f: Fn = <T>(x: T) => x;";
+        let file = SourceFile::parse(source);
+        let flags: Vec<(String, bool)> = file
+            .exported_declarations()
+            .into_iter()
+            .map(|(n, d)| (n.to_string(), d.function_initializer))
+            .collect();
+
+        assert_eq!(
+            flags,
+            vec![
+                ("a".to_string(), true),
+                ("b".to_string(), true),
+                ("c".to_string(), true),
+                ("d".to_string(), true),
+                ("e".to_string(), false),
+                ("f".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_and_destructured_variables() {
+        let source = "export const a = 1, b = 2;\nexport const { c, d: e, f = 3 } = obj;";
+        assert_eq!(exported_names(source), vec!["a", "b", "c", "e", "f"]);
+    }
+
+    #[test]
+    fn test_decorators_are_attached() {
+        let source = "@AutoUnsubscribe()\n@Component({\n  selector: 'app-x',\n})\nexport class XComponent {}";
+        let file = SourceFile::parse(source);
+        let decls = file.exported_declarations();
+
+        assert_eq!(decls[0].1.decorators, vec!["AutoUnsubscribe", "Component"]);
+        assert_eq!(decls[0].1.span.start_line, 5);
+        assert_eq!(decls[0].1.tokens.start, 0);
+    }
+
+    #[test]
+    fn test_parse_imports() {
+        let source = r#"import Default, { A, type B, C as D } from './a';
+import * as ns from "./b";
+import type { E } from './e';
+import './side-effect';
+import type F from './f';"#;
+        let file = SourceFile::parse(source);
+
+        assert_eq!(file.imports.len(), 5);
+        let specs = &file.imports[0].specifiers;
+        assert_eq!(specs.len(), 4);
+        assert_eq!(specs[0].imported, "default");
+        assert_eq!(specs[0].local, "Default");
+        assert!(specs[2].type_only);
+        assert_eq!(specs[3].imported, "C");
+        assert_eq!(specs[3].local, "D");
+        assert_eq!(file.imports[1].specifiers[0].imported, "*");
+        assert!(file.imports[2].specifiers[0].type_only);
+        assert!(file.imports[3].specifiers.is_empty());
+        assert_eq!(file.imports[4].specifiers[0].local, "F");
+        assert!(file.imports[4].specifiers[0].type_only);
+    }
+
+    #[test]
+    fn test_parse_reexports() {
+        let source = "export * from './a';\nexport * as ns from './b';\nexport { x, y as z } from './c';\nexport type { T } from './t';";
+        let file = SourceFile::parse(source);
+
+        assert_eq!(file.exports.len(), 4);
+        assert!(
+            matches!(&file.exports[0], ExportDecl::All { namespace: None, source } if source == "./a")
+        );
+        assert!(
+            matches!(&file.exports[1], ExportDecl::All { namespace: Some(ns), .. } if ns == "ns")
+        );
+        assert!(matches!(
+            &file.exports[2],
+            ExportDecl::Named { specifiers, source: Some(_), .. }
+                if specifiers[1] == ExportSpecifier { local: "y".to_string(), exported: "z".to_string() }
+        ));
+        assert!(
+            matches!(&file.exports[3], ExportDecl::Named { source: Some(s), .. } if s == "./t")
+        );
+        assert!(file.exported_declarations().is_empty());
+    }
+
+    #[test]
+    fn test_dynamic_imports() {
+        let source = r#"
+const routes = [{ loadChildren: () => import('./a.module').then((m) => m.AModule) }];
+const { Foo, Bar: Local, ...rest } = await import('./lazy');
+const worker = new Worker(new URL('./x.worker', import.meta.url));
+"#;
+        let file = SourceFile::parse(source);
+
+        assert_eq!(file.dynamic_imports.len(), 3);
+        assert_eq!(file.dynamic_imports[0].names, vec!["AModule"]);
+        assert_eq!(file.dynamic_imports[0].kind, DynamicImportKind::Lazy);
+        assert_eq!(file.dynamic_imports[1].names, vec!["Foo", "Bar"]);
+        assert_eq!(file.dynamic_imports[2].kind, DynamicImportKind::Worker);
+        assert_eq!(file.dynamic_imports[2].source, "./x.worker");
+        assert_eq!(file.tokens[file.dynamic_imports[2].token].text, "new");
+    }
+
+    #[test]
+    fn test_references_of_declaration() {
+        let source = "import { A, B } from './a';\n@Dec(A)\nexport class Foo extends Base {\n  b = this.B;\n  c: Foo = helper();\n}\nconst x = B;";
+        let file = SourceFile::parse(source);
+        let decl = file.exported_declarations()[0].1;
+        let refs = file.references(decl);
+
+        for name in ["A", "Dec", "Base", "helper"] {
+            assert!(refs.contains(name), "{name} should be referenced");
+        }
+        // Own name, member names and code outside the declaration
+        for name in ["Foo", "B", "x"] {
+            assert!(!refs.contains(name), "{name} should not be referenced");
+        }
+        assert_eq!(
+            file.declaration_at(decl.tokens.start)
+                .map(|d| d.name.as_str()),
+            Some("Foo")
+        );
+    }
+
+    #[test]
+    fn test_is_referenced_ignores_declaration_and_members() {
+        let file = SourceFile::parse(
+            "export class Foo {}\nexport class Bar {}\nconst x = other.Foo;\nconst y = new Bar();\nexport { Foo };",
+        );
+
+        assert!(!file.is_referenced("Foo"));
+        assert!(file.is_referenced("Bar"));
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_changed_declarations() {
+        let file = SourceFile::parse(
+            "import { A } from './a';\n\n@Dec()\nexport class Foo {\n  a = 1;\n}\n\nexport const bar = `x\ny`;\nconsole.log(A);\nexport { Foo as Baz };",
+        );
+        let names = |lines: &[Range<usize>]| {
+            file.changed_declarations(lines).map(|changed| {
+                let mut names: Vec<&str> = changed
+                    .iter()
+                    .map(|idx| file.declarations[*idx].name.as_str())
+                    .collect();
+                names.sort();
+                names
+            })
+        };
+
+        assert_eq!(names(&[3..4]), Some(vec!["Foo"]));
+        assert_eq!(names(&[5..5, 9..10]), Some(vec!["Foo", "bar"]));
+        // Imports, export lists and blank lines declare nothing
+        assert_eq!(names(&[1..3, 11..12]), Some(vec![]));
+        // Top-level code and lines removed between statements
+        assert_eq!(names(&[10..11]), None);
+        assert_eq!(names(&[8..8]), None);
+    }
+
+    #[test]
+    fn test_decorator_properties() {
+        let file = SourceFile::parse(
+            "@Component({\n  selector: 'app-user',\n  'templateUrl': \"./user.html\",\n  imports: [Foo],\n  styleUrls: ['./a.scss', `./b.scss`],\n  host: { role: 'button' },\n  template: `<p>${x}</p>`,\n})\nexport class UserComponent {}",
+        );
+        let decl = file.exported_declarations()[0].1;
+
+        assert_eq!(
+            file.decorator_properties(decl, "Component"),
+            vec![
+                ("selector", "app-user"),
+                ("templateUrl", "./user.html"),
+                ("styleUrls", "./a.scss"),
+                ("styleUrls", "./b.scss"),
+            ]
+        );
+        assert!(file.decorator_properties(decl, "Pipe").is_empty());
+    }
+
+    #[test]
+    fn test_injected_names() {
+        let file = SourceFile::parse(
+            "@Component({\n  providers: [UserStore, { provide: API_URL, useValue: url }, { provide: ENV, useValue: { api: environment.api } }, { provide: Logger, useFactory: createLogger, deps: [Http] }],\n})\nexport class Foo {\n  private auth = inject(AuthService);\n  constructor(@Inject(CONFIG) config: Config, private readonly router: Router<Route>, opts: { a: Options } = {}) {\n    const x: Helper = make();\n  }\n}",
+        );
+        let decl = file.exported_declarations()[0].1;
+        let mut names: Vec<&str> = file.injected_names(decl).into_iter().collect();
+        names.sort();
+
+        assert_eq!(
+            names,
+            vec![
+                "API_URL",
+                "AuthService",
+                "CONFIG",
+                "Config",
+                "ENV",
+                "Http",
+                "Logger",
+                "Router",
+                "UserStore",
+                "createLogger",
+            ]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::entity::{EdgeKind, Entity, EntityType, ImportInfo};
+use crate::graph::DependencyGraph;
+use crate::parser::{Route, RouteTable};
+
+/// Dependency hops followed from a lazily loaded NgModule or component to
+/// the routes it declares, e.g. through its routing module.
+const MODULE_ROUTES_DEPTH: usize = 2;
+
+/// A route of the app, with its full path.
+#[derive(Debug, Serialize)]
+pub(crate) struct RouteNode {
+    pub path: String,
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// Whether the route is in a lazily loaded chunk
+    pub lazy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_to: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub guards: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resolvers: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<RouteNode>,
+    /// Ids of the component, guards and resolvers
+    #[serde(skip)]
+    pub entity_ids: Vec<String>,
+}
+
+/// Builds the route tree of the workspace. Route arrays no other route
+/// loads are the roots; `loadChildren` and imported `children` are
+/// expanded in place.
+pub(crate) fn route_tree(
+    tables: &[RouteTable],
+    entities: &HashMap<String, Entity>,
+) -> Vec<RouteNode> {
+    let builder = TreeBuilder { tables, entities };
+
+    let mut declared = Vec::new();
+    for table in tables {
+        collect_routes(&table.routes, &mut declared);
+    }
+    let loaded: HashSet<usize> = declared
+        .iter()
+        .filter_map(|route| route.load_children.as_ref())
+        .flat_map(|target| builder.tables_of(target))
+        .collect();
+
+    let mut roots: Vec<usize> = (0..tables.len()).filter(|t| !loaded.contains(t)).collect();
+    roots.sort_by(|a, b| {
+        (&tables[*a].file, &tables[*a].name).cmp(&(&tables[*b].file, &tables[*b].name))
+    });
+    roots
+        .into_iter()
+        .flat_map(|table| builder.nodes(table, "", false, &mut vec![table]))
+        .collect()
+}
+
+/// Every route of the tree, parents before their children.
+pub(crate) fn flatten(tree: &[RouteNode]) -> Vec<&RouteNode> {
+    let mut nodes = Vec::new();
+    for node in tree {
+        nodes.push(node);
+        nodes.extend(flatten(&node.children));
+    }
+    nodes
+}
+
+/// Routes whose component, guards or resolvers are affected or depend on
+/// an affected entity, along with the paths of the routes each affected
+/// entity ends up in.
+pub(crate) fn affected_routes<'t>(
+    tree: &'t [RouteNode],
+    graph: &DependencyGraph,
+    affected_ids: &HashSet<String>,
+) -> (Vec<&'t RouteNode>, HashMap<String, Vec<String>>) {
+    let mut routes = Vec::new();
+    let mut by_entity: HashMap<String, Vec<String>> = HashMap::new();
+
+    for node in flatten(tree) {
+        let own: HashSet<String> = node.entity_ids.iter().cloned().collect();
+        let reached = graph.find_dependencies(&own, true);
+        let mut hits: Vec<&String> = own
+            .iter()
+            .chain(&reached)
+            .filter(|id| affected_ids.contains(*id))
+            .collect();
+        if hits.is_empty() {
+            continue;
+        }
+        hits.sort();
+        for id in hits {
+            let paths = by_entity.entry(id.clone()).or_default();
+            if !paths.contains(&node.path) {
+                paths.push(node.path.clone());
+            }
+        }
+        routes.push(node);
+    }
+
+    (routes, by_entity)
+}
+
+struct TreeBuilder<'t> {
+    tables: &'t [RouteTable],
+    entities: &'t HashMap<String, Entity>,
+}
+
+impl TreeBuilder<'_> {
+    /// Nodes of a route array, under the `parent` path. `stack` holds the
+    /// arrays being expanded, so that routes loading each other end.
+    fn nodes(
+        &self,
+        table: usize,
+        parent: &str,
+        lazy: bool,
+        stack: &mut Vec<usize>,
+    ) -> Vec<RouteNode> {
+        let file = &self.tables[table].file;
+        self.tables[table]
+            .routes
+            .iter()
+            .map(|route| self.node(route, file, parent, lazy, stack))
+            .collect()
+    }
+
+    fn node(
+        &self,
+        route: &Route,
+        file: &str,
+        parent: &str,
+        lazy: bool,
+        stack: &mut Vec<usize>,
+    ) -> RouteNode {
+        let path = join_path(parent, &route.path);
+        let lazy = lazy || route.load_component.is_some();
+        let component = route
+            .load_component
+            .as_ref()
+            .or(route.component.as_ref())
+            .and_then(|target| self.entity(target));
+        let guards: Vec<&Entity> = route.guards.iter().filter_map(|t| self.entity(t)).collect();
+        let resolvers: Vec<&Entity> = route
+            .resolvers
+            .iter()
+            .filter_map(|t| self.entity(t))
+            .collect();
+
+        let mut children: Vec<RouteNode> = route
+            .children
+            .iter()
+            .map(|child| self.node(child, file, &path, lazy, stack))
+            .collect();
+        if let Some(target) = &route.load_children {
+            let lazy_children = lazy || target.kind == EdgeKind::Lazy;
+            for table in self.tables_of(target) {
+                if stack.contains(&table) {
+                    continue;
+                }
+                stack.push(table);
+                children.extend(self.nodes(table, &path, lazy_children, stack));
+                stack.pop();
+            }
+        }
+
+        let names = |entities: &[&Entity]| entities.iter().map(|e| e.name.clone()).collect();
+        RouteNode {
+            file: file.to_string(),
+            component: component.map(|e| e.name.clone()),
+            lazy,
+            redirect_to: route.redirect_to.clone(),
+            guards: names(&guards),
+            resolvers: names(&resolvers),
+            children,
+            entity_ids: component
+                .into_iter()
+                .chain(guards)
+                .chain(resolvers)
+                .map(|e| e.id.clone())
+                .collect(),
+            path,
+        }
+    }
+
+    /// The entity a route names. The default export of a lazy
+    /// `loadComponent` is the component of that file.
+    fn entity(&self, target: &ImportInfo) -> Option<&Entity> {
+        if let Some(entity) = self.entities.get(&target.id) {
+            return Some(entity);
+        }
+        if target.name != "default" {
+            return None;
+        }
+        self.entities
+            .values()
+            .filter(|e| e.file_path == target.path)
+            .min_by_key(|e| (!matches!(e.entity_type, EntityType::Component), &e.name))
+    }
+
+    /// Route arrays loaded by `loadChildren` or imported `children`: the
+    /// array itself, or those of an NgModule or component and the files it
+    /// imports.
+    fn tables_of(&self, target: &ImportInfo) -> Vec<usize> {
+        let direct: Vec<usize> = (0..self.tables.len())
+            .filter(|t| {
+                let table = &self.tables[*t];
+                table.file == target.path && (table.name == target.name || target.name == "default")
+            })
+            .collect();
+        if !direct.is_empty() {
+            return direct;
+        }
+
+        let mut files: HashSet<&str> = HashSet::from([target.path.as_str()]);
+        let mut visited: HashSet<&str> = HashSet::new();
+        let mut frontier: Vec<&Entity> = self.entity(target).into_iter().collect();
+        for _ in 0..MODULE_ROUTES_DEPTH {
+            frontier = frontier
+                .iter()
+                .filter(|entity| visited.insert(entity.id.as_str()))
+                .flat_map(|entity| entity.deps.iter())
+                .filter(|dep| dep.kind != EdgeKind::Lazy)
+                .filter_map(|dep| {
+                    files.insert(dep.path.as_str());
+                    self.entities.get(&dep.id)
+                })
+                .collect();
+        }
+        (0..self.tables.len())
+            .filter(|t| files.contains(self.tables[*t].file.as_str()))
+            .collect()
+    }
+}
+
+fn collect_routes<'r>(routes: &'r [Route], out: &mut Vec<&'r Route>) {
+    for route in routes {
+        out.push(route);
+        collect_routes(&route.children, out);
+    }
+}
+
+/// Joins a route path to its parent's, as the router does.
+fn join_path(parent: &str, path: &str) -> String {
+    let segments: Vec<&str> = parent
+        .split('/')
+        .chain(path.split('/'))
+        .filter(|s| !s.is_empty())
+        .collect();
+    format!("/{}", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_path() {
+        assert_eq!(join_path("", ""), "/");
+        assert_eq!(join_path("/admin", ""), "/admin");
+        assert_eq!(join_path("/admin", "users/:id"), "/admin/users/:id");
+    }
+}
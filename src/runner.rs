@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::source::FileSource;
+
+/// Test runner that affected tests are handed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestRunner {
+    Jest,
+    Vitest,
+    Nx,
+}
+
+impl TestRunner {
+    /// Config files the runner picks up in a project root, by preference.
+    fn config_files(self) -> &'static [&'static str] {
+        match self {
+            TestRunner::Jest => &[
+                "jest.config.ts",
+                "jest.config.js",
+                "jest.config.mjs",
+                "jest.config.cjs",
+                "jest.config.json",
+            ],
+            TestRunner::Vitest => &[
+                "vitest.config.ts",
+                "vitest.config.mts",
+                "vitest.config.js",
+                "vitest.config.mjs",
+                "vite.config.ts",
+                "vite.config.mts",
+                "vite.config.js",
+                "vite.config.mjs",
+            ],
+            // Nx runs the test target of the project, whatever its config
+            TestRunner::Nx => &[],
+        }
+    }
+}
+
+/// Affected tests of one project and the command that runs them. Paths are
+/// relative to the workspace root, which the command is run from.
+#[derive(Debug, Serialize)]
+pub(crate) struct TestTarget {
+    pub project: String,
+    pub root: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<String>,
+    pub tests: Vec<String>,
+    pub command: String,
+}
+
+/// Groups test files by the project owning them: for Nx the closest
+/// directory with a `project.json`, otherwise the closest directory with a
+/// config file of the runner. Tests outside of any project belong to the
+/// workspace root, except for Nx, which can only run tests of a project;
+/// those are reported and skipped.
+pub(crate) fn group_tests(
+    source: &FileSource,
+    root_path: &Path,
+    runner: TestRunner,
+    tests: &[String],
+) -> Vec<TestTarget> {
+    let mut projects: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+
+    for test in tests {
+        let owner = Path::new(test)
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(root_path))
+            .find(|dir| match runner {
+                TestRunner::Nx => source.is_file(&dir.join("project.json")),
+                TestRunner::Jest | TestRunner::Vitest => find_config(source, dir, runner).is_some(),
+            });
+
+        match owner {
+            Some(dir) => projects.entry(dir.to_path_buf()).or_default(),
+            None if runner == TestRunner::Nx => {
+                eprintln!("Warning: No Nx project owns {}, skipping it", test);
+                continue;
+            }
+            None => projects.entry(root_path.to_path_buf()).or_default(),
+        }
+        .push(relative(root_path, Path::new(test)));
+    }
+
+    projects
+        .into_iter()
+        .map(|(dir, tests)| {
+            let root = relative(root_path, &dir);
+            let project = project_name(source, &dir).unwrap_or_else(|| root.clone());
+            let config = find_config(source, &dir, runner).map(|c| relative(root_path, &c));
+            let command = command(runner, &project, config.as_deref(), &tests);
+
+            TestTarget {
+                project,
+                root,
+                config,
+                tests,
+                command,
+            }
+        })
+        .collect()
+}
+
+fn find_config(source: &FileSource, dir: &Path, runner: TestRunner) -> Option<PathBuf> {
+    runner
+        .config_files()
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| source.is_file(path))
+}
+
+/// The name Nx knows the project of a `project.json` by: its `name`, or
+/// as Nx infers it, the `name` of the `package.json` next to it or else
+/// the directory name.
+fn project_name(source: &FileSource, dir: &Path) -> Option<String> {
+    let declared_name = |file: &str| -> Option<String> {
+        let content = source.read_to_string(&dir.join(file)).ok()?;
+        let project: Value = serde_json::from_str(&content).ok()?;
+        project.get("name")?.as_str().map(str::to_string)
+    };
+
+    if !source.is_file(&dir.join("project.json")) {
+        return None;
+    }
+    declared_name("project.json")
+        .or_else(|| declared_name("package.json"))
+        .or_else(|| Some(dir.file_name()?.to_string_lossy().to_string()))
+}
+
+fn command(runner: TestRunner, project: &str, config: Option<&str>, tests: &[String]) -> String {
+    let mut args: Vec<&str> = match runner {
+        TestRunner::Jest => vec!["npx", "jest"],
+        TestRunner::Vitest => vec!["npx", "vitest", "run"],
+        TestRunner::Nx => vec!["npx", "nx", "test", project, "--"],
+    };
+    if let Some(config) = config {
+        args.extend(["--config", config]);
+    }
+    args.extend(tests.iter().map(String::as_str));
+
+    args.into_iter()
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn relative(root_path: &Path, path: &Path) -> String {
+    match path.strip_prefix(root_path) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// Quotes an argument for POSIX shells when it contains special characters.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./@:=+,".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::FILESYSTEM;
+    use std::fs;
+
+    #[test]
+    fn test_group_tests_by_project() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let write = |rel: &str, content: &str| {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write("apps/web/project.json", r#"{ "name": "web" }"#);
+        write("apps/web/jest.config.ts", "");
+        write("libs/shared/jest.config.ts", "");
+        write("libs/jest.config.ts", "");
+        // Nx infers the names of projects that do not declare one
+        write("libs/ui/project.json", "{}");
+        write("libs/ui/package.json", r#"{ "name": "@acme/ui" }"#);
+        write("libs/util/project.json", "{}");
+        let tests: Vec<String> = [
+            "apps/web/src/app.spec.ts",
+            "apps/web/src/my app.spec.ts",
+            "libs/shared/src/user.spec.ts",
+            "libs/ui/src/button.spec.ts",
+            "libs/util/src/date.spec.ts",
+            "tools/script.spec.ts",
+        ]
+        .iter()
+        .map(|test| root.join(test).to_string_lossy().to_string())
+        .collect();
+
+        let targets = group_tests(&FILESYSTEM, &root, TestRunner::Jest, &tests);
+        let summary: Vec<(&str, &str, Option<&str>, usize)> = targets
+            .iter()
+            .map(|t| {
+                (
+                    t.project.as_str(),
+                    t.root.as_str(),
+                    t.config.as_deref(),
+                    t.tests.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (".", ".", None, 1),
+                ("web", "apps/web", Some("apps/web/jest.config.ts"), 2),
+                // Projects without a Jest config run with the closest one
+                ("libs", "libs", Some("libs/jest.config.ts"), 2),
+                (
+                    "libs/shared",
+                    "libs/shared",
+                    Some("libs/shared/jest.config.ts"),
+                    1
+                ),
+            ]
+        );
+        assert_eq!(
+            targets[1].command,
+            "npx jest --config apps/web/jest.config.ts apps/web/src/app.spec.ts 'apps/web/src/my app.spec.ts'"
+        );
+
+        // Nx only runs tests of projects
+        let targets = group_tests(&FILESYSTEM, &root, TestRunner::Nx, &tests);
+        let commands: Vec<&str> = targets.iter().map(|t| t.command.as_str()).collect();
+        assert_eq!(
+            commands,
+            vec![
+                "npx nx test web -- apps/web/src/app.spec.ts 'apps/web/src/my app.spec.ts'",
+                "npx nx test @acme/ui -- libs/ui/src/button.spec.ts",
+                "npx nx test util -- libs/util/src/date.spec.ts",
+            ]
+        );
+    }
+}
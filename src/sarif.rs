@@ -0,0 +1,578 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::entity::{Entity, EntityType};
+use crate::mem_leaks::{EntityLeakReport, Severity};
+use crate::parser::declaration_lines;
+use crate::source::FileSource;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+const SRCROOT: &str = "%SRCROOT%";
+
+/// Rule of an unused entity finding.
+const UNUSED_RULE: &str = "unused-entity";
+/// Rule of a circular dependency finding.
+const CYCLE_RULE: &str = "circular-dependency";
+
+/// Every rule sting reports, by stable id. Memory leak rules use the
+/// finding kind as id.
+const RULES: &[(&str, &str, Level)] = &[
+    (
+        UNUSED_RULE,
+        "Exported entity is not imported anywhere in the workspace",
+        Level::Note,
+    ),
+    (
+        CYCLE_RULE,
+        "Entity is part of a circular dependency",
+        Level::Warning,
+    ),
+    (
+        "rxjs-subscription",
+        "RxJS subscription without unsubscribe or takeUntil",
+        Level::Error,
+    ),
+    (
+        "dom-listener",
+        "addEventListener without a matching removeEventListener",
+        Level::Error,
+    ),
+    (
+        "timer-interval",
+        "setInterval without clearInterval",
+        Level::Error,
+    ),
+    (
+        "timer-timeout",
+        "setTimeout without clearTimeout",
+        Level::Warning,
+    ),
+    (
+        "observer",
+        "Observer (Mutation/Resize/Intersection) without disconnect",
+        Level::Error,
+    ),
+    ("websocket", "WebSocket without close", Level::Error),
+    ("eventsource", "EventSource without close", Level::Error),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Note,
+    Warning,
+    Error,
+}
+
+impl From<Severity> for Level {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::High => Level::Error,
+            Severity::Medium => Level::Warning,
+            Severity::Low => Level::Note,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Log {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    original_uri_base_ids: HashMap<&'static str, ArtifactLocation>,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+    default_configuration: Configuration,
+}
+
+#[derive(Serialize)]
+struct Configuration {
+    level: Level,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: Level,
+    message: Message,
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    partial_fingerprints: HashMap<&'static str, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+}
+
+/// Collects findings of one command and renders them as a SARIF log with
+/// paths relative to the workspace root.
+pub(crate) struct SarifBuilder<'a> {
+    root_path: &'a Path,
+    /// Where declaration lines are read from
+    source: &'a FileSource,
+    results: Vec<SarifResult>,
+    declaration_lines: HashMap<String, HashMap<String, usize>>,
+}
+
+impl<'a> SarifBuilder<'a> {
+    pub fn new(root_path: &'a Path, source: &'a FileSource) -> Self {
+        SarifBuilder {
+            root_path,
+            source,
+            results: Vec::new(),
+            declaration_lines: HashMap::new(),
+        }
+    }
+
+    pub fn add_unused(&mut self, entity: &Entity) {
+        let location = self.entity_location(entity);
+        self.push(
+            UNUSED_RULE,
+            Level::Note,
+            format!(
+                "{} '{}' is exported but never imported",
+                entity.entity_type, entity.name
+            ),
+            location,
+            Vec::new(),
+            fingerprint(&[&self.relative_path(&entity.file_path), &entity.name]),
+        );
+    }
+
+    /// Adds one result per participant of a cycle, each pointing at the
+    /// other participants as related locations.
+    pub fn add_cycle(&mut self, cycle: &[&Entity]) {
+        let mut names: Vec<&str> = cycle.iter().map(|e| e.name.as_str()).collect();
+        if let Some(first) = names.first() {
+            names.push(first);
+        }
+        let chain = names.join(" -> ");
+        let cycle_key: Vec<String> = cycle
+            .iter()
+            .map(|e| format!("{}:{}", self.relative_path(&e.file_path), e.name))
+            .collect();
+
+        for (i, entity) in cycle.iter().enumerate() {
+            let location = self.entity_location(entity);
+            let related = cycle
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| self.entity_location(other))
+                .collect();
+
+            let mut key = vec![cycle_key[i].as_str()];
+            key.extend(cycle_key.iter().map(String::as_str));
+            self.push(
+                CYCLE_RULE,
+                Level::Warning,
+                format!(
+                    "'{}' is part of a circular dependency: {}",
+                    entity.name, chain
+                ),
+                location,
+                related,
+                fingerprint(&key),
+            );
+        }
+    }
+
+    pub fn add_leaks(&mut self, report: &EntityLeakReport) {
+        for finding in &report.findings {
+            let Some((rule_id, _, _)) = RULES.iter().find(|(id, _, _)| *id == finding.kind) else {
+                continue;
+            };
+
+            let location = self.location(&report.file_path, Some(finding.line));
+            let file = self.relative_path(&report.file_path);
+            self.push(
+                rule_id,
+                finding.severity.into(),
+                format!("{}: {}", report.name, finding.message),
+                location,
+                Vec::new(),
+                fingerprint(&[&file, &report.name, finding.kind, &finding.message]),
+            );
+        }
+    }
+
+    pub fn into_json(self) -> Result<String> {
+        let log = Log {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "sting",
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules: RULES
+                            .iter()
+                            .map(|(id, description, level)| Rule {
+                                id,
+                                short_description: Message {
+                                    text: description.to_string(),
+                                },
+                                default_configuration: Configuration { level: *level },
+                            })
+                            .collect(),
+                    },
+                },
+                original_uri_base_ids: HashMap::from([(
+                    SRCROOT,
+                    ArtifactLocation {
+                        uri: format!(
+                            "file://{}/",
+                            encode_uri_path(&self.root_path.to_string_lossy())
+                        ),
+                        uri_base_id: None,
+                    },
+                )]),
+                results: self.results,
+            }],
+        };
+
+        Ok(serde_json::to_string_pretty(&log)?)
+    }
+
+    fn push(
+        &mut self,
+        rule_id: &'static str,
+        level: Level,
+        message: String,
+        location: Location,
+        related_locations: Vec<Location>,
+        fingerprint: String,
+    ) {
+        let rule_index = RULES
+            .iter()
+            .position(|(id, _, _)| *id == rule_id)
+            .unwrap_or_default();
+
+        self.results.push(SarifResult {
+            rule_id,
+            rule_index,
+            level,
+            message: Message { text: message },
+            locations: vec![location],
+            related_locations,
+            partial_fingerprints: HashMap::from([("stingFinding/v1", fingerprint)]),
+        });
+    }
+
+    fn entity_location(&mut self, entity: &Entity) -> Location {
+        let line = self.declaration_line(entity);
+        self.location(&entity.file_path, line)
+    }
+
+    fn relative_path(&self, file_path: &str) -> String {
+        Path::new(file_path)
+            .strip_prefix(self.root_path)
+            .unwrap_or(Path::new(file_path))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn location(&self, file_path: &str, line: Option<usize>) -> Location {
+        Location {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: encode_uri_path(&self.relative_path(file_path)),
+                    uri_base_id: Some(SRCROOT),
+                },
+                region: line.map(|start_line| Region { start_line }),
+            },
+        }
+    }
+
+    /// Line of the exported declaration of an entity, parsed once per file.
+    fn declaration_line(&mut self, entity: &Entity) -> Option<usize> {
+        if matches!(entity.entity_type, EntityType::Worker) {
+            return Some(1);
+        }
+
+        let lines = self
+            .declaration_lines
+            .entry(entity.file_path.clone())
+            .or_insert_with(|| declaration_lines(self.source, &entity.file_path));
+
+        lines.get(&entity.name).copied()
+    }
+}
+
+fn fingerprint(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Percent-encodes a path for use in a URI, keeping `/` separators.
+fn encode_uri_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem_leaks::{self, LeakFinding};
+    use crate::parser::JSDOC_HEADER;
+    use crate::source::FILESYSTEM;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::sync::Arc;
+
+    fn entity(root: &Path, file: &str, name: &str, entity_type: EntityType) -> Entity {
+        Entity::new(
+            name.to_string(),
+            entity_type,
+            root.join(file).to_string_lossy().to_string(),
+            Arc::new(Vec::new()),
+        )
+    }
+
+    #[test]
+    fn test_unused_and_cycles_point_at_declarations() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("libs/my lib")).unwrap();
+        fs::write(
+            root.join("libs/my lib/a.ts"),
+            "import { B } from './b';\n\nexport class A {}\n",
+        )
+        .unwrap();
+        fs::write(root.join("libs/my lib/b.ts"), "export class B {}\n").unwrap();
+
+        let a = entity(&root, "libs/my lib/a.ts", "A", EntityType::Class);
+        let b = entity(&root, "libs/my lib/b.ts", "B", EntityType::Class);
+
+        let mut sarif = SarifBuilder::new(&root, &FILESYSTEM);
+        sarif.add_unused(&a);
+        sarif.add_cycle(&[&a, &b]);
+        let log: Value = serde_json::from_str(&sarif.into_json().unwrap()).unwrap();
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "sting");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+
+        let unused = &results[0];
+        assert_eq!(unused["ruleId"], "unused-entity");
+        assert_eq!(unused["level"], "note");
+        let location = &unused["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "libs/my%20lib/a.ts");
+        assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+        assert_eq!(location["region"]["startLine"], 3);
+
+        let cycle = &results[2];
+        assert_eq!(cycle["ruleId"], "circular-dependency");
+        assert!(
+            cycle["message"]["text"]
+                .as_str()
+                .unwrap()
+                .contains("A -> B -> A")
+        );
+        assert_eq!(
+            cycle["relatedLocations"][0]["physicalLocation"]["region"]["startLine"],
+            3
+        );
+
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        let rule_index = cycle["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(rules[rule_index]["id"], "circular-dependency");
+    }
+
+    #[test]
+    fn test_leak_findings_use_kind_as_rule() {
+        let root = Path::new("/repo");
+        let report = EntityLeakReport {
+            name: "Poller".to_string(),
+            entity_type: "service".to_string(),
+            file_path: "/repo/apps/web/poller.ts".to_string(),
+            severity: Severity::High,
+            findings: vec![LeakFinding {
+                severity: Severity::Medium,
+                line: 12,
+                kind: "timer-interval",
+                message: "setInterval without clearInterval".to_string(),
+            }],
+        };
+
+        let mut sarif = SarifBuilder::new(root, &FILESYSTEM);
+        sarif.add_leaks(&report);
+        let first: Value = serde_json::from_str(&sarif.into_json().unwrap()).unwrap();
+
+        let result = &first["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "timer-interval");
+        assert_eq!(result["level"], "warning");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            12
+        );
+
+        // Fingerprints only depend on workspace-relative data
+        let mut sarif = SarifBuilder::new(Path::new("/elsewhere"), &FILESYSTEM);
+        sarif.add_leaks(&EntityLeakReport {
+            file_path: "/elsewhere/apps/web/poller.ts".to_string(),
+            findings: report.findings.clone(),
+            ..report
+        });
+        let second: Value = serde_json::from_str(&sarif.into_json().unwrap()).unwrap();
+        assert_eq!(
+            result["partialFingerprints"],
+            second["runs"][0]["results"][0]["partialFingerprints"]
+        );
+    }
+
+    #[test]
+    fn test_locations_after_block_comments_use_file_lines() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("libs")).unwrap();
+        fs::write(
+            root.join("libs/a.ts"),
+            format!("{JSDOC_HEADER}export class A {{}}\n"),
+        )
+        .unwrap();
+        fs::write(
+            root.join("libs/b.ts"),
+            format!("{JSDOC_HEADER}export class B {{}}\n"),
+        )
+        .unwrap();
+
+        let a = entity(&root, "libs/a.ts", "A", EntityType::Class);
+        let b = entity(&root, "libs/b.ts", "B", EntityType::Class);
+        let mut sarif = SarifBuilder::new(&root, &FILESYSTEM);
+        sarif.add_unused(&a);
+        sarif.add_cycle(&[&a, &b]);
+        let log: Value = serde_json::from_str(&sarif.into_json().unwrap()).unwrap();
+
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            13
+        );
+        assert_eq!(
+            results[1]["relatedLocations"][0]["physicalLocation"]["region"]["startLine"],
+            13
+        );
+    }
+
+    #[test]
+    fn test_leak_locations_after_block_comments_use_file_lines() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("libs")).unwrap();
+        fs::write(
+            root.join("libs/poller.service.ts"),
+            format!(
+                "{JSDOC_HEADER}export class PollerService {{\n  \
+                 start() {{\n    \
+                 setInterval(() => this.poll(), 1000);\n  \
+                 }}\n\
+                 }}\n"
+            ),
+        )
+        .unwrap();
+
+        let service = entity(
+            &root,
+            "libs/poller.service.ts",
+            "PollerService",
+            EntityType::Service,
+        );
+        let entities = HashMap::from([(service.id.clone(), service)]);
+        let reports = mem_leaks::analyze(&FILESYSTEM, &entities, &[], false);
+
+        let mut sarif = SarifBuilder::new(&root, &FILESYSTEM);
+        for report in &reports {
+            sarif.add_leaks(report);
+        }
+        let log: Value = serde_json::from_str(&sarif.into_json().unwrap()).unwrap();
+
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "timer-interval");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            15
+        );
+    }
+}
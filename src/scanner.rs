@@ -1,95 +1,566 @@
-use std::fs;
-use std::path::Path;
-
-use anyhow::Result;
-
-const DEFAULT_SKIP_DIRECTORIES: &[&str] = &[
-    "mocks",
-    "__mocks__",
-    "mocks_stubs",
-    "tests",
-    "environments",
-    "i18n",
-    ".storybook",
-    "fakes",
-];
-
-const DEFAULT_SKIP_FILE_SUFFIXES: &[&str] = &[
-    ".d.ts",
-    ".stories.ts",
-    "-stub.ts",
-    "-test.ts",
-    "-stub.service.ts",
-    "mocks.ts",
-    "mock.ts",
-];
-
-pub(crate) struct Scanner {
-    skip_directories: Vec<&'static str>,
-    skip_file_suffixes: Vec<&'static str>,
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use rayon::prelude::*;
+
+use crate::config::{ScanPattern, ScanSettings};
+use crate::source::FileSource;
+
+/// Ignore files honoured in every directory. Later files take precedence.
+const IGNORE_FILES: &[&str] = &[".gitignore", ".ignore"];
+
+struct Rule {
+    pattern: ScanPattern,
+    matcher: GlobMatcher,
+    /// For `dir/**` patterns, matches `dir` itself so the whole directory
+    /// is skipped instead of every file below it
+    dir_matcher: Option<GlobMatcher>,
 }
 
-impl Scanner {
-    pub fn new() -> Self {
-        Scanner {
-            skip_directories: DEFAULT_SKIP_DIRECTORIES.to_vec(),
-            skip_file_suffixes: DEFAULT_SKIP_FILE_SUFFIXES.to_vec(),
+impl Rule {
+    fn new(pattern: &ScanPattern) -> Result<Self> {
+        let compile = |p: &str| -> Result<GlobMatcher> {
+            Ok(Glob::new(p)
+                .with_context(|| format!("Invalid glob pattern '{}' ({})", p, pattern.origin))?
+                .compile_matcher())
+        };
+
+        Ok(Rule {
+            pattern: pattern.clone(),
+            matcher: compile(&pattern.pattern)?,
+            dir_matcher: pattern
+                .pattern
+                .strip_suffix("/**")
+                .map(compile)
+                .transpose()?,
+        })
+    }
+
+    fn matches(&self, relative: &Path, is_dir: bool) -> bool {
+        if is_dir {
+            self.dir_matcher
+                .as_ref()
+                .is_some_and(|matcher| matcher.is_match(relative))
+        } else {
+            self.matcher.is_match(relative)
         }
     }
+}
 
-    pub fn scan(&self, dir: &Path) -> Result<Vec<String>> {
-        let mut ts_files = Vec::new();
+/// Ignore files of a directory and, through `parent`, of all directories
+/// above it.
+struct IgnoreStack<'p> {
+    ignores: Vec<Gitignore>,
+    parent: Option<&'p IgnoreStack<'p>>,
+}
 
-        if dir.is_dir() {
-            for entry in fs::read_dir(dir)? {
-                let entry = entry?;
-                let path = entry.path();
+impl IgnoreStack<'_> {
+    /// Iterates from the deepest ignore file to the outermost one.
+    fn iter(&self) -> impl Iterator<Item = &Gitignore> {
+        let mut stack = Some(self);
+        std::iter::from_fn(move || {
+            let current = stack?;
+            stack = current.parent;
+            Some(current.ignores.iter().rev())
+        })
+        .flatten()
+    }
+}
 
-                if path.is_dir() {
-                    if let Some(dir_name) = path.file_name() {
-                        if let Some(name_str) = dir_name.to_str() {
-                            if self.should_skip_directory(name_str) {
-                                continue;
-                            }
-                        }
-                    }
+#[derive(Default)]
+struct Walk {
+    files: Vec<String>,
+    tests: Vec<String>,
+    excluded: Vec<(PathBuf, String)>,
+}
+
+/// TypeScript files found below a directory.
+pub(crate) struct ScannedFiles {
+    pub files: Vec<String>,
+    /// Test files excluded from `files` by a default test exclude such as
+    /// `**/tests/**`, kept to find the tests of affected code
+    pub tests: Vec<String>,
+}
+
+/// Walks project directories for TypeScript files, applying the include
+/// and exclude patterns and the `.gitignore`/`.ignore` files on the way.
+pub(crate) struct Scanner<'a> {
+    root_path: &'a Path,
+    source: &'a FileSource,
+    include: Vec<Rule>,
+    exclude: Vec<Rule>,
+    ignore_files: bool,
+    report_excluded: bool,
+}
+
+impl<'a> Scanner<'a> {
+    /// Creates a scanner for the workspace at `root_path` that walks the
+    /// directories of `source`. Patterns are matched against paths relative
+    /// to the root.
+    pub fn new(
+        root_path: &'a Path,
+        settings: &ScanSettings,
+        source: &'a FileSource,
+    ) -> Result<Self> {
+        Ok(Scanner {
+            root_path,
+            source,
+            include: settings
+                .include
+                .iter()
+                .map(Rule::new)
+                .collect::<Result<_>>()?,
+            exclude: settings
+                .exclude
+                .iter()
+                .map(Rule::new)
+                .collect::<Result<_>>()?,
+            ignore_files: settings.ignore_files,
+            report_excluded: settings.report_excluded,
+        })
+    }
+
+    pub fn scan(&self, dir: &Path) -> Result<ScannedFiles> {
+        let parents = IgnoreStack {
+            ignores: if self.ignore_files {
+                self.parent_ignores(dir)
+            } else {
+                Vec::new()
+            },
+            parent: None,
+        };
+
+        if !self.source.is_dir(dir) {
+            return Ok(ScannedFiles {
+                files: Vec::new(),
+                tests: Vec::new(),
+            });
+        }
+
+        // Exclusions are collected during the parallel walk and reported
+        // afterwards so the output does not depend on thread scheduling
+        let walk = self.scan_dir(dir, &parents, false)?;
+        for (path, reason) in &walk.excluded {
+            self.report(path, reason);
+        }
+        Ok(ScannedFiles {
+            files: walk.files,
+            tests: walk.tests,
+        })
+    }
+
+    /// Walks `dir`, collecting only test files when `tests_only` is set
+    /// because a test exclude matched the directory.
+    fn scan_dir(&self, dir: &Path, parent: &IgnoreStack, tests_only: bool) -> Result<Walk> {
+        let ignores = IgnoreStack {
+            ignores: if self.ignore_files {
+                self.load_ignore_files(dir)
+            } else {
+                Vec::new()
+            },
+            parent: Some(parent),
+        };
+
+        let entries = self.source.read_dir(dir)?;
 
-                    match self.scan(&path) {
-                        Ok(mut nested_files) => ts_files.append(&mut nested_files),
-                        Err(e) => eprintln!("Warning: Could not read directory {:?}: {}", path, e),
+        let mut walk = Walk::default();
+        let mut subdirs = Vec::new();
+
+        for entry in entries {
+            let path = entry.path;
+            let is_dir = entry.is_dir;
+            let is_source_file = entry.is_file && is_typescript_file(&path);
+
+            if !is_dir && !is_source_file {
+                continue;
+            }
+
+            let Some(path_str) = path.to_str().map(str::to_string) else {
+                continue;
+            };
+            let is_test = is_source_file && is_test_file(&path_str);
+
+            match self.exclusion_reason(&path, is_dir, &ignores) {
+                None if is_dir => subdirs.push((path, tests_only)),
+                None if tests_only => {
+                    if is_test {
+                        walk.tests.push(path_str);
+                    }
+                }
+                None => walk.files.push(path_str),
+                Some(reason) => {
+                    if (is_dir || is_test) && self.keeps_tests(&path, is_dir, &ignores) {
+                        if is_dir {
+                            subdirs.push((path.clone(), true));
+                        } else {
+                            walk.tests.push(path_str);
+                        }
                     }
-                } else if path.is_file() {
-                    if self.should_skip_file(&path) {
-                        continue;
+                    // Only report paths excluded from the entities once
+                    if !tests_only {
+                        walk.excluded.push((path, reason));
                     }
+                }
+            }
+        }
 
-                    if let Some(extension) = path.extension() {
-                        if extension == "ts" || extension == "tsx" {
-                            if let Some(path_str) = path.to_str() {
-                                ts_files.push(path_str.to_string());
-                            }
-                        }
+        let nested: Vec<Walk> = subdirs
+            .par_iter()
+            .filter_map(
+                |(path, tests_only)| match self.scan_dir(path, &ignores, *tests_only) {
+                    Ok(walk) => Some(walk),
+                    Err(e) => {
+                        eprintln!("Warning: Could not read directory {:?}: {}", path, e);
+                        None
                     }
+                },
+            )
+            .collect();
+
+        for mut nested in nested {
+            walk.files.append(&mut nested.files);
+            walk.tests.append(&mut nested.tests);
+            walk.excluded.append(&mut nested.excluded);
+        }
+
+        Ok(walk)
+    }
+
+    /// Returns why a path is excluded, or None when it should be scanned.
+    fn exclusion_reason(&self, path: &Path, is_dir: bool, ignores: &IgnoreStack) -> Option<String> {
+        let relative = path.strip_prefix(self.root_path).unwrap_or(path);
+
+        if let Some(rule) = self.exclude.iter().find(|r| r.matches(relative, is_dir)) {
+            return Some(format!(
+                "exclude pattern '{}' ({})",
+                rule.pattern.pattern, rule.pattern.origin
+            ));
+        }
+
+        if let Some(reason) = self.ignore_reason(path, is_dir, ignores) {
+            return Some(reason);
+        }
+
+        if !is_dir && !self.is_included(relative) {
+            return Some("not matched by any include pattern".to_string());
+        }
+
+        None
+    }
+
+    /// Whether test files at or below an excluded path are still scanned
+    /// as tests: test excludes are the only rules that exclude it.
+    fn keeps_tests(&self, path: &Path, is_dir: bool, ignores: &IgnoreStack) -> bool {
+        let relative = path.strip_prefix(self.root_path).unwrap_or(path);
+        let mut rules = self
+            .exclude
+            .iter()
+            .filter(|r| r.matches(relative, is_dir))
+            .peekable();
+
+        rules.peek().is_some()
+            && rules.all(|r| r.pattern.keeps_tests)
+            && self.ignore_reason(path, is_dir, ignores).is_none()
+            && (is_dir || self.is_included(relative))
+    }
+
+    /// The rule of the ignore files that excludes a path. The deepest ignore
+    /// file with a matching rule decides.
+    fn ignore_reason(&self, path: &Path, is_dir: bool, ignores: &IgnoreStack) -> Option<String> {
+        for ignore in ignores.iter() {
+            match ignore.matched(path, is_dir) {
+                Match::None => continue,
+                Match::Whitelist(_) => break,
+                Match::Ignore(glob) => {
+                    let from = glob
+                        .from()
+                        .map(|f| {
+                            f.strip_prefix(self.root_path)
+                                .unwrap_or(f)
+                                .display()
+                                .to_string()
+                        })
+                        .unwrap_or_default();
+                    return Some(format!("'{}' in {}", glob.original(), from));
                 }
             }
         }
+        None
+    }
+
+    fn is_included(&self, relative: &Path) -> bool {
+        self.include.is_empty() || self.include.iter().any(|r| r.matches(relative, false))
+    }
+
+    /// Ignore files of the directories above `dir`, up to the enclosing
+    /// git repository root or, outside of git, the workspace root.
+    fn parent_ignores(&self, dir: &Path) -> Vec<Gitignore> {
+        let mut parents = Vec::new();
+        let mut found_repo_root = false;
+
+        for ancestor in dir.ancestors().skip(1) {
+            parents.push(ancestor);
+            if ancestor.join(".git").exists() {
+                found_repo_root = true;
+                break;
+            }
+        }
+
+        if !found_repo_root {
+            parents.retain(|p| p.starts_with(self.root_path));
+        }
+
+        parents
+            .into_iter()
+            .rev()
+            .flat_map(|dir| self.load_ignore_files(dir))
+            .collect()
+    }
+
+    fn load_ignore_files(&self, dir: &Path) -> Vec<Gitignore> {
+        IGNORE_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|path| self.source.is_file(path))
+            .filter_map(|path| match self.source.read_to_string(&path) {
+                Ok(content) => Some(parse_ignore_file(dir, &path, &content)),
+                Err(e) => {
+                    eprintln!("Warning: Could not read {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn report(&self, path: &Path, reason: &str) {
+        if self.report_excluded {
+            let relative = path.strip_prefix(self.root_path).unwrap_or(path);
+            eprintln!("Excluded {}: {}", relative.display(), reason);
+        }
+    }
+}
+
+fn parse_ignore_file(dir: &Path, path: &Path, content: &str) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(dir);
+    for line in content.lines() {
+        if let Err(e) = builder.add_line(Some(path.to_path_buf()), line) {
+            eprintln!("Warning: Could not fully parse {}: {}", path.display(), e);
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("Warning: Could not fully parse {}: {}", path.display(), e);
+        Gitignore::empty()
+    })
+}
+
+/// Spec, unit and end-to-end test files.
+pub(crate) fn is_test_file(path: &str) -> bool {
+    path.ends_with(".test.ts")
+        || path.ends_with(".spec.ts")
+        || path.ends_with(".e2e.ts")
+        || path.ends_with("-test.ts")
+}
+
+fn is_typescript_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "ts" || extension == "tsx")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StingConfig;
+    use crate::source::FILESYSTEM;
+    use std::fs;
 
-        Ok(ts_files)
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
     }
 
-    fn should_skip_directory(&self, dir_name: &str) -> bool {
-        self.skip_directories.contains(&dir_name)
+    fn scan(root: &Path, settings: &ScanSettings) -> Vec<String> {
+        let scanner = Scanner::new(root, settings, &FILESYSTEM).unwrap();
+        scanner
+            .scan(&root.join("apps"))
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|f| {
+                Path::new(&f)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .collect()
     }
 
-    fn should_skip_file(&self, path: &Path) -> bool {
-        if let Some(file_name) = path.file_name() {
-            if let Some(name_str) = file_name.to_str() {
-                return self
-                    .skip_file_suffixes
-                    .iter()
-                    .any(|suffix| name_str.ends_with(suffix));
+    #[test]
+    fn test_default_excludes() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, "apps/web/src/app.ts", "");
+        write(root, "apps/web/src/app.tsx", "");
+        write(root, "apps/web/src/types.d.ts", "");
+        write(root, "apps/web/src/button.stories.ts", "");
+        write(root, "apps/web/src/mocks/user.ts", "");
+        write(root, "apps/web/node_modules/pkg/index.ts", "");
+        write(root, "apps/web/src/readme.md", "");
+
+        let files = scan(root, &StingConfig::default().scan);
+
+        assert_eq!(files, vec!["apps/web/src/app.ts", "apps/web/src/app.tsx"]);
+    }
+
+    #[test]
+    fn test_test_excludes_keep_test_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, ".gitignore", "ignored/\n");
+        write(root, "apps/web/src/app.ts", "");
+        write(root, "apps/web/src/app.spec.ts", "");
+        write(root, "apps/web/src/helper-test.ts", "");
+        write(root, "apps/web/tests/flow.spec.ts", "");
+        write(root, "apps/web/tests/helpers.ts", "");
+        write(root, "apps/web/tests/ignored/old.spec.ts", "");
+        write(root, "apps/web/mocks/user.spec.ts", "");
+
+        let scanner = Scanner::new(root, &StingConfig::default().scan, &FILESYSTEM).unwrap();
+        let scanned = scanner.scan(&root.join("apps")).unwrap();
+        let relative = |files: Vec<String>| -> Vec<String> {
+            let mut files: Vec<String> = files
+                .iter()
+                .map(|f| {
+                    Path::new(f)
+                        .strip_prefix(root)
+                        .unwrap()
+                        .display()
+                        .to_string()
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(
+            relative(scanned.files),
+            vec!["apps/web/src/app.spec.ts", "apps/web/src/app.ts"]
+        );
+        // Other default excludes and ignore files still apply to tests
+        assert_eq!(
+            relative(scanned.tests),
+            vec!["apps/web/src/helper-test.ts", "apps/web/tests/flow.spec.ts"]
+        );
+    }
+
+    #[test]
+    fn test_gitignore_and_ignore_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, ".gitignore", "dist/\n*.generated.ts\n");
+        write(root, "apps/web/.ignore", "legacy/\n!keep.generated.ts\n");
+        write(root, "apps/web/src/app.ts", "");
+        write(root, "apps/web/dist/main.ts", "");
+        write(root, "apps/web/src/api.generated.ts", "");
+        write(root, "apps/web/src/keep.generated.ts", "");
+        write(root, "apps/web/legacy/old.ts", "");
+
+        let files = scan(root, &StingConfig::default().scan);
+        assert_eq!(
+            files,
+            vec!["apps/web/src/app.ts", "apps/web/src/keep.generated.ts"]
+        );
+
+        let mut settings = StingConfig::default().scan;
+        settings.ignore_files = false;
+        assert_eq!(scan(root, &settings).len(), 5);
+    }
+
+    #[test]
+    fn test_include_and_exclude_patterns() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, "apps/web/src/app.ts", "");
+        write(root, "apps/web/src/feature/feature.ts", "");
+        write(root, "apps/web/scripts/build.ts", "");
+
+        let mut settings = StingConfig::default().scan;
+        settings.include_from_command_line(&["apps/*/src/**".to_string()]);
+        settings.exclude_from_command_line(&["**/feature/**".to_string()]);
+
+        assert_eq!(scan(root, &settings), vec!["apps/web/src/app.ts"]);
+    }
+
+    #[test]
+    fn test_exclusion_reasons_name_the_rule() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(root, "apps/web/.gitignore", "*.gen.ts\n");
+
+        let settings = StingConfig::default().scan;
+        let scanner = Scanner::new(root, &settings, &FILESYSTEM).unwrap();
+        let web = root.join("apps/web");
+        let ignores = IgnoreStack {
+            ignores: scanner.load_ignore_files(&web),
+            parent: None,
+        };
+
+        let reason = scanner
+            .exclusion_reason(&web.join("src/mocks"), true, &ignores)
+            .unwrap();
+        assert_eq!(reason, "exclude pattern '**/mocks/**' (default)");
+
+        let reason = scanner
+            .exclusion_reason(&web.join("src/api.gen.ts"), false, &ignores)
+            .unwrap();
+        assert_eq!(reason, "'*.gen.ts' in apps/web/.gitignore");
+
+        assert!(
+            scanner
+                .exclusion_reason(&web.join("src/app.ts"), false, &ignores)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_parallel_scan_is_deterministic() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for i in 0..20 {
+            for j in 0..5 {
+                write(root, &format!("apps/web/m{}/s{}/file{}.ts", i, j, j), "");
             }
+            write(root, &format!("apps/web/m{}.ts", i), "");
         }
-        false
+
+        let settings = StingConfig::default().scan;
+        let scan_with = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| scan(root, &settings))
+        };
+
+        let sequential = scan_with(1);
+        assert_eq!(sequential.len(), 120);
+        assert_eq!(sequential, scan_with(8));
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_reported() {
+        let mut settings = StingConfig::default().scan;
+        settings.exclude_from_command_line(&["src/[a".to_string()]);
+
+        let err = Scanner::new(Path::new("/repo"), &settings, &FILESYSTEM)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("src/[a"));
+        assert!(err.contains("--exclude"));
     }
 }
@@ -0,0 +1,307 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use serde::Serialize;
+
+use crate::config::Project;
+use crate::entity::{Entity, EntityType};
+
+/// How an entity name matched a query, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MatchKind {
+    Exact,
+    Prefix,
+    Substring,
+    Fuzzy,
+}
+
+impl std::fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchKind::Exact => write!(f, "exact"),
+            MatchKind::Prefix => write!(f, "prefix"),
+            MatchKind::Substring => write!(f, "substring"),
+            MatchKind::Fuzzy => write!(f, "fuzzy"),
+        }
+    }
+}
+
+/// Rank of a name against a query; lower ranks are better matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct NameMatch {
+    pub kind: MatchKind,
+    pub score: usize,
+}
+
+/// Matches a name against a query, case-insensitively except that an
+/// exact case match ranks first. Fuzzy matches are subsequences, like
+/// `usrsvc` for `UserService`, or names within a few typos of the query.
+pub(crate) fn match_name(query: &str, name: &str) -> Option<NameMatch> {
+    if query.is_empty() {
+        return None;
+    }
+    if name == query {
+        return Some(NameMatch {
+            kind: MatchKind::Exact,
+            score: 0,
+        });
+    }
+
+    let query_lower = query.to_lowercase();
+    let name_lower = name.to_lowercase();
+    let extra = name_lower.len().saturating_sub(query_lower.len());
+
+    if name_lower == query_lower {
+        return Some(NameMatch {
+            kind: MatchKind::Exact,
+            score: 1,
+        });
+    }
+    if name_lower.starts_with(&query_lower) {
+        return Some(NameMatch {
+            kind: MatchKind::Prefix,
+            score: extra,
+        });
+    }
+    if let Some(position) = name_lower.find(&query_lower) {
+        return Some(NameMatch {
+            kind: MatchKind::Substring,
+            score: position + extra,
+        });
+    }
+
+    let subsequence = subsequence_gaps(&query_lower, &name_lower);
+    let typos = edit_distance(&query_lower, &name_lower);
+    let max_typos = (query_lower.chars().count() / 4).max(1);
+
+    let score = match (subsequence, typos <= max_typos) {
+        (Some(gaps), true) => gaps.min(typos * 4),
+        (Some(gaps), false) => gaps,
+        (None, true) => typos * 4,
+        (None, false) => return None,
+    };
+
+    Some(NameMatch {
+        kind: MatchKind::Fuzzy,
+        score,
+    })
+}
+
+/// Number of skipped characters when matching `query` as a subsequence
+/// of `name`, or None if it is not one.
+fn subsequence_gaps(query: &str, name: &str) -> Option<usize> {
+    let mut gaps = 0;
+    let mut name_chars = name.chars();
+
+    for q in query.chars() {
+        loop {
+            let c = name_chars.next()?;
+            if c == q {
+                break;
+            }
+            gaps += 1;
+        }
+    }
+
+    Some(gaps)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Restricts entities by type, project and file glob.
+pub(crate) struct EntityFilter<'a> {
+    root_path: &'a Path,
+    entity_types: &'a [String],
+    project: Option<&'a Project>,
+    file: Option<GlobMatcher>,
+}
+
+impl<'a> EntityFilter<'a> {
+    /// `file` is a glob matched against paths relative to the root.
+    pub fn new(
+        root_path: &'a Path,
+        entity_types: &'a [String],
+        project: Option<&'a Project>,
+        file: Option<&str>,
+    ) -> Result<Self> {
+        let file = file
+            .map(|pattern| {
+                Glob::new(pattern)
+                    .map(|glob| glob.compile_matcher())
+                    .with_context(|| format!("Invalid file pattern '{}'", pattern))
+            })
+            .transpose()?;
+
+        Ok(EntityFilter {
+            root_path,
+            entity_types,
+            project,
+            file,
+        })
+    }
+
+    /// Whether an entity passes all filters. Unresolved imports (unknown
+    /// entities) never do.
+    pub fn matches(&self, entity: &Entity) -> bool {
+        if matches!(entity.entity_type, EntityType::Unknown) {
+            return false;
+        }
+
+        if !self.entity_types.is_empty()
+            && !self.entity_types.contains(&entity.entity_type.to_string())
+        {
+            return false;
+        }
+
+        if let Some(project) = self.project
+            && !project.contains(self.root_path, &entity.file_path)
+        {
+            return false;
+        }
+
+        if let Some(file) = &self.file {
+            let relative = Path::new(&entity.file_path)
+                .strip_prefix(self.root_path)
+                .unwrap_or(Path::new(&entity.file_path));
+            if !file.is_match(relative) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Entities matching a query by name, or exactly by id, best match first.
+pub(crate) fn search<'e>(
+    entities: impl IntoIterator<Item = &'e Entity>,
+    query: &str,
+    filter: &EntityFilter,
+) -> Vec<(&'e Entity, NameMatch)> {
+    let mut matches: Vec<(&Entity, NameMatch)> = entities
+        .into_iter()
+        .filter(|entity| filter.matches(entity))
+        .filter_map(|entity| {
+            if entity.id == query {
+                return Some((
+                    entity,
+                    NameMatch {
+                        kind: MatchKind::Exact,
+                        score: 0,
+                    },
+                ));
+            }
+            match_name(query, &entity.name).map(|m| (entity, m))
+        })
+        .collect();
+
+    matches.sort_by(|(a, a_match), (b, b_match)| {
+        a_match
+            .cmp(b_match)
+            .then(a.name.len().cmp(&b.name.len()))
+            .then(a.name.cmp(&b.name))
+            .then(a.file_path.cmp(&b.file_path))
+    });
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn kind(query: &str, name: &str) -> Option<MatchKind> {
+        match_name(query, name).map(|m| m.kind)
+    }
+
+    fn entity(name: &str, entity_type: EntityType, file: &str) -> Entity {
+        Entity::new(
+            name.to_string(),
+            entity_type,
+            file.to_string(),
+            Arc::new(Vec::new()),
+        )
+    }
+
+    #[test]
+    fn test_match_kinds() {
+        assert_eq!(kind("UserService", "UserService"), Some(MatchKind::Exact));
+        assert_eq!(kind("userservice", "UserService"), Some(MatchKind::Exact));
+        assert_eq!(kind("User", "UserService"), Some(MatchKind::Prefix));
+        assert_eq!(kind("service", "UserService"), Some(MatchKind::Substring));
+        assert_eq!(kind("usrsvc", "UserService"), Some(MatchKind::Fuzzy));
+        assert_eq!(kind("UserSevrice", "UserService"), Some(MatchKind::Fuzzy));
+        assert_eq!(kind("Dashboard", "UserService"), None);
+        assert_eq!(kind("", "UserService"), None);
+    }
+
+    #[test]
+    fn test_exact_case_ranks_before_case_insensitive() {
+        let exact = match_name("Api", "Api").unwrap();
+        let insensitive = match_name("Api", "API").unwrap();
+        let prefix = match_name("Api", "ApiClient").unwrap();
+
+        assert!(exact < insensitive);
+        assert!(insensitive < prefix);
+    }
+
+    #[test]
+    fn test_search_ranks_and_filters() {
+        let root = Path::new("/repo");
+        let entities = [
+            entity(
+                "UserListComponent",
+                EntityType::Component,
+                "/repo/apps/web/list.ts",
+            ),
+            entity(
+                "UserService",
+                EntityType::Service,
+                "/repo/libs/user.service.ts",
+            ),
+            entity("CurrentUser", EntityType::Interface, "/repo/libs/user.ts"),
+            entity("User", EntityType::Unknown, "/repo/node_modules/user.ts"),
+        ];
+
+        let filter = EntityFilter::new(root, &[], None, None).unwrap();
+        let names: Vec<&str> = search(&entities, "user", &filter)
+            .into_iter()
+            .map(|(e, _)| e.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["UserService", "UserListComponent", "CurrentUser"]
+        );
+
+        let types = vec!["service".to_string()];
+        let filter = EntityFilter::new(root, &types, None, None).unwrap();
+        assert_eq!(search(&entities, "user", &filter).len(), 1);
+
+        let filter = EntityFilter::new(root, &[], None, Some("apps/**")).unwrap();
+        let matches = search(&entities, "user", &filter);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0.name, "UserListComponent");
+
+        let filter = EntityFilter::new(root, &[], None, None).unwrap();
+        let by_id = search(&entities, &entities[2].id, &filter);
+        assert_eq!(by_id[0].0.name, "CurrentUser");
+    }
+}
@@ -0,0 +1,387 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+
+/// The filesystem source, for tests of readers that take a source.
+#[cfg(test)]
+pub(crate) static FILESYSTEM: FileSource = FileSource::Filesystem;
+
+/// Where the files of a workspace are read from.
+///
+/// Paths are always absolute paths in the working directory. A git tree
+/// source serves the content those files have at a revision instead, so
+/// entity ids, project filters and changed file lists line up with a
+/// filesystem scan.
+pub(crate) enum FileSource {
+    Filesystem,
+    GitTree(GitTree),
+}
+
+/// A file or directory inside a directory of a file source.
+pub(crate) struct DirEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub is_file: bool,
+}
+
+thread_local! {
+    /// Repository handles of the current thread, by git directory. A handle
+    /// is not Sync, so each thread reading blobs opens its own and files
+    /// parsed in parallel are read in parallel.
+    static REPOSITORIES: RefCell<HashMap<PathBuf, Repository>> = RefCell::new(HashMap::new());
+}
+
+/// Files and directories of a git tree, indexed by working directory path.
+pub(crate) struct GitTree {
+    /// Resolved revision, for messages
+    revision: String,
+    /// Git directory blobs are read from on demand
+    git_dir: PathBuf,
+    files: HashMap<PathBuf, Oid>,
+    dirs: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl FileSource {
+    /// Serves the tree of the commit `rev` resolves to, in the repository
+    /// at or above `repo_path`.
+    pub fn git_tree(repo_path: &Path, rev: &str) -> Result<Self> {
+        let (repo, workdir) = open_repository(repo_path)?;
+
+        let commit = repo
+            .revparse_single(rev)
+            .with_context(|| {
+                format!(
+                    "Could not resolve git reference '{}'. Ensure it exists.",
+                    rev
+                )
+            })?
+            .peel_to_commit()
+            .with_context(|| format!("Reference '{}' does not point to a commit", rev))?;
+        let revision = format!("{} ({})", rev, &commit.id().to_string()[..10]);
+        let tree = commit.tree().context("Failed to get tree from commit")?;
+
+        let mut files = HashMap::new();
+        let mut dirs: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        dirs.insert(workdir.clone(), Vec::new());
+
+        tree.walk(TreeWalkMode::PreOrder, |parent, entry| {
+            let Some(name) = entry.name() else {
+                return TreeWalkResult::Skip;
+            };
+            let dir = workdir.join(parent);
+            let path = dir.join(name);
+
+            match entry.kind() {
+                Some(ObjectType::Tree) => {
+                    dirs.insert(path.clone(), Vec::new());
+                }
+                // Symlinks point outside of the tree or to other entries
+                Some(ObjectType::Blob) if entry.filemode() != 0o120000 => {
+                    files.insert(path.clone(), entry.id());
+                }
+                _ => return TreeWalkResult::Skip,
+            }
+
+            dirs.entry(dir).or_default().push(path);
+            TreeWalkResult::Ok
+        })
+        .context("Failed to read the git tree")?;
+
+        Ok(FileSource::GitTree(GitTree {
+            revision,
+            git_dir: repo.path().to_path_buf(),
+            files,
+            dirs,
+        }))
+    }
+
+    /// Serves the content staged in the index of the repository at or
+    /// above `repo_path`.
+    pub fn git_index(repo_path: &Path) -> Result<Self> {
+        let (repo, workdir) = open_repository(repo_path)?;
+        let index = repo.index().context("Failed to read the git index")?;
+
+        let mut files = HashMap::new();
+        let mut dirs: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        dirs.insert(workdir.clone(), Vec::new());
+
+        for entry in index.iter() {
+            // Only regular files, at stage 0 when merge conflicts are staged
+            let stage = (entry.flags >> 12) & 0x3;
+            if entry.mode & 0o170000 != 0o100000 || stage != 0 {
+                continue;
+            }
+            let Ok(relative) = std::str::from_utf8(&entry.path) else {
+                continue;
+            };
+            let path = workdir.join(relative);
+            files.insert(path.clone(), entry.id);
+
+            let mut child = path;
+            while let Some(dir) = child.parent().map(Path::to_path_buf) {
+                let known = dirs.contains_key(&dir);
+                dirs.entry(dir.clone()).or_default().push(child);
+                if known {
+                    break;
+                }
+                child = dir;
+            }
+        }
+
+        Ok(FileSource::GitTree(GitTree {
+            revision: "the index".to_string(),
+            git_dir: repo.path().to_path_buf(),
+            files,
+            dirs,
+        }))
+    }
+
+    pub fn is_filesystem(&self) -> bool {
+        matches!(self, FileSource::Filesystem)
+    }
+
+    /// The revision files are read at, or None for the filesystem.
+    pub fn revision(&self) -> Option<&str> {
+        match self {
+            FileSource::Filesystem => None,
+            FileSource::GitTree(tree) => Some(&tree.revision),
+        }
+    }
+
+    pub fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self {
+            FileSource::Filesystem => std::fs::read_to_string(path),
+            FileSource::GitTree(tree) => tree.read_to_string(path),
+        }
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        match self {
+            FileSource::Filesystem => path.is_file(),
+            FileSource::GitTree(tree) => tree.files.contains_key(&normalize(path)),
+        }
+    }
+
+    pub fn is_dir(&self, path: &Path) -> bool {
+        match self {
+            FileSource::Filesystem => path.is_dir(),
+            FileSource::GitTree(tree) => tree.dirs.contains_key(&normalize(path)),
+        }
+    }
+
+    pub fn exists(&self, path: &Path) -> bool {
+        self.is_file(path) || self.is_dir(path)
+    }
+
+    /// Entries of a directory, sorted by name.
+    pub fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let mut entries = match self {
+            FileSource::Filesystem => std::fs::read_dir(path)?
+                .map(|entry| {
+                    let path = entry?.path();
+                    Ok(DirEntry {
+                        is_dir: path.is_dir(),
+                        is_file: path.is_file(),
+                        path,
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?,
+            FileSource::GitTree(tree) => tree
+                .dirs
+                .get(&normalize(path))
+                .ok_or_else(|| not_found(path))?
+                .iter()
+                .map(|path| DirEntry {
+                    path: path.clone(),
+                    is_dir: tree.dirs.contains_key(path),
+                    is_file: tree.files.contains_key(path),
+                })
+                .collect(),
+        };
+
+        entries.sort_by(|a, b| a.path.file_name().cmp(&b.path.file_name()));
+        Ok(entries)
+    }
+
+    /// Absolute path without `.` and `..` components. Symlinks are only
+    /// resolved on the filesystem; a git tree has none.
+    pub fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        match self {
+            FileSource::Filesystem => path.canonicalize(),
+            FileSource::GitTree(_) if self.exists(path) => Ok(normalize(path)),
+            FileSource::GitTree(_) => Err(not_found(path)),
+        }
+    }
+}
+
+impl GitTree {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let oid = self
+            .files
+            .get(&normalize(path))
+            .ok_or_else(|| not_found(path))?;
+
+        REPOSITORIES.with(|repositories| {
+            let mut repositories = repositories.borrow_mut();
+            let repo = match repositories.entry(self.git_dir.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(Repository::open(&self.git_dir).map_err(io::Error::other)?)
+                }
+            };
+            let blob = repo.find_blob(*oid).map_err(io::Error::other)?;
+            String::from_utf8(blob.content().to_vec())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+    }
+}
+
+/// The repository at or above `repo_path`, with its canonical working
+/// directory.
+fn open_repository(repo_path: &Path) -> Result<(Repository, PathBuf)> {
+    let repo = Repository::discover(repo_path).with_context(|| {
+        format!(
+            "Failed to find git repository at or above '{}'",
+            repo_path.display()
+        )
+    })?;
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow::anyhow!("Repository has no working directory (bare repository)"))?
+        .canonicalize()?;
+    Ok((repo, workdir))
+}
+
+/// Resolves `.` and `..` components without touching the filesystem.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist in the git tree", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use rayon::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_git_tree_serves_committed_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        fs::create_dir_all(root.join("src/lib")).unwrap();
+        fs::write(root.join("src/lib/a.ts"), "export class A {}").unwrap();
+        fs::write(root.join("src/b.ts"), "export class B {}").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@test.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])
+            .unwrap();
+
+        // Working directory changes are not visible
+        fs::write(root.join("src/lib/a.ts"), "export class Changed {}").unwrap();
+        fs::write(root.join("src/untracked.ts"), "").unwrap();
+
+        let source = FileSource::git_tree(&root, "HEAD").unwrap();
+
+        assert_eq!(
+            source.read_to_string(&root.join("src/lib/a.ts")).unwrap(),
+            "export class A {}"
+        );
+        assert!(source.is_file(&root.join("src/lib/../b.ts")));
+        assert!(!source.is_file(&root.join("src/untracked.ts")));
+        assert!(source.is_dir(&root.join("src/lib")));
+        assert!(
+            source
+                .read_to_string(&root.join("src/untracked.ts"))
+                .is_err()
+        );
+
+        // Every thread reads through its own repository handle
+        let contents: Vec<String> = (0..16)
+            .into_par_iter()
+            .map(|_| source.read_to_string(&root.join("src/b.ts")).unwrap())
+            .collect();
+        assert!(contents.iter().all(|c| c == "export class B {}"));
+
+        let names: Vec<String> = source
+            .read_dir(&root.join("src"))
+            .unwrap()
+            .iter()
+            .map(|e| format!("{} {}", e.path.display(), e.is_dir))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                format!("{} false", root.join("src/b.ts").display()),
+                format!("{} true", root.join("src/lib").display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_index_serves_staged_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        fs::create_dir_all(root.join("src/lib")).unwrap();
+        fs::write(root.join("src/lib/a.ts"), "export class Staged {}").unwrap();
+        fs::write(root.join("src/b.ts"), "export class B {}").unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+
+        // Unstaged edits and untracked files are not visible
+        fs::write(root.join("src/lib/a.ts"), "export class Unstaged {}").unwrap();
+        fs::write(root.join("src/untracked.ts"), "").unwrap();
+
+        let source = FileSource::git_index(&root).unwrap();
+
+        assert_eq!(
+            source.read_to_string(&root.join("src/lib/a.ts")).unwrap(),
+            "export class Staged {}"
+        );
+        assert!(!source.is_file(&root.join("src/untracked.ts")));
+        assert!(source.is_dir(&root.join("src/lib")));
+        assert_eq!(source.revision(), Some("the index"));
+
+        let names: Vec<PathBuf> = source
+            .read_dir(&root)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(names, vec![root.join("src")]);
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::parser::strip_comments;
+use crate::source::FileSource;
+
+/// Config files looked up at the project root, in order of preference.
+const ROOT_TSCONFIG_FILES: &[&str] = &["tsconfig.base.json", "tsconfig.json"];
+
+/// Maximum depth of `extends` chains, guards against cyclic configs.
+const MAX_EXTENDS_DEPTH: usize = 16;
+
+/// Module resolution settings (`baseUrl` and `paths`) of a tsconfig file,
+/// with `extends` chains already applied.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TsConfigPaths {
+    base_url: Option<PathBuf>,
+    paths_base: PathBuf,
+    paths: Vec<(String, Vec<String>)>,
+}
+
+/// Candidate locations for a non-relative import specifier.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct PathCandidates {
+    /// Targets of the matching `paths` entry, in fallback order.
+    pub mapped: Vec<PathBuf>,
+    /// `baseUrl`-relative location, only used when the file exists.
+    pub base_url: Option<PathBuf>,
+}
+
+impl TsConfigPaths {
+    /// Loads the project tsconfig from the root directory, preferring
+    /// `tsconfig.base.json` (NX workspaces) over `tsconfig.json`.
+    pub fn load(root_path: &Path, source: &FileSource) -> Option<Self> {
+        for file_name in ROOT_TSCONFIG_FILES {
+            let config_path = root_path.join(file_name);
+            if !source.is_file(&config_path) {
+                continue;
+            }
+
+            match Self::from_file(&config_path, source) {
+                Ok(config) => return Some(config),
+                Err(e) => eprintln!("Warning: Could not load {}: {:#}", config_path.display(), e),
+            }
+        }
+
+        None
+    }
+
+    pub fn from_file(config_path: &Path, source: &FileSource) -> Result<Self> {
+        let mut config = TsConfigPaths {
+            paths_base: config_path.parent().unwrap_or(Path::new("")).to_path_buf(),
+            ..Default::default()
+        };
+        let mut has_paths = false;
+        let mut visited = HashSet::new();
+
+        config.apply_file(source, config_path, &mut has_paths, &mut visited, 0)?;
+
+        if let Some(base_url) = &config.base_url {
+            config.paths_base = base_url.clone();
+        }
+
+        Ok(config)
+    }
+
+    /// Applies a config file and the files it extends. Settings of the
+    /// extending file win, so it is applied before its parents, which only
+    /// fill values that are still unset.
+    fn apply_file(
+        &mut self,
+        source: &FileSource,
+        config_path: &Path,
+        has_paths: &mut bool,
+        visited: &mut HashSet<PathBuf>,
+        depth: usize,
+    ) -> Result<()> {
+        if depth > MAX_EXTENDS_DEPTH {
+            anyhow::bail!("tsconfig extends chain is too deep");
+        }
+
+        let canonical = source
+            .canonicalize(config_path)
+            .unwrap_or_else(|_| config_path.to_path_buf());
+        if !visited.insert(canonical) {
+            return Ok(());
+        }
+
+        let content = source
+            .read_to_string(config_path)
+            .with_context(|| format!("Unable to read {}", config_path.display()))?;
+        let json = parse_jsonc(&content)
+            .with_context(|| format!("Invalid JSON in {}", config_path.display()))?;
+
+        let config_dir = config_path.parent().unwrap_or(Path::new(""));
+        let compiler_options = json.get("compilerOptions");
+
+        if self.base_url.is_none()
+            && let Some(base_url) = compiler_options
+                .and_then(|o| o.get("baseUrl"))
+                .and_then(Value::as_str)
+        {
+            self.base_url = Some(config_dir.join(base_url));
+        }
+
+        if !*has_paths
+            && let Some(paths) = compiler_options
+                .and_then(|o| o.get("paths"))
+                .and_then(Value::as_object)
+        {
+            *has_paths = true;
+            // Without baseUrl, targets are relative to the defining config
+            self.paths_base = config_dir.to_path_buf();
+            self.paths = paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .as_array()
+                        .map(|arr| {
+                            arr.iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    (pattern.clone(), targets)
+                })
+                .collect();
+        }
+
+        let parents: Vec<String> = match json.get("extends") {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Array(arr)) => arr
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Later entries of an `extends` array override earlier ones
+        for parent in parents.iter().rev() {
+            match resolve_extends(source, config_dir, parent) {
+                Some(parent_path) => {
+                    self.apply_file(source, &parent_path, has_paths, visited, depth + 1)?
+                }
+                None => eprintln!(
+                    "Warning: Could not resolve tsconfig extends '{}' from {}",
+                    parent,
+                    config_path.display()
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds alias patterns with targets relative to `base`. They replace
+    /// tsconfig entries with the same pattern.
+    pub fn add_aliases(&mut self, base: &Path, aliases: &[(String, Vec<String>)]) {
+        for (pattern, targets) in aliases {
+            let targets = targets
+                .iter()
+                .map(|target| base.join(target).to_string_lossy().to_string())
+                .collect();
+            self.paths.retain(|(p, _)| p != pattern);
+            self.paths.push((pattern.clone(), targets));
+        }
+    }
+
+    /// Returns the locations `tsc` would try for a non-relative specifier.
+    pub fn candidates(&self, specifier: &str) -> PathCandidates {
+        let mut candidates = PathCandidates::default();
+
+        if let Some((pattern, matched)) = self.best_match(specifier)
+            && let Some((_, targets)) = self.paths.iter().find(|(p, _)| p == pattern)
+        {
+            candidates.mapped = targets
+                .iter()
+                .map(|target| self.paths_base.join(target.replacen('*', matched, 1)))
+                .collect();
+        }
+
+        if let Some(base_url) = &self.base_url {
+            candidates.base_url = Some(base_url.join(specifier));
+        }
+
+        candidates
+    }
+
+    /// Finds the `paths` pattern matching the specifier. Exact patterns win,
+    /// otherwise the wildcard pattern with the longest prefix is chosen.
+    /// Returns the pattern and the text captured by its `*`.
+    fn best_match<'s>(&self, specifier: &'s str) -> Option<(&str, &'s str)> {
+        if let Some((pattern, _)) = self.paths.iter().find(|(p, _)| p == specifier) {
+            return Some((pattern.as_str(), ""));
+        }
+
+        let mut best: Option<(&str, &'s str, usize)> = None;
+
+        for (pattern, _) in &self.paths {
+            let Some((prefix, suffix)) = pattern.split_once('*') else {
+                continue;
+            };
+
+            if specifier.len() < prefix.len() + suffix.len()
+                || !specifier.starts_with(prefix)
+                || !specifier.ends_with(suffix)
+            {
+                continue;
+            }
+
+            if best.is_none_or(|(_, _, len)| prefix.len() > len) {
+                let matched = &specifier[prefix.len()..specifier.len() - suffix.len()];
+                best = Some((pattern.as_str(), matched, prefix.len()));
+            }
+        }
+
+        best.map(|(pattern, matched, _)| (pattern, matched))
+    }
+}
+
+/// Resolves an `extends` value either relative to the config directory or
+/// as a package inside `node_modules`.
+fn resolve_extends(source: &FileSource, config_dir: &Path, specifier: &str) -> Option<PathBuf> {
+    let with_json = |path: PathBuf| -> Option<PathBuf> {
+        if source.is_file(&path) {
+            return Some(path);
+        }
+        let json_path = PathBuf::from(format!("{}.json", path.to_string_lossy()));
+        if source.is_file(&json_path) {
+            return Some(json_path);
+        }
+        let nested = path.join("tsconfig.json");
+        source.is_file(&nested).then_some(nested)
+    };
+
+    if specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || Path::new(specifier).is_absolute()
+    {
+        return with_json(config_dir.join(specifier));
+    }
+
+    config_dir
+        .ancestors()
+        .find_map(|dir| with_json(dir.join("node_modules").join(specifier)))
+}
+
+/// Parses tsconfig-flavoured JSON, which allows comments and trailing commas.
+fn parse_jsonc(content: &str) -> Result<Value> {
+    let without_comments = strip_comments(content);
+    let cleaned = strip_trailing_commas(&without_comments);
+    Ok(serde_json::from_str(&cleaned)?)
+}
+
+fn strip_trailing_commas(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut result = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if in_string {
+            result.push(c);
+            if c == '\\' {
+                if let Some(&next) = chars.get(i + 1) {
+                    result.push(next);
+                    i += 1;
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            result.push(c);
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|ch| !ch.is_whitespace());
+            if !matches!(next, Some('}') | Some(']')) {
+                result.push(c);
+            }
+        } else {
+            result.push(c);
+        }
+
+        i += 1;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write(path: &Path, content: &str) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_jsonc_with_comments_and_trailing_commas() {
+        let content = r#"{
+  // line comment
+  "compilerOptions": {
+    /* block comment */
+    "baseUrl": ".",
+    "paths": { "@org/*": ["libs/*",], },
+  },
+}"#;
+        let json = parse_jsonc(content).unwrap();
+        assert_eq!(json["compilerOptions"]["baseUrl"], ".");
+    }
+
+    #[test]
+    fn test_strip_trailing_commas_keeps_commas_in_strings() {
+        let result = strip_trailing_commas(r#"{"a": ",}", }"#);
+        assert_eq!(result, r#"{"a": ",}" }"#);
+    }
+
+    #[test]
+    fn test_wildcard_mapping_uses_base_url() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("tsconfig.base.json"),
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "@org/feature-*": ["libs/feature-*/src/index.ts"] } } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+        let candidates = config.candidates("@org/feature-users");
+
+        assert_eq!(
+            candidates.mapped,
+            vec![temp.path().join("libs/feature-users/src/index.ts")]
+        );
+    }
+
+    #[test]
+    fn test_exact_mapping_wins_over_wildcard() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": {
+                "@org/*": ["libs/*"],
+                "@org/core": ["libs/core/src/index.ts"]
+            } } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+
+        assert_eq!(
+            config.candidates("@org/core").mapped,
+            vec![temp.path().join("libs/core/src/index.ts")]
+        );
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": {
+                "@org/*": ["libs/*"],
+                "@org/ui/*": ["libs/ui/src/*"]
+            } } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+
+        assert_eq!(
+            config.candidates("@org/ui/button").mapped,
+            vec![temp.path().join("libs/ui/src/button")]
+        );
+    }
+
+    #[test]
+    fn test_multiple_fallback_targets_keep_order() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": ".", "paths": { "*": ["generated/*", "src/*"] } } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+
+        assert_eq!(
+            config.candidates("models/user").mapped,
+            vec![
+                temp.path().join("generated/models/user"),
+                temp.path().join("src/models/user"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extends_chain_inherits_paths_relative_to_parent() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("config/tsconfig.paths.json"),
+            r#"{ "compilerOptions": { "baseUrl": "..", "paths": { "@org/*": ["libs/*"] } } }"#,
+        );
+        write(
+            &temp.path().join("tsconfig.json"),
+            r#"{ "extends": "./config/tsconfig.paths", "compilerOptions": { "strict": true } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+
+        assert_eq!(
+            config.candidates("@org/shared").mapped,
+            vec![temp.path().join("config/../libs/shared")]
+        );
+    }
+
+    #[test]
+    fn test_child_paths_override_parent_paths() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("tsconfig.parent.json"),
+            r#"{ "compilerOptions": { "paths": { "@org/*": ["parent/*"] } } }"#,
+        );
+        write(
+            &temp.path().join("tsconfig.json"),
+            r#"{ "extends": "./tsconfig.parent.json", "compilerOptions": { "paths": { "@org/*": ["child/*"] } } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+
+        assert_eq!(
+            config.candidates("@org/x").mapped,
+            vec![temp.path().join("child/x")]
+        );
+    }
+
+    #[test]
+    fn test_paths_without_base_url_are_relative_to_config() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "paths": { "@org/*": ["./libs/*"] } } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+        let candidates = config.candidates("@org/x");
+
+        assert_eq!(candidates.mapped, vec![temp.path().join("./libs/x")]);
+        assert_eq!(candidates.base_url, None);
+    }
+
+    #[test]
+    fn test_unmatched_specifier_only_has_base_url_candidate() {
+        let temp = tempdir().unwrap();
+        write(
+            &temp.path().join("tsconfig.json"),
+            r#"{ "compilerOptions": { "baseUrl": "src", "paths": { "@org/*": ["libs/*"] } } }"#,
+        );
+
+        let config = TsConfigPaths::load(temp.path(), &FileSource::Filesystem).unwrap();
+        let candidates = config.candidates("rxjs");
+
+        assert!(candidates.mapped.is_empty());
+        assert_eq!(candidates.base_url, Some(temp.path().join("src/rxjs")));
+    }
+
+    #[test]
+    fn test_load_returns_none_without_tsconfig() {
+        let temp = tempdir().unwrap();
+        assert!(TsConfigPaths::load(temp.path(), &FileSource::Filesystem).is_none());
+    }
+}